@@ -0,0 +1,93 @@
+use crate::{
+    ray::Ray,
+    vec3::{Point3, Vec3},
+};
+
+// Grows the far slab distance so that rounding errors don't make rays
+// grazing a box miss it.
+const ROUNDING_ERROR_FACTOR: f32 = 1.0 + 4.0 * f32::EPSILON;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Aabb {
+    pub min: Point3,
+    pub max: Point3,
+}
+
+impl Aabb {
+    pub const fn new(min: Point3, max: Point3) -> Self {
+        Self { min, max }
+    }
+
+    pub fn from_points(points: &[Point3]) -> Self {
+        points
+            .iter()
+            .skip(1)
+            .fold(Self::new(points[0], points[0]), |aabb, &point| {
+                Self::new(aabb.min.min(point), aabb.max.max(point))
+            })
+    }
+
+    pub fn surrounding(&self, other: &Self) -> Self {
+        Self::new(self.min.min(other.min), self.max.max(other.max))
+    }
+
+    pub fn padded(&self, padding: f32) -> Self {
+        let delta = Vec3::new(padding, padding, padding);
+
+        Self::new(self.min - delta, self.max + delta)
+    }
+
+    pub fn centroid(&self) -> Point3 {
+        (self.min + self.max) / 2.0
+    }
+
+    pub fn diagonal(&self) -> Vec3 {
+        self.max - self.min
+    }
+
+    pub fn surface_area(&self) -> f32 {
+        let d = self.diagonal();
+
+        2.0 * (d.x * d.y + d.y * d.z + d.z * d.x)
+    }
+
+    pub fn longest_axis(&self) -> usize {
+        let d = self.diagonal();
+
+        if d.x > d.y && d.x > d.z {
+            0
+        } else if d.y > d.z {
+            1
+        } else {
+            2
+        }
+    }
+
+    pub fn hit(&self, ray: &Ray, inverse_direction: Vec3, max_t: f32) -> bool {
+        let mut t_min = 0.0;
+        let mut t_max = max_t;
+
+        for axis in 0..3 {
+            let t0 = (self.min[axis] - ray.origin[axis]) * inverse_direction[axis];
+            let t1 = (self.max[axis] - ray.origin[axis]) * inverse_direction[axis];
+
+            let (t_near, t_far) = if t0 < t1 { (t0, t1) } else { (t1, t0) };
+            let t_far = t_far * ROUNDING_ERROR_FACTOR;
+
+            // Written so that NaN slabs (ray parallel to and on a slab plane)
+            // don't reject the box.
+            if t_near > t_min {
+                t_min = t_near;
+            }
+            if t_far < t_max {
+                t_max = t_far;
+            }
+
+            if t_min > t_max {
+                return false;
+            }
+        }
+
+        true
+    }
+}
@@ -0,0 +1,27 @@
+use crate::{ray::Ray, rgb_color::RgbColor, vec3::Vec3};
+
+/// Radiance arriving from infinitely far away, seen by rays that don't hit
+/// any object.
+pub trait Background: Send + Sync {
+    fn radiance(&self, ray: &Ray) -> RgbColor;
+
+    /// Whether the background is bright enough to be sampled as a light.
+    fn is_sampled(&self) -> bool {
+        false
+    }
+
+    /// Probability density, in solid angle, of sampling the direction.
+    fn direction_probability(&self, _direction: Vec3) -> f32 {
+        0.0
+    }
+
+    fn random_direction(&self, u: (f32, f32)) -> Vec3 {
+        Vec3::random_unit(u)
+    }
+}
+
+impl<F: Fn(&Ray) -> RgbColor + Send + Sync> Background for F {
+    fn radiance(&self, ray: &Ray) -> RgbColor {
+        self(ray)
+    }
+}
@@ -0,0 +1,313 @@
+use std::f32::consts::PI;
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::Path;
+
+use image::{
+    codecs::hdr::HdrDecoder,
+    error::{DecodingError, ImageFormatHint},
+    ImageError,
+};
+
+use crate::{
+    backgrounds::Background,
+    color_space::TransferFunction,
+    distribution::Distribution2d,
+    ray::Ray,
+    rgb_color::{RgbColor, BLACK},
+    vec3::Vec3,
+};
+
+/// Equirectangular map of the radiance arriving from every direction, with
+/// up along the y axis and the center of the image towards -z. Directions
+/// are sampled proportionally to the luminance of the map.
+pub struct EnvironmentMap {
+    pixels: Vec<RgbColor>,
+    width: u32,
+    height: u32,
+    distribution: Distribution2d,
+    /// Rotation around the y axis, in radians.
+    rotation: f32,
+    pub intensity: f32,
+}
+
+impl EnvironmentMap {
+    /// Takes the pixels row by row, from the top down. Maps without pixels
+    /// are black, and aren't sampled.
+    ///
+    /// Panics if there aren't exactly `width * height` pixels.
+    pub fn new(pixels: Vec<RgbColor>, width: u32, height: u32) -> Self {
+        assert_eq!(
+            pixels.len(),
+            width as usize * height as usize,
+            "environment map of {}x{} pixels",
+            width,
+            height
+        );
+
+        // Rows near the poles cover a smaller solid angle than the ones
+        // near the horizon.
+        let function: Vec<_> = pixels
+            .iter()
+            .enumerate()
+            .map(|(index, color)| {
+                let row = index as u32 / width;
+                let sin_theta = f32::sin(PI * (row as f32 + 0.5) / height as f32);
+
+                color.luminance() * sin_theta
+            })
+            .collect();
+
+        let distribution = Distribution2d::new(&function, width as usize, height as usize);
+
+        Self {
+            pixels,
+            width,
+            height,
+            distribution,
+            rotation: 0.0,
+            intensity: 1.0,
+        }
+    }
+
+    /// Opens Radiance `.hdr` and `.pfm` images with linear colors, or any
+    /// other image with sRGB colors.
+    pub fn open<P: AsRef<Path>>(path: P) -> image::ImageResult<Self> {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| extension.to_ascii_lowercase());
+
+        match extension.as_deref() {
+            Some("hdr") => {
+                let decoder = HdrDecoder::new(BufReader::new(File::open(path)?))?;
+                let metadata = decoder.metadata();
+
+                let pixels = decoder
+                    .read_image_hdr()?
+                    .iter()
+                    .map(|pixel| RgbColor::new(pixel[0], pixel[1], pixel[2]))
+                    .collect();
+
+                Ok(Self::new(pixels, metadata.width, metadata.height))
+            }
+            Some("pfm") => {
+                let (pixels, width, height) = read_pfm(&fs::read(path)?)
+                    .ok_or_else(|| decoding_error("invalid PFM image"))?;
+
+                Ok(Self::new(pixels, width, height))
+            }
+            _ => {
+                let image = image::open(path)?.to_rgb16();
+
+                let pixels = image
+                    .pixels()
+                    .map(|pixel| {
+                        TransferFunction::Srgb.decode_color(RgbColor::new(
+                            pixel[0] as f32 / u16::MAX as f32,
+                            pixel[1] as f32 / u16::MAX as f32,
+                            pixel[2] as f32 / u16::MAX as f32,
+                        ))
+                    })
+                    .collect();
+
+                Ok(Self::new(pixels, image.width(), image.height()))
+            }
+        }
+    }
+
+    /// Rotates the map around the y axis by the given angle, in degrees.
+    pub fn with_rotation(self, rotation: f32) -> Self {
+        Self {
+            rotation: rotation.to_radians(),
+            ..self
+        }
+    }
+
+    pub fn with_intensity(self, intensity: f32) -> Self {
+        Self { intensity, ..self }
+    }
+
+    /// Coordinates of the direction in the image, between 0 and 1 and
+    /// growing rightwards and downwards.
+    fn image_coordinates(&self, direction: Vec3) -> (f32, f32) {
+        let direction = rotate_around_y(direction.unit(), -self.rotation);
+
+        let phi = f32::atan2(direction.x, -direction.z);
+        let theta = f32::acos(direction.y.clamp(-1.0, 1.0));
+
+        ((0.5 + phi / (2.0 * PI)).rem_euclid(1.0), theta / PI)
+    }
+
+    fn direction(&self, (x, y): (f32, f32)) -> Vec3 {
+        let phi = (x - 0.5) * 2.0 * PI;
+        let theta = y * PI;
+
+        let direction = Vec3::new(
+            theta.sin() * phi.sin(),
+            theta.cos(),
+            -theta.sin() * phi.cos(),
+        );
+
+        rotate_around_y(direction, self.rotation)
+    }
+}
+
+impl Background for EnvironmentMap {
+    fn radiance(&self, ray: &Ray) -> RgbColor {
+        if self.pixels.is_empty() {
+            return BLACK;
+        }
+
+        let (x, y) = self.image_coordinates(ray.direction);
+
+        let column = u32::min((x * self.width as f32) as u32, self.width - 1);
+        let row = u32::min((y * self.height as f32) as u32, self.height - 1);
+
+        self.pixels[(row * self.width + column) as usize] * self.intensity
+    }
+
+    fn is_sampled(&self) -> bool {
+        !self.pixels.is_empty()
+    }
+
+    fn direction_probability(&self, direction: Vec3) -> f32 {
+        let (x, y) = self.image_coordinates(direction);
+        let sin_theta = f32::sin(y * PI);
+
+        if sin_theta <= 0.0 {
+            return 0.0;
+        }
+
+        // The image spans 2π radians horizontally and π vertically.
+        self.distribution.pdf((x, y)) / (2.0 * PI * PI * sin_theta)
+    }
+
+    fn random_direction(&self, u: (f32, f32)) -> Vec3 {
+        let (coordinates, _) = self.distribution.sample(u);
+
+        self.direction(coordinates)
+    }
+}
+
+fn rotate_around_y(direction: Vec3, angle: f32) -> Vec3 {
+    let (sin, cos) = angle.sin_cos();
+
+    Vec3::new(
+        direction.x * cos + direction.z * sin,
+        direction.y,
+        -direction.x * sin + direction.z * cos,
+    )
+}
+
+/// Reads a color portable float map, stored from the bottom row up.
+fn read_pfm(bytes: &[u8]) -> Option<(Vec<RgbColor>, u32, u32)> {
+    // The header is three whitespace separated tokens after the magic
+    // number, followed by a single whitespace character.
+    let mut tokens = Vec::new();
+    let mut position = 0;
+
+    while tokens.len() < 4 {
+        while bytes.get(position)?.is_ascii_whitespace() {
+            position += 1;
+        }
+
+        let start = position;
+
+        while !bytes.get(position)?.is_ascii_whitespace() {
+            position += 1;
+        }
+
+        tokens.push(std::str::from_utf8(&bytes[start..position]).ok()?);
+    }
+
+    if tokens[0] != "PF" {
+        return None;
+    }
+
+    let width: u32 = tokens[1].parse().ok()?;
+    let height: u32 = tokens[2].parse().ok()?;
+    let scale: f32 = tokens[3].parse().ok()?;
+
+    if width == 0 || height == 0 {
+        return None;
+    }
+
+    let data = bytes.get(position + 1..)?;
+    let float_count = width as usize * height as usize * 3;
+
+    if data.len() < float_count * 4 {
+        return None;
+    }
+
+    let floats: Vec<_> = data[..float_count * 4]
+        .chunks_exact(4)
+        .map(|chunk| {
+            let bytes = [chunk[0], chunk[1], chunk[2], chunk[3]];
+
+            // A negative scale means little endian values.
+            if scale < 0.0 {
+                f32::from_le_bytes(bytes)
+            } else {
+                f32::from_be_bytes(bytes)
+            }
+        })
+        .collect();
+
+    let pixels = floats
+        .chunks_exact(width as usize * 3)
+        .rev()
+        .flat_map(|row| {
+            row.chunks_exact(3)
+                .map(|rgb| RgbColor::new(rgb[0], rgb[1], rgb[2]))
+        })
+        .collect();
+
+    Some((pixels, width, height))
+}
+
+fn decoding_error(message: &str) -> ImageError {
+    ImageError::Decoding(DecodingError::new(ImageFormatHint::Unknown, message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn treats_maps_without_pixels_as_black() {
+        let ray = Ray::new(Vec3::zero(), Vec3::new(0.0, 1.0, 0.0), 0.0);
+
+        for &(width, height) in [(0, 0), (0, 4), (4, 0)].iter() {
+            let map = EnvironmentMap::new(Vec::new(), width, height);
+
+            assert_eq!(map.radiance(&ray), BLACK);
+            assert!(!map.is_sampled());
+            assert!((map.random_direction((0.3, 0.6)).len() - 1.0).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "environment map of 2x2 pixels")]
+    fn rejects_pixels_that_do_not_fill_the_map() {
+        EnvironmentMap::new(vec![RgbColor::new(1.0, 1.0, 1.0); 3], 2, 2);
+    }
+
+    #[test]
+    fn rejects_pfm_images_without_pixels() {
+        assert!(read_pfm(b"PF\n0 2\n-1.0\n").is_none());
+        assert!(read_pfm(b"PF\n2 0\n-1.0\n").is_none());
+
+        let mut image = b"PF\n1 1\n-1.0\n".to_vec();
+
+        for value in [1.0_f32, 2.0, 3.0].iter() {
+            image.extend_from_slice(&value.to_le_bytes());
+        }
+
+        assert_eq!(
+            read_pfm(&image),
+            Some((vec![RgbColor::new(1.0, 2.0, 3.0)], 1, 1))
+        );
+    }
+}
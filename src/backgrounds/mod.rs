@@ -0,0 +1,5 @@
+mod background;
+mod environment_map;
+mod sky;
+
+pub use self::{background::*, environment_map::*, sky::*};
@@ -0,0 +1,250 @@
+use std::f32::consts::PI;
+
+use crate::{
+    backgrounds::Background,
+    color_space::xyy_to_rgb,
+    lights::{DirectionalLight, CANDELAS_PER_UNIT},
+    ray::Ray,
+    rgb_color::{RgbColor, BLACK},
+    vec3::{dot, Vec3},
+};
+
+/// Angle between the center and the edge of the sun, seen from the ground.
+pub const SUN_ANGULAR_RADIUS: f32 = 0.004_65;
+
+/// Luminance of the sun above the atmosphere, in kcd/m².
+const SUN_LUMINANCE: f32 = 2.0e6;
+
+/// Wavelengths, in micrometers, standing for the red, green and blue
+/// channels when attenuating sunlight.
+const WAVELENGTHS: [f32; 3] = [0.68, 0.55, 0.44];
+
+/// Clear sky model by Preetham, Shirley and Smits. Radiance is in kcd/m²,
+/// so daylight scenes need an exposure of about -6 EV. Directions below the
+/// horizon see the sky at the horizon.
+///
+/// The sun isn't part of the background: it is the directional light given
+/// by [`Sky::sun_light`], which the scene has to sample along with its other
+/// delta lights.
+pub struct Sky {
+    sun_direction: Vec3,
+    turbidity: f32,
+    pub intensity: f32,
+    /// Perez coefficients of the luminance and the x and y chromaticities.
+    coefficients: [[f32; 5]; 3],
+    /// Zenith luminance and chromaticities, divided by the Perez function at
+    /// the zenith so that the model only has to multiply them.
+    zenith: [f32; 3],
+    sun_radiance: RgbColor,
+}
+
+impl Sky {
+    /// Turbidity goes from 2 for a very clear sky to 10 for a hazy one, and
+    /// is clamped to that range.
+    pub fn new(sun_direction: Vec3, turbidity: f32) -> Self {
+        let sun_direction = sun_direction.unit();
+        let turbidity = turbidity.clamp(2.0, 10.0);
+        let t = turbidity;
+
+        let coefficients = [
+            [
+                0.1787 * t - 1.4630,
+                -0.3554 * t + 0.4275,
+                -0.0227 * t + 5.3251,
+                0.1206 * t - 2.5771,
+                -0.0670 * t + 0.3703,
+            ],
+            [
+                -0.0193 * t - 0.2592,
+                -0.0665 * t + 0.0008,
+                -0.0004 * t + 0.2125,
+                -0.0641 * t - 0.8989,
+                -0.0033 * t + 0.0452,
+            ],
+            [
+                -0.0167 * t - 0.2608,
+                -0.0950 * t + 0.0092,
+                -0.0079 * t + 0.2102,
+                -0.0441 * t - 1.6537,
+                -0.0109 * t + 0.0529,
+            ],
+        ];
+
+        // The model was fitted for suns above the horizon, lower ones light
+        // the sky as a sun on the horizon would.
+        let sun_theta = f32::acos(sun_direction.y.clamp(0.0, 1.0));
+
+        let chi = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * sun_theta);
+        let zenith_luminance = (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192;
+
+        let chromaticity = |matrix: [[f32; 4]; 3]| {
+            let theta = [sun_theta.powi(3), sun_theta.powi(2), sun_theta, 1.0];
+            let row = |row: [f32; 4]| (0..4).map(|i| row[i] * theta[i]).sum::<f32>();
+
+            t * t * row(matrix[0]) + t * row(matrix[1]) + row(matrix[2])
+        };
+
+        let zenith_x = chromaticity([
+            [0.00166, -0.00375, 0.00209, 0.0],
+            [-0.02903, 0.06377, -0.03202, 0.00394],
+            [0.11693, -0.21196, 0.06052, 0.25886],
+        ]);
+        let zenith_y = chromaticity([
+            [0.00275, -0.00610, 0.00317, 0.0],
+            [-0.04214, 0.08970, -0.04153, 0.00516],
+            [0.15346, -0.26756, 0.06670, 0.26688],
+        ]);
+
+        let mut zenith = [zenith_luminance, zenith_x, zenith_y];
+
+        for (value, coefficients) in zenith.iter_mut().zip(coefficients.iter()) {
+            *value /= perez(coefficients, 1.0, sun_theta);
+        }
+
+        Self {
+            sun_direction,
+            turbidity,
+            intensity: 1.0,
+            coefficients,
+            zenith,
+            sun_radiance: sun_radiance(sun_direction, turbidity),
+        }
+    }
+
+    pub fn with_intensity(self, intensity: f32) -> Self {
+        Self { intensity, ..self }
+    }
+
+    pub fn sun_direction(&self) -> Vec3 {
+        self.sun_direction
+    }
+
+    pub fn turbidity(&self) -> f32 {
+        self.turbidity
+    }
+
+    /// Sunlight reaching the ground, with the illuminance of a disk of the
+    /// radiance of the sun, or nothing once the sun has set.
+    pub fn sun_light(&self) -> Option<DirectionalLight> {
+        let luminance = self.sun_radiance.luminance();
+
+        if luminance <= 0.0 {
+            return None;
+        }
+
+        let solid_angle = 2.0 * PI * (1.0 - SUN_ANGULAR_RADIUS.cos());
+
+        Some(DirectionalLight::new(
+            -self.sun_direction,
+            self.sun_radiance,
+            luminance * solid_angle * CANDELAS_PER_UNIT * self.intensity,
+        ))
+    }
+
+    fn sky_radiance(&self, direction: Vec3) -> RgbColor {
+        // Keeps the Perez function away from its singularity at the
+        // horizon.
+        let cos_theta = f32::max(direction.y, 0.001);
+        let gamma = f32::acos(dot(direction, self.sun_direction).clamp(-1.0, 1.0));
+
+        let [luminance, x, y] = [0, 1, 2]
+            .map(|index| self.zenith[index] * perez(&self.coefficients[index], cos_theta, gamma));
+
+        let color = xyy_to_rgb(x, y, luminance);
+
+        RgbColor::new(
+            f32::max(color.red, 0.0),
+            f32::max(color.green, 0.0),
+            f32::max(color.blue, 0.0),
+        )
+    }
+}
+
+impl Background for Sky {
+    fn radiance(&self, ray: &Ray) -> RgbColor {
+        self.sky_radiance(ray.direction.unit()) * self.intensity
+    }
+
+    fn is_sampled(&self) -> bool {
+        true
+    }
+
+    fn direction_probability(&self, _direction: Vec3) -> f32 {
+        1.0 / (4.0 * PI)
+    }
+}
+
+/// Perez sky distribution function, given the cosine of the angle with the
+/// zenith and the angle with the sun.
+fn perez(coefficients: &[f32; 5], cos_theta: f32, gamma: f32) -> f32 {
+    let [a, b, c, d, e] = *coefficients;
+
+    (1.0 + a * f32::exp(b / cos_theta)) * (1.0 + c * f32::exp(d * gamma) + e * gamma.cos().powi(2))
+}
+
+/// Sunlight after Rayleigh and aerosol scattering through the atmosphere,
+/// as approximated in the appendix of the Preetham paper.
+fn sun_radiance(sun_direction: Vec3, turbidity: f32) -> RgbColor {
+    if sun_direction.y <= -SUN_ANGULAR_RADIUS {
+        return BLACK;
+    }
+
+    let theta = f32::acos(sun_direction.y.clamp(0.0, 1.0));
+
+    // Relative optical mass of the air between the ground and the sun.
+    let optical_mass = 1.0 / (theta.cos() + 0.15 * f32::powf(93.885 - theta.to_degrees(), -1.253));
+
+    let beta = 0.046_08 * turbidity - 0.045_86;
+
+    let [red, green, blue] = WAVELENGTHS.map(|wavelength| {
+        let rayleigh = f32::exp(-0.008_735 * wavelength.powf(-4.08) * optical_mass);
+        let aerosol = f32::exp(-beta * wavelength.powf(-1.3) * optical_mass);
+
+        SUN_LUMINANCE * rayleigh * aerosol
+    });
+
+    RgbColor::new(red, green, blue)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::lights::DeltaLight;
+
+    #[test]
+    fn lights_the_ground_with_the_sun() {
+        let sky = Sky::new(Vec3::new(0.0, 1.0, 0.5), 3.0);
+        let sample = sky.sun_light().unwrap().illuminate(Vec3::zero()).unwrap();
+
+        assert!((sample.direction - sky.sun_direction()).len() < 1e-6);
+        assert_eq!(sample.distance, f32::INFINITY);
+
+        // A high sun in a clear sky gives around 100,000 lux.
+        let illuminance = sample.irradiance.luminance() * CANDELAS_PER_UNIT;
+
+        assert!(
+            (50_000.0..150_000.0).contains(&illuminance),
+            "{}",
+            illuminance
+        );
+
+        let dimmed = sky.with_intensity(0.5).sun_light().unwrap();
+        let dimmed_illuminance = dimmed
+            .illuminate(Vec3::zero())
+            .unwrap()
+            .irradiance
+            .luminance()
+            * CANDELAS_PER_UNIT;
+
+        assert!((dimmed_illuminance / illuminance - 0.5).abs() < 1e-4);
+    }
+
+    #[test]
+    fn has_no_sun_light_at_night() {
+        let sky = Sky::new(Vec3::new(0.0, -1.0, 1.0), 3.0);
+
+        assert!(sky.sun_light().is_none());
+        assert!(sky.radiance(&Ray::new(Vec3::zero(), Vec3::y(), 0.0)).red >= 0.0);
+    }
+}
@@ -1,6 +1,8 @@
+use std::f32::consts::PI;
+
 use crate::{
-    helpers::random,
     ray::Ray,
+    samplers::Sampler,
     vec3::{cross, Point3, Vec3},
 };
 
@@ -23,6 +25,7 @@ pub struct Camera {
 }
 
 impl Camera {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         look_from: Point3,
         look_at: Point3,
@@ -65,10 +68,9 @@ impl Camera {
         }
     }
 
-    pub fn cast_ray(&self, s: f32, t: f32) -> Ray {
-        let point_in_aperture_sphere = Point3::random_in_sphere(self.aperture / 2.0);
-        let origin_offset =
-            point_in_aperture_sphere.x * self.u + point_in_aperture_sphere.y * self.v;
+    pub fn cast_ray(&self, s: f32, t: f32, sampler: &mut dyn Sampler) -> Ray {
+        let (lens_x, lens_y) = random_in_disk(self.aperture / 2.0, sampler.next_2d());
+        let origin_offset = lens_x * self.u + lens_y * self.v;
 
         let ray_origin = self.origin + origin_offset;
         let ray_direction = (self.viewport_lower_left_corner
@@ -77,10 +79,21 @@ impl Camera {
             - ray_origin)
             .unit();
 
-        Ray::new(ray_origin, ray_direction, self.random_time())
+        Ray::new(
+            ray_origin,
+            ray_direction,
+            self.random_time(sampler.next_1d()),
+        )
     }
 
-    fn random_time(&self) -> f32 {
-        random(self.time..(self.time + self.shutter_speed))
+    fn random_time(&self, u: f32) -> f32 {
+        self.time + u * f32::max(self.shutter_speed, 0.0)
     }
 }
+
+fn random_in_disk(radius: f32, (u1, u2): (f32, f32)) -> (f32, f32) {
+    let distance = radius * f32::sqrt(u1);
+    let angle = 2.0 * PI * u2;
+
+    (distance * f32::cos(angle), distance * f32::sin(angle))
+}
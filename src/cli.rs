@@ -0,0 +1,444 @@
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+use tracer::{
+    color_space::TransferFunction,
+    render::{ProgressiveSettings, RenderSettings, ToneMapper},
+    samplers::SamplerKind,
+};
+
+use crate::demo::DEMO_NAMES;
+
+pub const USAGE: &str = "\
+Usage: tracer [OPTIONS] --output <PATH>
+
+Renders a demo scene or a scene file.
+
+Options:
+  -o, --output <PATH>            Path of the rendered image, .exr, .hdr and .pfm
+                                 images store linear floats
+      --demo <NAME>              Demo scene to render [default: cornell-box]
+      --scene <PATH>             Scene file to render instead of a demo
+  -H, --height <PIXELS>          Height of the image [default: 400]
+  -s, --samples <COUNT>          Samples per pixel [default: 10]
+  -b, --max-bounces <COUNT>      Maximum number of ray bounces [default: 10]
+      --roulette-depth <COUNT>   Bounces before paths may be randomly terminated
+                                 by Russian roulette [default: 3]
+      --color-space <NAME>       Transfer function of the image: srgb, rec709 or
+                                 linear [default: srgb]
+  -g, --gamma <VALUE>            Plain power law gamma instead of a color space
+      --dither                   Dither before quantizing to 8 bits
+  -e, --exposure <EV>            Exposure compensation in stops [default: 0.0]
+  -t, --tone-map <NAME>          Tone mapper: reinhard, extended-reinhard, aces or
+                                 hable [default: none, colors are clamped]
+      --white-point <VALUE>      Luminance mapped to white by extended-reinhard
+                                 [default: 4.0]
+  -j, --threads <COUNT>          Number of render threads [default: one per core]
+      --seed <NUMBER>            Seed of the random numbers [default: 0]
+      --sampler <NAME>           Sample generator: independent, stratified, halton
+                                 or sobol [default: sobol]
+      --pass-samples <COUNT>     Render progressively, this many samples per pixel
+                                 at a time [default: 1 when saving snapshots]
+      --snapshot-passes <COUNT>  Save the image rendered so far every this many passes
+      --snapshot-seconds <SECS>  Save the image rendered so far every this many seconds
+  -h, --help                     Print this help
+";
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum SceneSource {
+    Demo(String),
+    File(PathBuf),
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Options {
+    pub output: String,
+    pub source: SceneSource,
+    pub settings: RenderSettings,
+    pub threads: Option<usize>,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Command {
+    Help,
+    Render(Options),
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct UsageError(String);
+
+impl fmt::Display for UsageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+pub fn parse_args<I: Iterator<Item = String>>(args: I) -> Result<Command, UsageError> {
+    let mut output = None;
+    let mut demo = None;
+    let mut scene = None;
+    let mut settings = RenderSettings::default();
+    let mut threads = None;
+    let mut white_point = None;
+
+    let mut pass_samples = None;
+    let mut snapshot_passes = None;
+    let mut snapshot_interval = None;
+
+    let mut args = args.peekable();
+
+    while let Some(arg) = args.next() {
+        // Both `--option value` and `--option=value` are accepted.
+        let (name, inline_value) = match arg.split_once('=') {
+            Some((name, value)) if name.starts_with("--") => (name.to_string(), Some(value)),
+            _ => (arg.clone(), None),
+        };
+
+        let mut value = || -> Result<String, UsageError> {
+            match inline_value {
+                Some(value) => Ok(value.to_string()),
+                None => args
+                    .next()
+                    .ok_or_else(|| UsageError(format!("missing value for {}", name))),
+            }
+        };
+
+        match &name[..] {
+            "-h" | "--help" => return Ok(Command::Help),
+            "-o" | "--output" => output = Some(value()?),
+            "--demo" => demo = Some(value()?),
+            "--scene" => scene = Some(PathBuf::from(value()?)),
+            "-H" | "--height" => settings.image_height = parse_positive(&name, &value()?)?,
+            "-s" | "--samples" => settings.samples_per_pixel = parse_positive(&name, &value()?)?,
+            "-b" | "--max-bounces" => settings.max_ray_bounces = parse_positive(&name, &value()?)?,
+            "--roulette-depth" => settings.russian_roulette_depth = parse_value(&name, &value()?)?,
+            "--color-space" => {
+                let value = value()?;
+
+                settings.transfer_function =
+                    TransferFunction::from_name(&value).ok_or_else(|| {
+                        UsageError(format!(
+                            "unknown color space {}, expected one of: {}",
+                            value,
+                            TransferFunction::NAMES.join(", ")
+                        ))
+                    })?
+            }
+            "-g" | "--gamma" => {
+                settings.transfer_function =
+                    TransferFunction::Gamma(parse_positive(&name, &value()?)?)
+            }
+            "--dither" if inline_value.is_none() => settings.dither = true,
+            "-e" | "--exposure" => settings.exposure = parse_value(&name, &value()?)?,
+            "-t" | "--tone-map" => {
+                let value = value()?;
+
+                settings.tone_mapper = Some(ToneMapper::from_name(&value).ok_or_else(|| {
+                    UsageError(format!(
+                        "unknown tone mapper {}, expected one of: {}",
+                        value,
+                        ToneMapper::NAMES.join(", ")
+                    ))
+                })?)
+            }
+            "--white-point" => white_point = Some(parse_positive(&name, &value()?)?),
+            "-j" | "--threads" => threads = Some(parse_positive(&name, &value()?)?),
+            "--seed" => settings.seed = parse_value(&name, &value()?)?,
+            "--sampler" => {
+                let value = value()?;
+
+                settings.sampler = SamplerKind::from_name(&value).ok_or_else(|| {
+                    UsageError(format!(
+                        "unknown sampler {}, expected one of: {}",
+                        value,
+                        SamplerKind::NAMES.join(", ")
+                    ))
+                })?
+            }
+            "--pass-samples" => pass_samples = Some(parse_positive(&name, &value()?)?),
+            "--snapshot-passes" => snapshot_passes = Some(parse_positive(&name, &value()?)?),
+            "--snapshot-seconds" => {
+                let value = value()?;
+
+                snapshot_interval = Some(
+                    Duration::try_from_secs_f32(parse_positive(&name, &value)?)
+                        .map_err(|_| UsageError(format!("invalid value {} for {}", value, name)))?,
+                )
+            }
+            _ => return Err(UsageError(format!("unexpected argument {}", arg))),
+        }
+    }
+
+    if let Some(white_point) = white_point {
+        match &mut settings.tone_mapper {
+            Some(ToneMapper::ExtendedReinhard {
+                white_point: tone_mapper_white_point,
+            }) => *tone_mapper_white_point = white_point,
+            _ => {
+                return Err(UsageError(String::from(
+                    "--white-point requires --tone-map extended-reinhard",
+                )))
+            }
+        }
+    }
+
+    // Asking for snapshots is enough to render progressively, one sample per
+    // pixel at a time by default.
+    if pass_samples.is_some() || snapshot_passes.is_some() || snapshot_interval.is_some() {
+        settings.progressive = Some(ProgressiveSettings {
+            samples_per_pass: pass_samples.unwrap_or(1),
+            snapshot_passes,
+            snapshot_interval,
+        });
+    }
+
+    let output = output.ok_or_else(|| UsageError(String::from("missing --output")))?;
+
+    let source = match (demo, scene) {
+        (Some(_), Some(_)) => {
+            return Err(UsageError(String::from(
+                "--demo and --scene can't be used together",
+            )))
+        }
+        (None, Some(scene)) => SceneSource::File(scene),
+        (Some(demo), None) if !DEMO_NAMES.contains(&&demo[..]) => {
+            return Err(UsageError(format!(
+                "unknown demo {}, expected one of: {}",
+                demo,
+                DEMO_NAMES.join(", ")
+            )))
+        }
+        (demo, None) => SceneSource::Demo(demo.unwrap_or_else(|| String::from(DEMO_NAMES[0]))),
+    };
+
+    Ok(Command::Render(Options {
+        output,
+        source,
+        settings,
+        threads,
+    }))
+}
+
+fn parse_value<T: FromStr>(name: &str, value: &str) -> Result<T, UsageError> {
+    value
+        .parse()
+        .map_err(|_| UsageError(format!("invalid value {} for {}", value, name)))
+}
+
+fn parse_positive<T: FromStr + PartialOrd + Default>(
+    name: &str,
+    value: &str,
+) -> Result<T, UsageError> {
+    let parsed = parse_value(name, value)?;
+
+    if parsed > T::default() {
+        Ok(parsed)
+    } else {
+        Err(UsageError(format!("{} must be greater than zero", name)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Command, UsageError> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    fn parse_options(args: &[&str]) -> Options {
+        match parse(args) {
+            Ok(Command::Render(options)) => options,
+            result => panic!("expected options, got {:?}", result),
+        }
+    }
+
+    fn parse_error(args: &[&str]) -> String {
+        match parse(args) {
+            Err(error) => error.to_string(),
+            result => panic!("expected an error, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn defaults_to_the_first_demo() {
+        let options = parse_options(&["-o", "image.png"]);
+
+        assert_eq!(options.output, "image.png");
+        assert_eq!(
+            options.source,
+            SceneSource::Demo(String::from(DEMO_NAMES[0]))
+        );
+        assert_eq!(options.settings, RenderSettings::default());
+        assert_eq!(options.threads, None);
+    }
+
+    #[test]
+    fn parses_short_long_and_inline_options() {
+        let options = parse_options(&[
+            "--output=image.exr",
+            "-H",
+            "200",
+            "--samples=64",
+            "-b",
+            "5",
+            "--roulette-depth",
+            "0",
+            "-e",
+            "-1.5",
+            "--tone-map",
+            "extended-reinhard",
+            "--white-point=2",
+            "--color-space",
+            "linear",
+            "--dither",
+            "-j",
+            "4",
+            "--seed",
+            "42",
+            "--sampler",
+            "halton",
+            "--scene",
+            "scene.json",
+        ]);
+
+        assert_eq!(options.output, "image.exr");
+        assert_eq!(
+            options.source,
+            SceneSource::File(PathBuf::from("scene.json"))
+        );
+        assert_eq!(options.threads, Some(4));
+
+        let settings = options.settings;
+
+        assert_eq!(settings.image_height, 200);
+        assert_eq!(settings.samples_per_pixel, 64);
+        assert_eq!(settings.max_ray_bounces, 5);
+        assert_eq!(settings.russian_roulette_depth, 0);
+        assert_eq!(settings.exposure, -1.5);
+        assert_eq!(
+            settings.tone_mapper,
+            Some(ToneMapper::ExtendedReinhard { white_point: 2.0 })
+        );
+        assert_eq!(settings.transfer_function, TransferFunction::Linear);
+        assert!(settings.dither);
+        assert_eq!(settings.seed, 42);
+        assert_eq!(settings.sampler, SamplerKind::Halton);
+        assert_eq!(settings.progressive, None);
+    }
+
+    #[test]
+    fn help_needs_no_output() {
+        assert_eq!(parse(&["-h"]), Ok(Command::Help));
+        assert_eq!(parse(&["-o", "image.png", "--help"]), Ok(Command::Help));
+    }
+
+    #[test]
+    fn snapshots_render_progressively() {
+        let options = parse_options(&["-o", "image.png", "--snapshot-seconds", "2.5"]);
+
+        assert_eq!(
+            options.settings.progressive,
+            Some(ProgressiveSettings {
+                samples_per_pass: 1,
+                snapshot_passes: None,
+                snapshot_interval: Some(Duration::from_millis(2500)),
+            })
+        );
+
+        let options = parse_options(&[
+            "-o",
+            "image.png",
+            "--pass-samples",
+            "4",
+            "--snapshot-passes",
+            "8",
+        ]);
+
+        assert_eq!(
+            options.settings.progressive,
+            Some(ProgressiveSettings {
+                samples_per_pass: 4,
+                snapshot_passes: Some(8),
+                snapshot_interval: None,
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_values() {
+        assert_eq!(parse_error(&[]), "missing --output");
+        assert_eq!(parse_error(&["-o"]), "missing value for -o");
+        assert_eq!(
+            parse_error(&["-o", "a.png", "--frobnicate"]),
+            "unexpected argument --frobnicate"
+        );
+        assert_eq!(
+            parse_error(&["-o", "a.png", "--dither=yes"]),
+            "unexpected argument --dither=yes"
+        );
+        assert_eq!(
+            parse_error(&["-o", "a.png", "-s", "ten"]),
+            "invalid value ten for -s"
+        );
+        assert_eq!(
+            parse_error(&["-o", "a.png", "-H", "0"]),
+            "-H must be greater than zero"
+        );
+        assert_eq!(
+            parse_error(&["-o", "a.png", "-g", "-2"]),
+            "-g must be greater than zero"
+        );
+        assert_eq!(
+            parse_error(&["-o", "a.png", "--snapshot-seconds", "inf"]),
+            "invalid value inf for --snapshot-seconds"
+        );
+        assert_eq!(
+            parse_error(&["-o", "a.png", "--snapshot-seconds=1e30"]),
+            "invalid value 1e30 for --snapshot-seconds"
+        );
+        assert_eq!(
+            parse_error(&["-o", "a.png", "--snapshot-seconds", "NaN"]),
+            "--snapshot-seconds must be greater than zero"
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_names() {
+        assert!(
+            parse_error(&["-o", "a.png", "--demo", "teapot"]).starts_with("unknown demo teapot")
+        );
+        assert!(
+            parse_error(&["-o", "a.png", "-t", "filmic"]).starts_with("unknown tone mapper filmic")
+        );
+        assert!(parse_error(&["-o", "a.png", "--sampler", "sobel"])
+            .starts_with("unknown sampler sobel"));
+        assert!(parse_error(&["-o", "a.png", "--color-space", "p3"])
+            .starts_with("unknown color space p3"));
+    }
+
+    #[test]
+    fn rejects_conflicting_options() {
+        assert_eq!(
+            parse_error(&[
+                "-o",
+                "a.png",
+                "--demo",
+                DEMO_NAMES[0],
+                "--scene",
+                "scene.json"
+            ]),
+            "--demo and --scene can't be used together"
+        );
+        assert_eq!(
+            parse_error(&["-o", "a.png", "--white-point", "2"]),
+            "--white-point requires --tone-map extended-reinhard"
+        );
+        assert_eq!(
+            parse_error(&["-o", "a.png", "--white-point", "2", "-t", "aces"]),
+            "--white-point requires --tone-map extended-reinhard"
+        );
+    }
+}
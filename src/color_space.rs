@@ -0,0 +1,176 @@
+use crate::rgb_color::{RgbColor, BLACK};
+
+/// Encodes linear values, with Rec. 709 primaries, into the values stored
+/// in an image.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum TransferFunction {
+    Linear,
+    /// Piecewise sRGB curve.
+    Srgb,
+    /// Rec. 709 camera curve.
+    Rec709,
+    /// Plain power law with the given gamma.
+    Gamma(f32),
+}
+
+impl TransferFunction {
+    pub const NAMES: [&'static str; 3] = ["srgb", "rec709", "linear"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "srgb" => Some(Self::Srgb),
+            "rec709" => Some(Self::Rec709),
+            "linear" => Some(Self::Linear),
+            _ => None,
+        }
+    }
+
+    pub fn encode(&self, value: f32) -> f32 {
+        let value = f32::max(value, 0.0);
+
+        match *self {
+            Self::Linear => value,
+            Self::Srgb => {
+                if value <= 0.003_130_8 {
+                    value * 12.92
+                } else {
+                    1.055 * value.powf(1.0 / 2.4) - 0.055
+                }
+            }
+            Self::Rec709 => {
+                if value < 0.018 {
+                    value * 4.5
+                } else {
+                    1.099 * value.powf(0.45) - 0.099
+                }
+            }
+            Self::Gamma(gamma) => value.powf(1.0 / gamma),
+        }
+    }
+
+    pub fn decode(&self, value: f32) -> f32 {
+        let value = f32::max(value, 0.0);
+
+        match *self {
+            Self::Linear => value,
+            Self::Srgb => {
+                if value <= 0.040_45 {
+                    value / 12.92
+                } else {
+                    ((value + 0.055) / 1.055).powf(2.4)
+                }
+            }
+            Self::Rec709 => {
+                if value < 0.081 {
+                    value / 4.5
+                } else {
+                    ((value + 0.099) / 1.099).powf(1.0 / 0.45)
+                }
+            }
+            Self::Gamma(gamma) => value.powf(gamma),
+        }
+    }
+
+    pub fn encode_color(&self, color: RgbColor) -> RgbColor {
+        RgbColor::new(
+            self.encode(color.red),
+            self.encode(color.green),
+            self.encode(color.blue),
+        )
+    }
+
+    pub fn decode_color(&self, color: RgbColor) -> RgbColor {
+        RgbColor::new(
+            self.decode(color.red),
+            self.decode(color.green),
+            self.decode(color.blue),
+        )
+    }
+}
+
+/// Converts a CIE xyY color to linear RGB with Rec. 709 primaries and a D65
+/// white point.
+pub fn xyy_to_rgb(x: f32, y: f32, luminance: f32) -> RgbColor {
+    if y <= 0.0 {
+        return BLACK;
+    }
+
+    let big_x = x * luminance / y;
+    let big_z = (1.0 - x - y) * luminance / y;
+
+    RgbColor::new(
+        3.240_6 * big_x - 1.537_2 * luminance - 0.498_6 * big_z,
+        -0.968_9 * big_x + 1.875_8 * luminance + 0.041_5 * big_z,
+        0.055_7 * big_x - 0.204 * luminance + 1.057 * big_z,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRANSFER_FUNCTIONS: [TransferFunction; 4] = [
+        TransferFunction::Linear,
+        TransferFunction::Srgb,
+        TransferFunction::Rec709,
+        TransferFunction::Gamma(2.2),
+    ];
+
+    #[test]
+    fn decodes_what_it_encodes() {
+        for transfer_function in TRANSFER_FUNCTIONS.iter() {
+            for step in 0..=1000 {
+                let value = step as f32 / 1000.0;
+                let decoded = transfer_function.decode(transfer_function.encode(value));
+
+                assert!(
+                    (decoded - value).abs() < 1e-5,
+                    "{:?}: {}",
+                    transfer_function,
+                    value
+                );
+            }
+
+            assert_eq!(transfer_function.encode(0.0), 0.0);
+            assert!((transfer_function.encode(1.0) - 1.0).abs() < 1e-5);
+            assert_eq!(transfer_function.encode(-1.0), 0.0);
+        }
+    }
+
+    #[test]
+    fn encodes_srgb_linearly_near_black() {
+        let srgb = TransferFunction::Srgb;
+
+        assert!((srgb.encode(0.003_130_8) - 0.040_45).abs() < 1e-6);
+        assert!((srgb.encode(0.001) - 0.012_92).abs() < 1e-7);
+        assert!((srgb.decode(0.040_45) - 0.003_130_8).abs() < 1e-6);
+
+        // Both pieces of the curve meet at the threshold.
+        let below = srgb.encode(0.003_130_8 - 1e-7);
+        let above = srgb.encode(0.003_130_8 + 1e-7);
+        assert!((above - below).abs() < 1e-5);
+
+        assert!((srgb.encode(0.5) - 0.735_357).abs() < 1e-5);
+        assert!((srgb.decode(0.5) - 0.214_041).abs() < 1e-5);
+    }
+
+    #[test]
+    fn finds_every_transfer_function_by_name() {
+        for name in TransferFunction::NAMES.iter() {
+            assert!(TransferFunction::from_name(name).is_some(), "{}", name);
+        }
+
+        assert_eq!(TransferFunction::from_name("gamma"), None);
+    }
+
+    #[test]
+    fn converts_the_white_point_to_white() {
+        let white = xyy_to_rgb(0.312_7, 0.329_0, 1.0);
+
+        for &channel in [white.red, white.green, white.blue].iter() {
+            assert!((channel - 1.0).abs() < 1e-2, "{:?}", white);
+        }
+
+        assert_eq!(xyy_to_rgb(0.3, 0.0, 1.0), BLACK);
+    }
+}
@@ -4,10 +4,10 @@ use std::sync::Arc;
 use tracer::{
     camera::Camera,
     materials::{Dielectric, DiffuseLight, Lambertian, Material},
-    objects::{Object, ObjectList, RectangleBuilder, RectangularPrismBuilder, SphereBuilder},
-    rgb_color::{RgbColor, BLACK, WHITE},
+    objects::{Bvh, Object, ObjectList, RectangleBuilder, RectangularPrismBuilder, SphereBuilder},
+    ray::Ray,
+    rgb_color::{BLACK, WHITE},
     scene::Scene,
-    textures::SolidColor,
     vec3::{Point3, Vec3},
 };
 
@@ -175,10 +175,16 @@ impl Demo for CornellBox {
 
         objects.push(box2);
 
+        let camera = self.camera();
+
         Scene::new(
-            Arc::new(ObjectList::new(objects)),
+            Arc::new(Bvh::new(
+                objects,
+                camera.time,
+                camera.time + camera.shutter_speed,
+            )),
             Arc::new(ObjectList::new(lights)),
-            |_ray| BLACK,
+            Arc::new(|_ray: &Ray| BLACK),
         )
     }
 }
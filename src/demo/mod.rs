@@ -1,4 +1,5 @@
 mod cornell_box;
+#[allow(clippy::module_inception)]
 mod demo;
 
 pub use self::{cornell_box::*, demo::*};
@@ -0,0 +1,220 @@
+/// Piecewise constant distribution over `[0, 1)`, with one piece per value
+/// of the function.
+#[derive(Clone, Debug)]
+pub struct Distribution1d {
+    function: Vec<f32>,
+    cdf: Vec<f32>,
+    integral: f32,
+}
+
+impl Distribution1d {
+    pub fn new(function: Vec<f32>) -> Self {
+        // An empty function is sampled as a single piece that is zero, so
+        // uniformly.
+        let function = if function.is_empty() {
+            vec![0.0]
+        } else {
+            function
+        };
+        let count = function.len();
+
+        let mut cdf = Vec::with_capacity(count + 1);
+        cdf.push(0.0);
+
+        for (index, value) in function.iter().enumerate() {
+            cdf.push(cdf[index] + f32::max(*value, 0.0) / count as f32);
+        }
+
+        let integral = cdf[count];
+
+        // A function that is zero everywhere falls back to a uniform
+        // distribution.
+        for (index, value) in cdf.iter_mut().enumerate() {
+            *value = if integral > 0.0 {
+                *value / integral
+            } else {
+                index as f32 / count as f32
+            };
+        }
+
+        Self {
+            function,
+            cdf,
+            integral,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.function.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.function.is_empty()
+    }
+
+    pub const fn integral(&self) -> f32 {
+        self.integral
+    }
+
+    /// Returns a point in `[0, 1)` distributed like the function, its
+    /// probability density and the index of its piece.
+    pub fn sample(&self, u: f32) -> (f32, f32, usize) {
+        let index = usize::min(
+            self.cdf
+                .partition_point(|&cumulative| cumulative <= u)
+                .saturating_sub(1),
+            self.len() - 1,
+        );
+
+        let piece_start = self.cdf[index];
+        let piece_probability = self.cdf[index + 1] - piece_start;
+
+        let offset = if piece_probability > 0.0 {
+            (u - piece_start) / piece_probability
+        } else {
+            0.0
+        };
+
+        let x = f32::min(
+            (index as f32 + offset) / self.len() as f32,
+            1.0 - f32::EPSILON / 2.0,
+        );
+
+        (x, self.pdf(index), index)
+    }
+
+    /// Probability density of the points of the given piece.
+    pub fn pdf(&self, index: usize) -> f32 {
+        if self.integral > 0.0 {
+            f32::max(self.function[index], 0.0) / self.integral
+        } else {
+            1.0
+        }
+    }
+}
+
+/// Piecewise constant distribution over `[0, 1)²`, sampled by picking a row
+/// from the marginal distribution and then a column from that row.
+#[derive(Clone, Debug)]
+pub struct Distribution2d {
+    rows: Vec<Distribution1d>,
+    marginal: Distribution1d,
+}
+
+impl Distribution2d {
+    /// Takes the values of the function row by row.
+    pub fn new(function: &[f32], width: usize, height: usize) -> Self {
+        let mut rows: Vec<_> = if width == 0 {
+            Vec::new()
+        } else {
+            function
+                .chunks(width)
+                .take(height)
+                .map(|row| Distribution1d::new(row.to_vec()))
+                .collect()
+        };
+
+        // Empty functions are sampled uniformly, like the ones that are zero
+        // everywhere.
+        if rows.is_empty() {
+            rows.push(Distribution1d::new(Vec::new()));
+        }
+
+        let marginal = Distribution1d::new(rows.iter().map(|row| row.integral()).collect());
+
+        Self { rows, marginal }
+    }
+
+    /// Returns a point `(x, y)` distributed like the function and its
+    /// probability density.
+    pub fn sample(&self, (u1, u2): (f32, f32)) -> ((f32, f32), f32) {
+        let (y, row_pdf, row) = self.marginal.sample(u2);
+        let (x, column_pdf, _) = self.rows[row].sample(u1);
+
+        ((x, y), row_pdf * column_pdf)
+    }
+
+    pub fn pdf(&self, (x, y): (f32, f32)) -> f32 {
+        let row = usize::min((y * self.rows.len() as f32) as usize, self.rows.len() - 1);
+        let columns = &self.rows[row];
+        let column = usize::min((x * columns.len() as f32) as usize, columns.len() - 1);
+
+        self.marginal.pdf(row) * columns.pdf(column)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_sample(sample: (f32, f32, usize), expected: (f32, f32, usize)) {
+        assert!(
+            (sample.0 - expected.0).abs() < 1e-6
+                && sample.1 == expected.1
+                && sample.2 == expected.2,
+            "{:?} != {:?}",
+            sample,
+            expected
+        );
+    }
+
+    #[test]
+    fn samples_pieces_proportionally_to_the_function() {
+        let distribution = Distribution1d::new(vec![1.0, 0.0, 3.0]);
+
+        assert_eq!(distribution.integral(), 4.0 / 3.0);
+        assert_sample(distribution.sample(0.0), (0.0, 0.75, 0));
+        assert_sample(distribution.sample(0.125), (1.0 / 6.0, 0.75, 0));
+        assert_sample(distribution.sample(0.5), (7.0 / 9.0, 2.25, 2));
+        assert_eq!(distribution.pdf(1), 0.0);
+    }
+
+    #[test]
+    fn samples_empty_and_zero_functions_uniformly() {
+        for function in [Vec::new(), vec![0.0], vec![0.0, -1.0, 0.0, 0.0]].iter() {
+            let distribution = Distribution1d::new(function.clone());
+            let index = (0.6 * distribution.len() as f32) as usize;
+
+            assert_sample(distribution.sample(0.6), (0.6, 1.0, index));
+        }
+    }
+
+    #[test]
+    fn samples_empty_2d_functions_uniformly() {
+        for &(function, width, height) in [
+            (&[][..], 0, 0),
+            (&[][..], 0, 4),
+            (&[][..], 4, 0),
+            (&[1.0, 2.0][..], 0, 2),
+            (&[][..], 2, 2),
+        ]
+        .iter()
+        {
+            let distribution = Distribution2d::new(function, width, height);
+            let ((x, y), pdf) = distribution.sample((0.25, 0.75));
+
+            assert!((x - 0.25).abs() < 1e-6 && (y - 0.75).abs() < 1e-6);
+            assert_eq!(pdf, 1.0);
+            assert_eq!(distribution.pdf((0.9, 0.1)), 1.0);
+        }
+    }
+
+    #[test]
+    fn matches_the_density_of_2d_samples() {
+        let function = [0.0, 1.0, 2.0, 3.0, 4.0, 5.0];
+        let distribution = Distribution2d::new(&function, 3, 2);
+
+        for i in 0..10 {
+            for j in 0..10 {
+                let u = ((i as f32 + 0.5) / 10.0, (j as f32 + 0.5) / 10.0);
+                let ((x, y), pdf) = distribution.sample(u);
+
+                let row = (y * 2.0) as usize;
+                let column = (x * 3.0) as usize;
+
+                assert!((pdf - function[row * 3 + column] / 2.5).abs() < 1e-5);
+                assert!((distribution.pdf((x, y)) - pdf).abs() < 1e-5);
+            }
+        }
+    }
+}
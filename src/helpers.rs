@@ -1,17 +1,5 @@
-use rand::prelude::*;
-
 use crate::vec3::{Point3, Vec3};
 
-pub fn random<T, R>(range: R) -> T
-where
-    T: rand::distributions::uniform::SampleUniform,
-    R: rand::distributions::uniform::SampleRange<T>,
-{
-    let mut rng = rand::thread_rng();
-
-    rng.gen_range(range)
-}
-
 pub fn position(
     time: f32,
     acceleration: Vec3,
@@ -20,3 +8,73 @@ pub fn position(
 ) -> Point3 {
     acceleration * time.powi(2) / 2.0 + initial_velocity * time + initial_position
 }
+
+pub fn position_bounds(
+    time_start: f32,
+    time_end: f32,
+    acceleration: Vec3,
+    initial_velocity: Vec3,
+    initial_position: Point3,
+) -> (Point3, Point3) {
+    let start = position(time_start, acceleration, initial_velocity, initial_position);
+    let end = position(time_end, acceleration, initial_velocity, initial_position);
+
+    let mut min = start.min(end);
+    let mut max = start.max(end);
+
+    // A constantly accelerated motion can turn around halfway through the
+    // interval, in which case the extreme isn't at either end.
+    for axis in 0..3 {
+        if acceleration[axis] == 0.0 {
+            continue;
+        }
+
+        let turning_time = -initial_velocity[axis] / acceleration[axis];
+
+        if turning_time > time_start && turning_time < time_end {
+            let turning_position = position(
+                turning_time,
+                acceleration,
+                initial_velocity,
+                initial_position,
+            )[axis];
+
+            min[axis] = f32::min(min[axis], turning_position);
+            max[axis] = f32::max(max[axis], turning_position);
+        }
+    }
+
+    (min, max)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    /// Directory of its own for the files of a test, removed with them once
+    /// the test ends, even if it fails.
+    pub(crate) struct TemporaryDirectory(PathBuf);
+
+    impl TemporaryDirectory {
+        pub(crate) fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("tracer-{}-{}", name, std::process::id()));
+
+            // Leftovers of a run killed before it could clean up.
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+
+            Self(path)
+        }
+
+        pub(crate) fn path(&self) -> &Path {
+            &self.0
+        }
+    }
+
+    impl Drop for TemporaryDirectory {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use std::sync::Arc;
 
 use crate::{
+    aabb::Aabb,
     materials::Material,
     ray::{Ray, MIN_T},
     vec3::{dot, Point3, Vec3},
@@ -8,9 +9,10 @@ use crate::{
 
 pub trait Hittable {
     fn hit(&self, ray: &Ray, max_t: f32) -> Option<Hit>;
+    fn bounding_box(&self, time_start: f32, time_end: f32) -> Option<Aabb>;
 
     fn ray_to_self_probability(&self, ray: &Ray) -> f32;
-    fn random_direction_to_self(&self, origin: Point3, time: f32) -> Vec3;
+    fn random_direction_to_self(&self, origin: Point3, time: f32, u: (f32, f32)) -> Vec3;
 }
 
 #[derive(Clone)]
@@ -47,6 +49,18 @@ impl Hit {
         }
     }
 
+    /// Replaces the normal with an interpolated one, flipped to the side of
+    /// the surface the ray hit.
+    pub fn with_shading_normal(self, shading_normal: Vec3) -> Self {
+        let normal = if dot(shading_normal, self.normal) < 0.0 {
+            -shading_normal
+        } else {
+            shading_normal
+        };
+
+        Self { normal, ..self }
+    }
+
     pub fn normal_from_outward_normal(outward_normal: Vec3, front_face: bool) -> Vec3 {
         if front_face {
             outward_normal
@@ -57,9 +71,5 @@ impl Hit {
 }
 
 pub fn intersects(t: f32, max_t: f32) -> bool {
-    if t < MIN_T || t > max_t || !t.is_normal() {
-        false
-    } else {
-        true
-    }
+    (MIN_T..=max_t).contains(&t) && t.is_normal()
 }
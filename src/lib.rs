@@ -1,6 +1,12 @@
+pub mod aabb;
+pub mod backgrounds;
 pub mod camera;
+pub mod color_space;
+pub mod distribution;
 pub mod helpers;
 pub mod hittable;
+pub mod lights;
+pub mod loaders;
 pub mod materials;
 pub mod objects;
 pub mod onb;
@@ -8,6 +14,8 @@ pub mod pdfs;
 pub mod ray;
 pub mod render;
 pub mod rgb_color;
+pub mod rng;
+pub mod samplers;
 pub mod scene;
 pub mod textures;
 pub mod vec3;
@@ -0,0 +1,60 @@
+use crate::{
+    rgb_color::{RgbColor, BLACK},
+    vec3::{Point3, Vec3},
+};
+
+/// Candelas per square meter in one unit of radiance, the scale of the sky.
+/// Distances are taken to be in meters.
+pub const CANDELAS_PER_UNIT: f32 = 1000.0;
+
+/// Light arriving at a point from a delta light.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct LightSample {
+    /// Unit vector from the point towards the light.
+    pub direction: Vec3,
+    /// Distance to the light, infinite for directional lights.
+    pub distance: f32,
+    /// Irradiance on a surface facing the light.
+    pub irradiance: RgbColor,
+}
+
+/// Light coming from a single point or direction, which rays can't hit and
+/// can only be reached by sampling it.
+pub trait DeltaLight: Send + Sync {
+    fn illuminate(&self, point: Point3) -> Option<LightSample>;
+}
+
+/// Scales the color to a luminance of one, so that the brightness of a
+/// light only comes from its photometric quantity.
+pub(crate) fn normalized_color(color: RgbColor) -> RgbColor {
+    let luminance = color.luminance();
+
+    if luminance > 0.0 {
+        color / luminance
+    } else {
+        BLACK
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_colors_to_a_luminance_of_one() {
+        for &color in [
+            RgbColor::new(1.0, 1.0, 1.0),
+            RgbColor::new(2.0, 0.5, 0.1),
+            RgbColor::new(0.0, 0.0, 3.0),
+        ]
+        .iter()
+        {
+            let normalized = normalized_color(color);
+
+            assert!((normalized.luminance() - 1.0).abs() < 1e-5);
+            assert!((normalized.red * color.blue - normalized.blue * color.red).abs() < 1e-5);
+        }
+
+        assert_eq!(normalized_color(BLACK), BLACK);
+    }
+}
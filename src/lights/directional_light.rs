@@ -0,0 +1,55 @@
+use crate::{
+    lights::{normalized_color, DeltaLight, LightSample, CANDELAS_PER_UNIT},
+    rgb_color::RgbColor,
+    vec3::{Point3, Vec3},
+};
+
+/// Light arriving from infinitely far away along a single direction, like
+/// sunlight.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct DirectionalLight {
+    /// Direction in which the light travels.
+    direction: Vec3,
+    irradiance: RgbColor,
+}
+
+impl DirectionalLight {
+    /// Light travelling along the direction, with an illuminance in lux on
+    /// surfaces facing it.
+    pub fn new(direction: Vec3, color: RgbColor, illuminance: f32) -> Self {
+        Self {
+            direction: direction.unit(),
+            irradiance: normalized_color(color) * illuminance / CANDELAS_PER_UNIT,
+        }
+    }
+}
+
+impl DeltaLight for DirectionalLight {
+    fn illuminate(&self, _point: Point3) -> Option<LightSample> {
+        Some(LightSample {
+            direction: -self.direction,
+            distance: f32::INFINITY,
+            irradiance: self.irradiance,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::rgb_color::WHITE;
+
+    #[test]
+    fn lights_every_point_from_the_same_direction() {
+        let light = DirectionalLight::new(Vec3::new(1.0, -1.0, 0.0), WHITE, 100_000.0);
+
+        for &point in [Point3::zero(), Point3::new(100.0, -5.0, 3.0)].iter() {
+            let sample = light.illuminate(point).unwrap();
+
+            assert!((sample.direction - Vec3::new(-1.0, 1.0, 0.0).unit()).len() < 1e-6);
+            assert_eq!(sample.distance, f32::INFINITY);
+            assert!((sample.irradiance.luminance() - 100.0).abs() < 1e-3);
+        }
+    }
+}
@@ -0,0 +1,6 @@
+mod delta_light;
+mod directional_light;
+mod point_light;
+mod spot_light;
+
+pub use self::{delta_light::*, directional_light::*, point_light::*, spot_light::*};
@@ -0,0 +1,92 @@
+use std::f32::consts::PI;
+
+use crate::{
+    lights::{normalized_color, DeltaLight, LightSample, CANDELAS_PER_UNIT},
+    rgb_color::RgbColor,
+    vec3::Point3,
+};
+
+/// Light emitted equally in every direction from a point.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct PointLight {
+    pub position: Point3,
+    /// Radiant intensity, in units of radiance times square meters.
+    intensity: RgbColor,
+}
+
+impl PointLight {
+    /// Light with a luminous intensity in candelas.
+    pub fn new(position: Point3, color: RgbColor, intensity: f32) -> Self {
+        Self {
+            position,
+            intensity: normalized_color(color) * intensity / CANDELAS_PER_UNIT,
+        }
+    }
+
+    /// Light with a luminous power in lumens.
+    pub fn from_power(position: Point3, color: RgbColor, power: f32) -> Self {
+        Self::new(position, color, power / (4.0 * PI))
+    }
+}
+
+impl DeltaLight for PointLight {
+    fn illuminate(&self, point: Point3) -> Option<LightSample> {
+        let offset = self.position - point;
+        let distance_squared = offset.len_squared();
+
+        if distance_squared == 0.0 {
+            return None;
+        }
+
+        let distance = distance_squared.sqrt();
+
+        Some(LightSample {
+            direction: offset / distance,
+            distance,
+            irradiance: self.intensity / distance_squared,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{rgb_color::WHITE, vec3::Vec3};
+
+    #[test]
+    fn falls_off_with_the_squared_distance() {
+        let light = PointLight::new(Point3::new(1.0, 2.0, 3.0), WHITE, 800.0);
+
+        for &distance in [0.5, 1.0, 4.0].iter() {
+            let sample = light
+                .illuminate(Point3::new(1.0, 2.0 - distance, 3.0))
+                .unwrap();
+
+            // Lux, from candelas over squared meters.
+            let illuminance = sample.irradiance.luminance() * CANDELAS_PER_UNIT;
+
+            assert!((illuminance - 800.0 / (distance * distance)).abs() < 1e-2 * illuminance);
+            assert!((sample.distance - distance).abs() < 1e-5);
+            assert!((sample.direction - Vec3::y()).len() < 1e-5);
+        }
+
+        assert!(light.illuminate(light.position).is_none());
+    }
+
+    #[test]
+    fn spreads_its_power_over_the_sphere() {
+        let position = Point3::new(1.0, 2.0, 3.0);
+        let color = RgbColor::new(1.0, 0.5, 0.25);
+        let from_power = PointLight::from_power(position, color, 4.0 * PI * 100.0);
+        let from_intensity = PointLight::new(position, color, 100.0);
+
+        let point = Point3::zero();
+        let (lhs, rhs) = (
+            from_power.illuminate(point).unwrap().irradiance,
+            from_intensity.illuminate(point).unwrap().irradiance,
+        );
+
+        assert!((lhs.red - rhs.red).abs() < 1e-6 && (lhs.blue - rhs.blue).abs() < 1e-6);
+    }
+}
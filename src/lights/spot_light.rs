@@ -0,0 +1,187 @@
+use std::f32::consts::PI;
+
+use crate::{
+    lights::{normalized_color, DeltaLight, LightSample, CANDELAS_PER_UNIT},
+    rgb_color::RgbColor,
+    vec3::{dot, Point3, Vec3},
+};
+
+/// Point light that only shines inside a cone, fading out smoothly between
+/// the falloff start and the edge of the cone.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct SpotLight {
+    pub position: Point3,
+    direction: Vec3,
+    /// Radiant intensity along the axis, in units of radiance times square
+    /// meters.
+    intensity: RgbColor,
+    cos_cone_angle: f32,
+    cos_falloff_start: f32,
+}
+
+impl SpotLight {
+    /// Light with a luminous intensity in candelas along its axis. Angles are
+    /// measured from the axis, in degrees.
+    pub fn new(
+        position: Point3,
+        direction: Vec3,
+        color: RgbColor,
+        intensity: f32,
+        cone_angle: f32,
+        falloff_start: f32,
+    ) -> Self {
+        let cone_angle = cone_angle.clamp(0.0, 180.0);
+
+        Self {
+            position,
+            direction: direction.unit(),
+            intensity: normalized_color(color) * intensity / CANDELAS_PER_UNIT,
+            cos_cone_angle: cone_angle.to_radians().cos(),
+            cos_falloff_start: falloff_start.clamp(0.0, cone_angle).to_radians().cos(),
+        }
+    }
+
+    /// Light with a luminous power in lumens.
+    pub fn from_power(
+        position: Point3,
+        direction: Vec3,
+        color: RgbColor,
+        power: f32,
+        cone_angle: f32,
+        falloff_start: f32,
+    ) -> Self {
+        let cone_angle = cone_angle.clamp(0.0, 180.0);
+        let cos_cone_angle = cone_angle.to_radians().cos();
+        let cos_falloff_start = falloff_start.clamp(0.0, cone_angle).to_radians().cos();
+
+        // Solid angle of the cone, counting the falloff as half lit.
+        let solid_angle = 2.0 * PI * (1.0 - (cos_falloff_start + cos_cone_angle) / 2.0);
+
+        let intensity = if solid_angle > 0.0 {
+            power / solid_angle
+        } else {
+            0.0
+        };
+
+        Self::new(
+            position,
+            direction,
+            color,
+            intensity,
+            cone_angle,
+            falloff_start,
+        )
+    }
+
+    fn falloff(&self, cos_theta: f32) -> f32 {
+        if cos_theta >= self.cos_falloff_start {
+            return 1.0;
+        }
+
+        let t = ((cos_theta - self.cos_cone_angle)
+            / (self.cos_falloff_start - self.cos_cone_angle))
+            .clamp(0.0, 1.0);
+
+        t * t * (3.0 - 2.0 * t)
+    }
+}
+
+impl DeltaLight for SpotLight {
+    fn illuminate(&self, point: Point3) -> Option<LightSample> {
+        let offset = self.position - point;
+        let distance_squared = offset.len_squared();
+
+        if distance_squared == 0.0 {
+            return None;
+        }
+
+        let distance = distance_squared.sqrt();
+        let direction = offset / distance;
+
+        let cos_theta = dot(-direction, self.direction);
+
+        if cos_theta <= self.cos_cone_angle {
+            return None;
+        }
+
+        Some(LightSample {
+            direction,
+            distance,
+            irradiance: self.intensity * self.falloff(cos_theta) / distance_squared,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::rgb_color::WHITE;
+
+    /// Spot light one unit above the origin, pointing down.
+    fn spot_light(cone_angle: f32, falloff_start: f32) -> SpotLight {
+        SpotLight::new(
+            Vec3::y(),
+            -Vec3::y(),
+            WHITE,
+            1000.0,
+            cone_angle,
+            falloff_start,
+        )
+    }
+
+    /// Irradiance on the ground of a point lit at the angle from the axis.
+    fn irradiance(light: &SpotLight, angle: f32) -> f32 {
+        let angle = angle.to_radians();
+        let point = Vec3::new(angle.tan(), 0.0, 0.0);
+
+        light.illuminate(point).map_or(0.0, |sample| {
+            sample.irradiance.luminance() * sample.distance * sample.distance
+        })
+    }
+
+    #[test]
+    fn fades_out_between_the_falloff_start_and_the_cone() {
+        let light = spot_light(40.0, 20.0);
+
+        for &angle in [0.0, 10.0, 19.9].iter() {
+            assert!((irradiance(&light, angle) - 1.0).abs() < 1e-4, "{}", angle);
+        }
+
+        let mut previous = 1.0;
+
+        for step in 1..20 {
+            let value = irradiance(&light, 20.0 + step as f32);
+
+            assert!(value > 0.0 && value < previous, "{}: {}", step, value);
+
+            previous = value;
+        }
+
+        for &angle in [40.0, 40.1, 60.0, 89.0].iter() {
+            assert_eq!(irradiance(&light, angle), 0.0, "{}", angle);
+        }
+
+        assert_eq!(light.falloff(light.cos_cone_angle), 0.0);
+    }
+
+    #[test]
+    fn spreads_its_power_over_the_cone() {
+        let cone_angle = 30.0_f32;
+        let solid_angle = 2.0 * PI * (1.0 - cone_angle.to_radians().cos());
+
+        let from_power = SpotLight::from_power(
+            Vec3::y(),
+            -Vec3::y(),
+            WHITE,
+            1000.0 * solid_angle,
+            cone_angle,
+            cone_angle,
+        );
+
+        let sample = from_power.illuminate(Vec3::zero()).unwrap();
+
+        assert!((sample.irradiance.luminance() * CANDELAS_PER_UNIT - 1000.0).abs() < 1e-1);
+        assert_eq!(from_power.illuminate(Vec3::new(1.0, 0.0, 0.0)), None);
+    }
+}
@@ -0,0 +1,455 @@
+use std::iter::Peekable;
+use std::str::Chars;
+
+/// A JSON value and the line it starts on, so that errors found while
+/// interpreting a document can point at the offending line.
+#[derive(Clone, PartialEq, Debug)]
+pub struct JsonValue {
+    pub line: usize,
+    pub kind: JsonKind,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum JsonKind {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<JsonValue>),
+    Object(Vec<(String, JsonValue)>),
+}
+
+impl JsonValue {
+    pub fn type_name(&self) -> &'static str {
+        match self.kind {
+            JsonKind::Null => "null",
+            JsonKind::Bool(_) => "a boolean",
+            JsonKind::Number(_) => "a number",
+            JsonKind::String(_) => "a string",
+            JsonKind::Array(_) => "an array",
+            JsonKind::Object(_) => "an object",
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct JsonError {
+    pub line: usize,
+    pub message: String,
+}
+
+/// Parses a JSON document. Line comments starting with `//` are allowed
+/// anywhere whitespace is.
+pub fn parse_json(source: &str) -> Result<JsonValue, JsonError> {
+    let mut parser = JsonParser {
+        chars: source.chars().peekable(),
+        line: 1,
+    };
+
+    let value = parser.value()?;
+
+    parser.skip_whitespace()?;
+
+    match parser.chars.peek() {
+        None => Ok(value),
+        Some(_) => Err(parser.error("unexpected characters after the document")),
+    }
+}
+
+struct JsonParser<'a> {
+    chars: Peekable<Chars<'a>>,
+    line: usize,
+}
+
+impl<'a> JsonParser<'a> {
+    fn error(&self, message: &str) -> JsonError {
+        JsonError {
+            line: self.line,
+            message: message.to_string(),
+        }
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.chars.next();
+
+        if c == Some('\n') {
+            self.line += 1;
+        }
+
+        c
+    }
+
+    fn skip_whitespace(&mut self) -> Result<(), JsonError> {
+        while let Some(&c) = self.chars.peek() {
+            if c.is_whitespace() {
+                self.next();
+            } else if c == '/' {
+                self.next();
+
+                if self.chars.peek() != Some(&'/') {
+                    return Err(self.error("expected '/' to start a comment"));
+                }
+
+                while !matches!(self.chars.peek(), None | Some('\n')) {
+                    self.next();
+                }
+            } else {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), JsonError> {
+        self.skip_whitespace()?;
+
+        match self.next() {
+            Some(c) if c == expected => Ok(()),
+            _ => Err(self.error(&format!("expected '{}'", expected))),
+        }
+    }
+
+    fn value(&mut self) -> Result<JsonValue, JsonError> {
+        self.skip_whitespace()?;
+
+        let line = self.line;
+
+        let kind = match self.chars.peek() {
+            Some('{') => self.object()?,
+            Some('[') => self.array()?,
+            Some('"') => JsonKind::String(self.string()?),
+            Some(c) if *c == '-' || c.is_ascii_digit() => self.number()?,
+            Some(c) if c.is_ascii_alphabetic() => self.literal()?,
+            Some(_) => return Err(self.error("expected a value")),
+            None => return Err(self.error("unexpected end of file")),
+        };
+
+        Ok(JsonValue { line, kind })
+    }
+
+    fn object(&mut self) -> Result<JsonKind, JsonError> {
+        self.expect('{')?;
+
+        let mut fields = Vec::new();
+
+        self.skip_whitespace()?;
+
+        if self.chars.peek() == Some(&'}') {
+            self.next();
+
+            return Ok(JsonKind::Object(fields));
+        }
+
+        loop {
+            self.skip_whitespace()?;
+
+            if self.chars.peek() != Some(&'"') {
+                return Err(self.error("expected a field name"));
+            }
+
+            let key = self.string()?;
+
+            if fields.iter().any(|(field, _)| *field == key) {
+                return Err(self.error(&format!("duplicated field {}", key)));
+            }
+
+            self.expect(':')?;
+            fields.push((key, self.value()?));
+
+            self.skip_whitespace()?;
+
+            match self.next() {
+                Some(',') => continue,
+                Some('}') => return Ok(JsonKind::Object(fields)),
+                _ => return Err(self.error("expected ',' or '}'")),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<JsonKind, JsonError> {
+        self.expect('[')?;
+
+        let mut elements = Vec::new();
+
+        self.skip_whitespace()?;
+
+        if self.chars.peek() == Some(&']') {
+            self.next();
+
+            return Ok(JsonKind::Array(elements));
+        }
+
+        loop {
+            elements.push(self.value()?);
+
+            self.skip_whitespace()?;
+
+            match self.next() {
+                Some(',') => continue,
+                Some(']') => return Ok(JsonKind::Array(elements)),
+                _ => return Err(self.error("expected ',' or ']'")),
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, JsonError> {
+        self.expect('"')?;
+
+        let mut string = String::new();
+
+        loop {
+            match self.next() {
+                Some('"') => return Ok(string),
+                Some('\\') => {
+                    let c = match self.next() {
+                        Some('n') => '\n',
+                        Some('t') => '\t',
+                        Some('r') => '\r',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('u') => self.unicode_escape()?,
+                        Some(c @ ('"' | '\\' | '/')) => c,
+                        _ => return Err(self.error("invalid escape sequence")),
+                    };
+
+                    string.push(c);
+                }
+                Some('\n') | None => return Err(self.error("unterminated string")),
+                Some(c) => string.push(c),
+            }
+        }
+    }
+
+    fn unicode_escape(&mut self) -> Result<char, JsonError> {
+        let code_unit = self.code_unit()?;
+
+        // Characters outside the basic multilingual plane are escaped as a
+        // UTF-16 surrogate pair.
+        let code_point = if (0xd800..0xdc00).contains(&code_unit) {
+            if self.next() != Some('\\') || self.next() != Some('u') {
+                return Err(self.error("unpaired surrogate in unicode escape"));
+            }
+
+            let low_surrogate = self.code_unit()?;
+
+            if !(0xdc00..0xe000).contains(&low_surrogate) {
+                return Err(self.error("unpaired surrogate in unicode escape"));
+            }
+
+            0x10000 + ((code_unit - 0xd800) << 10) + (low_surrogate - 0xdc00)
+        } else {
+            code_unit
+        };
+
+        char::from_u32(code_point).ok_or_else(|| self.error("invalid unicode escape"))
+    }
+
+    /// Reads the four hexadecimal digits of a unicode escape.
+    fn code_unit(&mut self) -> Result<u32, JsonError> {
+        let digits: String = (0..4).filter_map(|_| self.next()).collect();
+
+        if digits.len() == 4 && digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            Ok(u32::from_str_radix(&digits, 16).expect("the digits are hexadecimal"))
+        } else {
+            Err(self.error("invalid unicode escape"))
+        }
+    }
+
+    fn number(&mut self) -> Result<JsonKind, JsonError> {
+        let mut number = String::new();
+
+        while let Some(&c) = self.chars.peek() {
+            if c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E') {
+                number.push(c);
+                self.next();
+            } else {
+                break;
+            }
+        }
+
+        number
+            .parse()
+            .map(JsonKind::Number)
+            .map_err(|_| self.error(&format!("invalid number {}", number)))
+    }
+
+    fn literal(&mut self) -> Result<JsonKind, JsonError> {
+        let mut literal = String::new();
+
+        while let Some(&c) = self.chars.peek() {
+            if c.is_ascii_alphabetic() {
+                literal.push(c);
+                self.next();
+            } else {
+                break;
+            }
+        }
+
+        match &literal[..] {
+            "true" => Ok(JsonKind::Bool(true)),
+            "false" => Ok(JsonKind::Bool(false)),
+            "null" => Ok(JsonKind::Null),
+            _ => Err(self.error(&format!("unexpected {}", literal))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_kind(source: &str) -> JsonKind {
+        parse_json(source).expect("the document is valid").kind
+    }
+
+    fn parse_error(source: &str) -> JsonError {
+        parse_json(source).expect_err("the document is invalid")
+    }
+
+    fn string(value: &str) -> JsonKind {
+        JsonKind::String(value.to_string())
+    }
+
+    #[test]
+    fn parses_literals_and_strings() {
+        assert_eq!(parse_kind("null"), JsonKind::Null);
+        assert_eq!(parse_kind(" true "), JsonKind::Bool(true));
+        assert_eq!(parse_kind("false"), JsonKind::Bool(false));
+        assert_eq!(parse_kind(r#""wood""#), string("wood"));
+        assert_eq!(parse_kind(r#""""#), string(""));
+    }
+
+    #[test]
+    fn parses_escape_sequences() {
+        assert_eq!(
+            parse_kind(r#""\"\\\/\b\f\n\r\t""#),
+            string("\"\\/\u{8}\u{c}\n\r\t")
+        );
+        assert_eq!(parse_kind(r#""\u0041\u00e9\u00C9""#), string("AéÉ"));
+        assert_eq!(parse_kind(r#""\ud83d\ude00""#), string("\u{1f600}"));
+
+        for source in &[
+            r#""\x""#,
+            r#""\u12""#,
+            r#""\u12g4""#,
+            r#""\ud83d""#,
+            r#""\ud83dA""#,
+            r#""\ude00""#,
+        ] {
+            parse_error(source);
+        }
+    }
+
+    #[test]
+    fn parses_numbers() {
+        assert_eq!(parse_kind("0"), JsonKind::Number(0.0));
+        assert_eq!(parse_kind("42"), JsonKind::Number(42.0));
+        assert_eq!(parse_kind("-0.5"), JsonKind::Number(-0.5));
+        assert_eq!(parse_kind("1e3"), JsonKind::Number(1000.0));
+        assert_eq!(parse_kind("2.5E-2"), JsonKind::Number(0.025));
+        assert_eq!(parse_kind("6.02e+23"), JsonKind::Number(6.02e23));
+
+        assert_eq!(parse_error("1.2.3").message, "invalid number 1.2.3");
+        assert_eq!(parse_error("-").message, "invalid number -");
+        assert_eq!(parse_error("1e").message, "invalid number 1e");
+        assert_eq!(parse_error("+1").message, "expected a value");
+    }
+
+    #[test]
+    fn parses_arrays_and_objects_with_lines() {
+        let value = parse_json("{\n  \"a\": [1,\n    true],\n  \"b\": {}\n}").unwrap();
+
+        assert_eq!(value.line, 1);
+
+        let fields = match value.kind {
+            JsonKind::Object(fields) => fields,
+            kind => panic!("expected an object, got {:?}", kind),
+        };
+
+        assert_eq!(fields.len(), 2);
+        assert_eq!(fields[0].0, "a");
+        assert_eq!(fields[0].1.line, 2);
+        assert_eq!(
+            fields[0].1.kind,
+            JsonKind::Array(vec![
+                JsonValue {
+                    line: 2,
+                    kind: JsonKind::Number(1.0)
+                },
+                JsonValue {
+                    line: 3,
+                    kind: JsonKind::Bool(true)
+                },
+            ])
+        );
+        assert_eq!(fields[1].0, "b");
+        assert_eq!(
+            fields[1].1,
+            JsonValue {
+                line: 4,
+                kind: JsonKind::Object(Vec::new())
+            }
+        );
+        assert_eq!(parse_kind("[]"), JsonKind::Array(Vec::new()));
+    }
+
+    #[test]
+    fn skips_line_comments() {
+        assert_eq!(
+            parse_kind("// scene\n[1, // first\n2]// end"),
+            parse_kind("\n[1,\n2]")
+        );
+
+        let error = parse_error("[1,\n 2 /x]");
+
+        assert_eq!(error.line, 2);
+        assert_eq!(error.message, "expected '/' to start a comment");
+    }
+
+    #[test]
+    fn rejects_duplicated_fields() {
+        let error = parse_error("{\n\"a\": 1,\n\"a\": 2\n}");
+
+        assert_eq!(error.line, 3);
+        assert_eq!(error.message, "duplicated field a");
+    }
+
+    #[test]
+    fn rejects_trailing_characters() {
+        let error = parse_error("{}\n\n]");
+
+        assert_eq!(error.line, 3);
+        assert_eq!(error.message, "unexpected characters after the document");
+
+        assert_eq!(
+            parse_error("1 2").message,
+            "unexpected characters after the document"
+        );
+    }
+
+    #[test]
+    fn reports_lines_of_syntax_errors() {
+        let cases = [
+            ("", 1, "unexpected end of file"),
+            ("[1,\n2,\n]", 3, "expected a value"),
+            ("[1\n2]", 2, "expected ',' or ']'"),
+            ("{\"a\" 1}", 1, "expected ':'"),
+            ("{\n\"a\": 1\n\"b\": 2}", 3, "expected ',' or '}'"),
+            ("{\n1: 2}", 2, "expected a field name"),
+            ("\n\n\"abc\ndef\"", 4, "unterminated string"),
+            ("[\ntru]", 2, "unexpected tru"),
+        ];
+
+        for (source, line, message) in cases.iter() {
+            let error = parse_error(source);
+
+            assert_eq!(
+                (error.line, &error.message[..]),
+                (*line, *message),
+                "{:?}",
+                source
+            );
+        }
+    }
+}
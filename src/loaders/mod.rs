@@ -0,0 +1,7 @@
+mod error;
+mod json;
+mod mtl;
+mod obj;
+mod scene_file;
+
+pub use self::{error::*, json::*, mtl::*, obj::*, scene_file::*};
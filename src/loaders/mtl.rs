@@ -0,0 +1,367 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::{
+    loaders::LoadError,
+    materials::{Dielectric, DiffuseLight, Lambertian, Material, Metal},
+    rgb_color::{RgbColor, BLACK, WHITE},
+    textures::{ImageTexture, SolidColor, Texture},
+};
+
+const DEFAULT_DIFFUSE_COLOR: RgbColor = RgbColor::new(0.8, 0.8, 0.8);
+
+/// The subset of MTL statements that can be mapped onto the materials of
+/// the tracer.
+#[derive(Clone, Debug)]
+pub struct MtlMaterial {
+    pub name: String,
+    pub diffuse: RgbColor,
+    pub specular: RgbColor,
+    pub emissive: RgbColor,
+    pub transmission_filter: Option<RgbColor>,
+    pub specular_exponent: f32,
+    pub refractive_index: f32,
+    pub dissolve: f32,
+    pub illumination_model: u32,
+    pub diffuse_map: Option<String>,
+}
+
+impl MtlMaterial {
+    pub fn new(name: String) -> Self {
+        Self {
+            name,
+            diffuse: DEFAULT_DIFFUSE_COLOR,
+            specular: BLACK,
+            emissive: BLACK,
+            transmission_filter: None,
+            specular_exponent: 0.0,
+            refractive_index: 1.0,
+            dissolve: 1.0,
+            illumination_model: 2,
+            diffuse_map: None,
+        }
+    }
+
+    pub fn is_emissive(&self) -> bool {
+        max_component(self.emissive) > 0.0
+    }
+
+    fn is_transparent(&self) -> bool {
+        self.dissolve < 1.0 || matches!(self.illumination_model, 4 | 6 | 7 | 9)
+    }
+
+    fn is_metallic(&self) -> bool {
+        self.illumination_model == 3
+            || (self.diffuse_map.is_none()
+                && max_component(self.specular) > max_component(self.diffuse))
+    }
+
+    /// Maps the material onto the closest material of the tracer. Texture
+    /// paths are resolved relative to `directory`.
+    pub fn to_material(&self, directory: &Path) -> Result<Material, LoadError> {
+        let material = if self.is_emissive() {
+            Material::Emissive(Arc::new(DiffuseLight::from_rgb_color(self.emissive)))
+        } else if self.is_transparent() {
+            Material::Reflective(Arc::new(Dielectric::new(
+                self.refractive_index,
+                self.transmission_filter.unwrap_or(WHITE),
+            )))
+        } else if self.is_metallic() {
+            Material::Reflective(Arc::new(Metal::new(
+                self.specular,
+                phong_roughness(self.specular_exponent),
+            )))
+        } else {
+            let texture: Arc<dyn Texture> = match &self.diffuse_map {
+                Some(file_name) => {
+                    let path = directory.join(file_name);
+
+                    Arc::new(
+                        ImageTexture::open(&path)
+                            .map_err(|error| LoadError::image(&path, error))?,
+                    )
+                }
+                None => Arc::new(SolidColor::from_rgb_color(self.diffuse)),
+            };
+
+            Material::Reflective(Arc::new(Lambertian::new(texture)))
+        };
+
+        Ok(material)
+    }
+}
+
+pub fn load_mtl(path: &Path) -> Result<HashMap<String, MtlMaterial>, LoadError> {
+    let source = fs::read_to_string(path).map_err(|error| LoadError::io(path, error))?;
+
+    let mut materials = HashMap::new();
+    let mut current: Option<MtlMaterial> = None;
+
+    for (index, line) in source.lines().enumerate() {
+        let line_number = index + 1;
+        let error = |message: &str| LoadError::parse(path, line_number, message);
+
+        let line = line.split('#').next().unwrap_or("").trim();
+        let mut tokens = line.split_whitespace();
+
+        let keyword = match tokens.next() {
+            Some(keyword) => keyword,
+            None => continue,
+        };
+        let arguments: Vec<_> = tokens.collect();
+
+        if keyword == "newmtl" {
+            if let Some(material) = current.take() {
+                materials.insert(material.name.clone(), material);
+            }
+
+            current = Some(MtlMaterial::new(arguments.join(" ")));
+
+            continue;
+        }
+
+        let material = match current.as_mut() {
+            Some(material) => material,
+            None => return Err(error("statement before the first newmtl")),
+        };
+
+        match keyword {
+            "Kd" => {
+                material.diffuse = parse_color(&arguments).ok_or_else(|| error("invalid Kd"))?
+            }
+            "Ks" => {
+                material.specular = parse_color(&arguments).ok_or_else(|| error("invalid Ks"))?
+            }
+            "Ke" => {
+                material.emissive = parse_color(&arguments).ok_or_else(|| error("invalid Ke"))?
+            }
+            "Tf" => {
+                material.transmission_filter =
+                    Some(parse_color(&arguments).ok_or_else(|| error("invalid Tf"))?)
+            }
+            "Ns" => {
+                material.specular_exponent =
+                    parse_number(&arguments).ok_or_else(|| error("invalid Ns"))?
+            }
+            "Ni" => {
+                material.refractive_index =
+                    parse_number(&arguments).ok_or_else(|| error("invalid Ni"))?
+            }
+            "d" => {
+                material.dissolve = parse_number(&arguments).ok_or_else(|| error("invalid d"))?
+            }
+            "Tr" => {
+                material.dissolve =
+                    1.0 - parse_number(&arguments).ok_or_else(|| error("invalid Tr"))?
+            }
+            "illum" => {
+                material.illumination_model = arguments
+                    .first()
+                    .and_then(|argument| argument.parse().ok())
+                    .ok_or_else(|| error("invalid illum"))?
+            }
+            // Map options such as `-bm 1.0` come before the file name.
+            "map_Kd" => {
+                material.diffuse_map = Some(
+                    arguments
+                        .last()
+                        .ok_or_else(|| error("map_Kd without a file name"))?
+                        .to_string(),
+                )
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(material) = current.take() {
+        materials.insert(material.name.clone(), material);
+    }
+
+    Ok(materials)
+}
+
+fn parse_color(arguments: &[&str]) -> Option<RgbColor> {
+    let values = arguments
+        .iter()
+        .map(|argument| argument.parse::<f32>().ok())
+        .collect::<Option<Vec<_>>>()?;
+
+    // A single value is used for all channels.
+    match values[..] {
+        [value] => Some(RgbColor::new(value, value, value)),
+        [red, green, blue] => Some(RgbColor::new(red, green, blue)),
+        _ => None,
+    }
+}
+
+fn parse_number(arguments: &[&str]) -> Option<f32> {
+    match arguments {
+        [argument] => argument.parse().ok(),
+        _ => None,
+    }
+}
+
+/// Perceptual GGX roughness of a Phong exponent, whose lobe has about the
+/// width of a microfacet distribution with an alpha of `sqrt(2 / (n + 2))`,
+/// see Walter et al. "Microfacet Models for Refraction through Rough
+/// Surfaces".
+fn phong_roughness(exponent: f32) -> f32 {
+    let alpha = f32::sqrt(2.0 / (f32::max(exponent, 0.0) + 2.0));
+
+    alpha.sqrt()
+}
+
+fn max_component(color: RgbColor) -> f32 {
+    color.red.max(color.green).max(color.blue)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::helpers::tests::TemporaryDirectory;
+
+    fn load(directory: &Path, source: &str) -> Result<HashMap<String, MtlMaterial>, LoadError> {
+        let path = directory.join("materials.mtl");
+
+        fs::write(&path, source).unwrap();
+
+        load_mtl(&path)
+    }
+
+    const MATERIALS: &str = "\
+# Exported materials
+newmtl matte
+Kd 0.5 0.25 0.125
+map_Kd -bm 1.0 bricks.png
+
+newmtl chrome
+Kd 0.1 0.1 0.1
+Ks 0.9
+Ns 1000
+
+newmtl glass
+Ni 1.5
+Tf 0.8 0.9 1
+illum 7
+
+newmtl frosted
+d 0.25
+
+newmtl lamp
+Ke 4 3 2
+";
+
+    #[test]
+    fn reads_the_statements_of_every_material() {
+        let directory = TemporaryDirectory::new("mtl-statements");
+        let materials =
+            load(directory.path(), MATERIALS).unwrap_or_else(|error| panic!("{}", error));
+
+        assert_eq!(materials.len(), 5);
+
+        let matte = &materials["matte"];
+
+        assert_eq!(matte.diffuse, RgbColor::new(0.5, 0.25, 0.125));
+        assert_eq!(matte.diffuse_map.as_deref(), Some("bricks.png"));
+
+        let chrome = &materials["chrome"];
+
+        assert_eq!(chrome.specular, RgbColor::new(0.9, 0.9, 0.9));
+        assert_eq!(chrome.specular_exponent, 1000.0);
+
+        let glass = &materials["glass"];
+
+        assert_eq!(glass.refractive_index, 1.5);
+        assert_eq!(
+            glass.transmission_filter,
+            Some(RgbColor::new(0.8, 0.9, 1.0))
+        );
+        assert_eq!(glass.illumination_model, 7);
+
+        assert_eq!(materials["frosted"].dissolve, 0.25);
+        assert_eq!(materials["lamp"].emissive, RgbColor::new(4.0, 3.0, 2.0));
+        assert_eq!(materials["lamp"].diffuse, DEFAULT_DIFFUSE_COLOR);
+    }
+
+    #[test]
+    fn maps_statements_onto_materials() {
+        let directory = TemporaryDirectory::new("mtl-mapping");
+        let materials =
+            load(directory.path(), MATERIALS).unwrap_or_else(|error| panic!("{}", error));
+
+        let kind = |name: &str| {
+            let material = &materials[name];
+
+            (
+                material.is_emissive(),
+                material.is_transparent(),
+                material.is_metallic(),
+            )
+        };
+
+        assert_eq!(kind("matte"), (false, false, false));
+        assert_eq!(kind("chrome"), (false, false, true));
+        assert_eq!(kind("glass"), (false, true, false));
+        assert_eq!(kind("frosted"), (false, true, false));
+        assert_eq!(kind("lamp"), (true, false, false));
+
+        for name in &["chrome", "glass", "frosted"] {
+            assert!(matches!(
+                materials[*name].to_material(directory.path()),
+                Ok(Material::Reflective(_))
+            ));
+        }
+
+        assert!(matches!(
+            materials["lamp"].to_material(directory.path()),
+            Ok(Material::Emissive(_))
+        ));
+    }
+
+    #[test]
+    fn maps_phong_exponents_onto_roughnesses() {
+        assert_eq!(phong_roughness(0.0), 1.0);
+        assert!((phong_roughness(1000.0).powi(2) - f32::sqrt(2.0 / 1002.0)).abs() < 1e-6);
+        assert!(phong_roughness(10.0) > phong_roughness(100.0));
+    }
+
+    #[test]
+    fn opens_the_diffuse_map_relative_to_the_directory() {
+        let directory = TemporaryDirectory::new("mtl-maps");
+        let materials =
+            load(directory.path(), MATERIALS).unwrap_or_else(|error| panic!("{}", error));
+
+        match materials["matte"].to_material(directory.path()) {
+            Err(LoadError::Image { path, .. }) => {
+                assert_eq!(path, directory.path().join("bricks.png"))
+            }
+            _ => panic!("expected an image error"),
+        }
+
+        image::RgbImage::new(1, 1)
+            .save(directory.path().join("bricks.png"))
+            .unwrap();
+
+        assert!(matches!(
+            materials["matte"].to_material(directory.path()),
+            Ok(Material::Reflective(_))
+        ));
+    }
+
+    #[test]
+    fn reports_invalid_statements() {
+        let directory = TemporaryDirectory::new("mtl-errors");
+        let error = |source: &str| match load(directory.path(), source) {
+            Ok(_) => panic!("expected an error loading {}", source),
+            Err(error) => error.to_string(),
+        };
+
+        assert!(error("Kd 1 1 1").ends_with("materials.mtl:1: statement before the first newmtl"));
+        assert!(error("newmtl a\nKd 1 1").ends_with("materials.mtl:2: invalid Kd"));
+        assert!(error("newmtl a\n\nNi").ends_with("materials.mtl:3: invalid Ni"));
+        assert!(error("newmtl a\nmap_Kd").ends_with("materials.mtl:2: map_Kd without a file name"));
+    }
+}
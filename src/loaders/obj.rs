@@ -0,0 +1,499 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::{
+    loaders::{load_mtl, LoadError, MtlMaterial},
+    materials::{Lambertian, Material},
+    objects::{Bvh, MeshBuffers, MeshFace, Object, TriangleMesh},
+    vec3::{Point3, Vec3},
+};
+
+const DEFAULT_GROUP: &str = "default";
+
+#[derive(Clone)]
+pub struct ObjModel {
+    pub object: Arc<dyn Object>,
+    /// The emissive parts of the model, to be added to the lights of a scene.
+    pub lights: Vec<Arc<dyn Object>>,
+}
+
+struct ObjPart {
+    material: Option<String>,
+    faces: Vec<MeshFace>,
+}
+
+struct ObjFile {
+    buffers: MeshBuffers,
+    parts: Vec<ObjPart>,
+    mtl_materials: HashMap<String, MtlMaterial>,
+}
+
+/// Loads the triangles of a Wavefront OBJ file and the materials of the MTL
+/// libraries it references. Every group and material combination becomes a
+/// `TriangleMesh`, all of them sharing the same vertex buffers.
+pub fn load_obj<P: AsRef<Path>>(path: P) -> Result<ObjModel, LoadError> {
+    let path = path.as_ref();
+    let directory = path.parent().unwrap_or_else(|| Path::new(""));
+
+    let source = fs::read_to_string(path).map_err(|error| LoadError::io(path, error))?;
+
+    let ObjFile {
+        buffers,
+        parts,
+        mtl_materials,
+    } = parse_obj(path, &source)?;
+
+    let buffers = Arc::new(buffers);
+    let mut materials: HashMap<Option<String>, Arc<Material>> = HashMap::new();
+
+    let mut meshes = Vec::<Arc<dyn Object>>::new();
+    let mut lights = Vec::<Arc<dyn Object>>::new();
+
+    for part in parts.into_iter() {
+        let mtl_material = part.material.as_ref().map(|name| &mtl_materials[name]);
+
+        let material = match materials.get(&part.material) {
+            Some(material) => Arc::clone(material),
+            None => {
+                let material = Arc::new(match mtl_material {
+                    Some(mtl_material) => mtl_material.to_material(directory)?,
+                    None => Material::Reflective(Arc::new(Lambertian::from_rgb(0.8, 0.8, 0.8))),
+                });
+
+                materials.insert(part.material.clone(), Arc::clone(&material));
+
+                material
+            }
+        };
+
+        let mesh: Arc<dyn Object> = Arc::new(TriangleMesh::new(
+            Arc::clone(&buffers),
+            part.faces,
+            material,
+        ));
+
+        if mtl_material.is_some_and(|mtl_material| mtl_material.is_emissive()) {
+            lights.push(Arc::clone(&mesh));
+        }
+
+        meshes.push(mesh);
+    }
+
+    let object = if meshes.len() == 1 {
+        meshes.remove(0)
+    } else {
+        Arc::new(Bvh::new(meshes, 0.0, 0.0))
+    };
+
+    Ok(ObjModel { object, lights })
+}
+
+/// Reads the vertices and faces of an OBJ file, along with the MTL
+/// libraries it references, which are resolved relative to `path`.
+fn parse_obj(path: &Path, source: &str) -> Result<ObjFile, LoadError> {
+    let directory = path.parent().unwrap_or_else(|| Path::new(""));
+
+    let mut buffers = MeshBuffers::default();
+    let mut mtl_materials: HashMap<String, MtlMaterial> = HashMap::new();
+
+    let mut parts: Vec<ObjPart> = Vec::new();
+    let mut part_indices: HashMap<(String, Option<String>), usize> = HashMap::new();
+
+    let mut group = String::from(DEFAULT_GROUP);
+    let mut material: Option<String> = None;
+
+    for (index, line) in source.lines().enumerate() {
+        let line_number = index + 1;
+        let error = |message: &str| LoadError::parse(path, line_number, message);
+
+        let line = line.split('#').next().unwrap_or("").trim();
+        let mut tokens = line.split_whitespace();
+
+        let keyword = match tokens.next() {
+            Some(keyword) => keyword,
+            None => continue,
+        };
+        let arguments: Vec<_> = tokens.collect();
+
+        match keyword {
+            "v" => {
+                let [x, y, z] = parse_floats(&arguments[..usize::min(3, arguments.len())])
+                    .ok_or_else(|| error("invalid vertex position"))?;
+
+                buffers.positions.push(Point3::new(x, y, z));
+            }
+            "vn" => {
+                let [x, y, z] =
+                    parse_floats(&arguments).ok_or_else(|| error("invalid vertex normal"))?;
+                let normal = Vec3::new(x, y, z);
+
+                // Normals without a direction would shade with NaNs.
+                if !(normal.len_squared() > 0.0 && normal.len_squared().is_finite()) {
+                    return Err(error("vertex normal without a direction"));
+                }
+
+                buffers.normals.push(normal.unit());
+            }
+            "vt" => {
+                let u = arguments.first().and_then(|argument| argument.parse().ok());
+                let v = arguments
+                    .get(1)
+                    .map_or(Some(0.0), |argument| argument.parse().ok());
+
+                match (u, v) {
+                    (Some(u), Some(v)) => buffers.texture_coordinates.push((u, v)),
+                    _ => return Err(error("invalid texture coordinates")),
+                }
+            }
+            "f" => {
+                let vertices = arguments
+                    .iter()
+                    .map(|argument| parse_face_vertex(argument, &buffers))
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or_else(|| error("invalid face vertex"))?;
+
+                if vertices.len() < 3 {
+                    return Err(error("face with less than three vertices"));
+                }
+
+                let key = (group.clone(), material.clone());
+                let part_index = *part_indices.entry(key).or_insert_with(|| {
+                    parts.push(ObjPart {
+                        material: material.clone(),
+                        faces: Vec::new(),
+                    });
+
+                    parts.len() - 1
+                });
+
+                triangulate(&vertices, &mut parts[part_index].faces);
+            }
+            "g" | "o" => {
+                group = if arguments.is_empty() {
+                    String::from(DEFAULT_GROUP)
+                } else {
+                    arguments.join(" ")
+                };
+            }
+            "usemtl" => {
+                let name = arguments.join(" ");
+
+                if !mtl_materials.contains_key(&name) {
+                    return Err(error(&format!("unknown material {}", name)));
+                }
+
+                material = Some(name);
+            }
+            "mtllib" => {
+                for file_name in arguments.iter() {
+                    mtl_materials.extend(load_mtl(&directory.join(file_name))?);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if parts.iter().all(|part| part.faces.is_empty()) {
+        return Err(LoadError::parse(path, source.lines().count(), "no faces"));
+    }
+
+    Ok(ObjFile {
+        buffers,
+        parts,
+        mtl_materials,
+    })
+}
+
+fn parse_floats(arguments: &[&str]) -> Option<[f32; 3]> {
+    match arguments {
+        [x, y, z] => Some([x.parse().ok()?, y.parse().ok()?, z.parse().ok()?]),
+        _ => None,
+    }
+}
+
+type FaceVertex = (usize, Option<usize>, Option<usize>);
+
+/// Parses a `v`, `v/vt`, `v//vn` or `v/vt/vn` face vertex into indices of
+/// the position, texture coordinates and normal buffers.
+fn parse_face_vertex(argument: &str, buffers: &MeshBuffers) -> Option<FaceVertex> {
+    let mut indices = argument.split('/');
+
+    let position = resolve_index(indices.next()?, buffers.positions.len())?;
+
+    let texture_coordinates = match indices.next() {
+        None | Some("") => None,
+        Some(index) => Some(resolve_index(index, buffers.texture_coordinates.len())?),
+    };
+
+    let normal = match indices.next() {
+        None | Some("") => None,
+        Some(index) => Some(resolve_index(index, buffers.normals.len())?),
+    };
+
+    Some((position, texture_coordinates, normal))
+}
+
+/// OBJ indices start at one, and negative indices are relative to the end
+/// of the elements read so far.
+fn resolve_index(index: &str, len: usize) -> Option<usize> {
+    let index: isize = index.parse().ok()?;
+
+    let resolved = if index > 0 {
+        index - 1
+    } else {
+        len as isize + index
+    };
+
+    if resolved >= 0 && (resolved as usize) < len {
+        Some(resolved as usize)
+    } else {
+        None
+    }
+}
+
+/// Splits a polygon into a fan of triangles around its first vertex.
+fn triangulate(vertices: &[FaceVertex], faces: &mut Vec<MeshFace>) {
+    for i in 1..(vertices.len() - 1) {
+        let triangle = [vertices[0], vertices[i], vertices[i + 1]];
+
+        let positions = [triangle[0].0, triangle[1].0, triangle[2].0];
+
+        let texture_coordinates = match (triangle[0].1, triangle[1].1, triangle[2].1) {
+            (Some(uv0), Some(uv1), Some(uv2)) => Some([uv0, uv1, uv2]),
+            _ => None,
+        };
+
+        let normals = match (triangle[0].2, triangle[1].2, triangle[2].2) {
+            (Some(n0), Some(n1), Some(n2)) => Some([n0, n1, n2]),
+            _ => None,
+        };
+
+        faces.push(MeshFace::new(positions, normals, texture_coordinates));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        helpers::tests::TemporaryDirectory,
+        ray::{Ray, MAX_T},
+    };
+
+    fn parse(source: &str) -> Result<ObjFile, LoadError> {
+        parse_obj(Path::new("model.obj"), source)
+    }
+
+    fn faces(source: &str) -> Vec<MeshFace> {
+        let file = parse(source).unwrap_or_else(|error| panic!("{}", error));
+
+        file.parts
+            .into_iter()
+            .flat_map(|part| part.faces.into_iter())
+            .collect()
+    }
+
+    fn parse_error(source: &str) -> String {
+        match parse(source) {
+            Ok(_) => panic!("expected an error parsing {}", source),
+            Err(error) => error.to_string(),
+        }
+    }
+
+    /// Writes files to a new directory of their own.
+    fn write_files(name: &str, files: &[(&str, &str)]) -> TemporaryDirectory {
+        let directory = TemporaryDirectory::new(&format!("obj-{}", name));
+
+        for (file_name, contents) in files.iter() {
+            fs::write(directory.path().join(file_name), contents).unwrap();
+        }
+
+        directory
+    }
+
+    const SQUARE: &str = "\
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+vt 0 0
+vt 1 0
+vt 1 1
+vn 0 0 1
+vn 0 0 2
+";
+
+    #[test]
+    fn parses_every_face_vertex_form() {
+        let faces = faces(&format!(
+            "{}f 1/1/1 2/2/1 3/3/1\nf 1//2 2//2 3//2\nf 1/1 2/2 3/3\nf 1 2 3\n",
+            SQUARE
+        ));
+
+        assert_eq!(
+            faces,
+            vec![
+                MeshFace::new([0, 1, 2], Some([0, 0, 0]), Some([0, 1, 2])),
+                MeshFace::new([0, 1, 2], Some([1, 1, 1]), None),
+                MeshFace::new([0, 1, 2], None, Some([0, 1, 2])),
+                MeshFace::new([0, 1, 2], None, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn resolves_negative_indices_against_the_elements_read_so_far() {
+        let faces = faces(&format!(
+            "{}f -4/-3/-2 -3/-2/-2 -2/-1/-1\nv 2 2 0\nf -1 -2 -3\n",
+            SQUARE
+        ));
+
+        assert_eq!(
+            faces,
+            vec![
+                MeshFace::new([0, 1, 2], Some([0, 0, 1]), Some([0, 1, 2])),
+                MeshFace::new([4, 3, 2], None, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_indices_out_of_range() {
+        let source = |face: &str| format!("{}{}\n", SQUARE, face);
+
+        assert_eq!(
+            parse_error(&source("f 1 2 5")),
+            "model.obj:10: invalid face vertex"
+        );
+        assert_eq!(
+            parse_error(&source("f 0 1 2")),
+            "model.obj:10: invalid face vertex"
+        );
+        assert_eq!(
+            parse_error(&source("f -5 1 2")),
+            "model.obj:10: invalid face vertex"
+        );
+        assert_eq!(
+            parse_error(&source("f 1/4 2/1 3/1")),
+            "model.obj:10: invalid face vertex"
+        );
+        assert_eq!(
+            parse_error(&source("f 1 2")),
+            "model.obj:10: face with less than three vertices"
+        );
+        assert_eq!(parse_error(SQUARE), "model.obj:9: no faces");
+    }
+
+    #[test]
+    fn rejects_vertex_normals_without_a_direction() {
+        for normal in &["0 0 0", "0 -0 0", "nan 0 1", "inf 0 0"] {
+            assert_eq!(
+                parse_error(&format!("{}vn {}\n", SQUARE, normal)),
+                "model.obj:10: vertex normal without a direction"
+            );
+        }
+
+        assert_eq!(
+            parse_error(&format!("{}vn 0 0\n", SQUARE)),
+            "model.obj:10: invalid vertex normal"
+        );
+    }
+
+    #[test]
+    fn triangulates_polygons_as_fans() {
+        let positions = |source: &str| -> Vec<[usize; 3]> {
+            faces(source).iter().map(|face| face.positions).collect()
+        };
+
+        assert_eq!(
+            positions(&format!("{}f 1 2 3 4\n", SQUARE)),
+            vec![[0, 1, 2], [0, 2, 3]]
+        );
+        assert_eq!(
+            positions(&format!("{}v 0.5 2 0\nf 1 2 3 5 4\n", SQUARE)),
+            vec![[0, 1, 2], [0, 2, 4], [0, 4, 3]]
+        );
+    }
+
+    #[test]
+    fn splits_parts_by_group_and_material() {
+        let directory = write_files(
+            "parts",
+            &[(
+                "materials.mtl",
+                "newmtl red\nKd 1 0 0\nnewmtl green\nKd 0 1 0\n",
+            )],
+        );
+
+        let file = parse_obj(
+            &directory.path().join("model.obj"),
+            &format!(
+                "mtllib materials.mtl\n{}f 1 2 3\nusemtl red\nf 1 2 3\ng lid\nf 1 2 3 4\nusemtl green\nf 1 2 3\ng\nusemtl red\nf 1 3 4\n",
+                SQUARE
+            ),
+        )
+        .unwrap_or_else(|error| panic!("{}", error));
+
+        let parts: Vec<_> = file
+            .parts
+            .iter()
+            .map(|part| (part.material.as_deref(), part.faces.len()))
+            .collect();
+
+        assert_eq!(
+            parts,
+            vec![
+                (None, 1),
+                (Some("red"), 2),
+                (Some("red"), 2),
+                (Some("green"), 1),
+            ]
+        );
+        assert_eq!(file.mtl_materials.len(), 2);
+
+        assert_eq!(
+            parse_error("usemtl red\nv 0 0 0"),
+            "model.obj:1: unknown material red"
+        );
+    }
+
+    #[test]
+    fn adds_emissive_parts_to_the_lights() {
+        let directory = write_files(
+            "lights",
+            &[
+                (
+                    "materials.mtl",
+                    "newmtl wall\nKd 0.5 0.5 0.5\n\nnewmtl lamp\nKd 0 0 0\nKe 4 4 4\n",
+                ),
+                (
+                    "model.obj",
+                    "mtllib materials.mtl\nv 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nusemtl wall\nf 1 2 3\nusemtl lamp\nf 1 3 4\n",
+                ),
+            ],
+        );
+
+        let model = load_obj(directory.path().join("model.obj"))
+            .unwrap_or_else(|error| panic!("{}", error));
+
+        assert_eq!(model.lights.len(), 1);
+
+        let ray = Ray::new(Point3::new(0.2, 0.8, -1.0), Vec3::new(0.0, 0.0, 1.0), 0.0);
+        let hit = model.lights[0].hit(&ray, MAX_T).expect("the lamp is hit");
+
+        assert!(matches!(hit.material.as_ref(), Material::Emissive(_)));
+
+        let hit = model
+            .object
+            .hit(
+                &ray.secondary(Point3::new(0.8, 0.2, -1.0), ray.direction),
+                MAX_T,
+            )
+            .expect("the wall is hit");
+
+        assert!(matches!(hit.material.as_ref(), Material::Reflective(_)));
+    }
+}
@@ -0,0 +1,1137 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::{
+    backgrounds::{Background, EnvironmentMap, Sky},
+    camera::Camera,
+    color_space::TransferFunction,
+    lights::{DeltaLight, DirectionalLight, PointLight, SpotLight},
+    loaders::{load_obj, parse_json, JsonKind, JsonValue, LoadError},
+    materials::{
+        ConductorFresnel, Dielectric, DiffuseLight, Lambertian, Material, Metal, MetalPreset,
+        Principled,
+    },
+    objects::{
+        Bvh, Object, ObjectList, RectangleBuilder, RectangularPrismBuilder, SphereBuilder,
+        TriangleBuilder,
+    },
+    ray::Ray,
+    rgb_color::{RgbColor, BLACK, WHITE},
+    scene::Scene,
+    textures::{
+        ImageTexture, Marble, Noise, SolidChecker, SolidColor, Texture, TextureFilter, UvChecker,
+        Wood, WrapMode, DEFAULT_TURBULENCE_DEPTH,
+    },
+    vec3::Vec3,
+};
+
+/// A camera and scene read from a scene file.
+#[derive(Clone)]
+pub struct SceneDescription {
+    pub camera: Camera,
+    pub scene: Scene,
+}
+
+/// Loads a scene file, a JSON document such as:
+///
+/// ```json
+/// {
+///     "camera": {
+///         "look_from": [0, 1, -5], "look_at": [0, 1, 0], "aspect_ratio": 1.5,
+///         "vertical_fov": 40, "time": 0, "shutter_speed": 1, "aperture": 0,
+///         "focus_distance": 5
+///     },
+///     "background": { "type": "environment", "path": "sky.hdr", "rotation": 90, "intensity": 2 },
+///     "textures": {
+///         "wood": { "type": "image", "path": "wood.png", "filter": "bilinear", "wrap": "mirror" },
+///         "tiles": { "type": "checker", "even": "wood", "odd": [0.1, 0.1, 0.1], "frequency": 8 },
+///         "stone": { "type": "marble", "seed": 7, "scale": 4 }
+///     },
+///     "materials": {
+///         "floor": { "type": "lambertian", "texture": "wood" },
+///         "glass": { "type": "dielectric", "refractive_index": 1.5 },
+///         "frosted_glass": { "type": "dielectric", "refractive_index": 1.5, "roughness": 0.2,
+///                            "absorption": [0.1, 0.4, 0.8] },
+///         "gold": { "type": "metal", "preset": "gold", "roughness": 0.3 },
+///         "varnished_wood": { "type": "principled", "base_color": "wood", "roughness": 0.6,
+///                             "clearcoat": 1 },
+///         "lamp": { "type": "diffuse_light", "color": [8, 8, 8] }
+///     },
+///     "objects": [
+///         { "type": "sphere", "center": [0, 1, 0], "radius": 1, "material": "glass",
+///           "acceleration": [0, -9.8, 0], "initial_velocity": [0, 2, 0] },
+///         { "type": "obj", "path": "model.obj" }
+///     ],
+///     "lights": [
+///         { "type": "spot", "position": [0, 4, 0], "direction": [0, -1, 0], "power": 800,
+///           "cone_angle": 30, "falloff_start": 20 },
+///         { "type": "directional", "direction": [1, -1, 0], "illuminance": 100000 }
+///     ]
+/// }
+/// ```
+///
+/// Objects with an emissive material are also added to the lights of the
+/// scene, unless they set `"light": false`. Point and spot lights take an
+/// `intensity` in candelas or a `power` in lumens, and directional lights an
+/// `illuminance` in lux. A `sky` background, with a `sun_direction` and a
+/// `turbidity`, also adds its sun as a directional light. Paths are
+/// relative to the directory of the scene file.
+pub fn load_scene<P: AsRef<Path>>(path: P) -> Result<SceneDescription, LoadError> {
+    let path = path.as_ref();
+    let source = fs::read_to_string(path).map_err(|error| LoadError::io(path, error))?;
+
+    parse_scene(path, &source)
+}
+
+/// Reads the source of a scene file, taking paths as relative to the
+/// directory of `path`.
+fn parse_scene(path: &Path, source: &str) -> Result<SceneDescription, LoadError> {
+    let document =
+        parse_json(source).map_err(|error| LoadError::parse(path, error.line, error.message))?;
+
+    SceneFileLoader::new(path).load(document)
+}
+
+struct SceneFileLoader {
+    path: PathBuf,
+    directory: PathBuf,
+    textures: HashMap<String, Arc<dyn Texture>>,
+    materials: HashMap<String, Arc<Material>>,
+}
+
+impl SceneFileLoader {
+    fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            directory: path.parent().unwrap_or_else(|| Path::new("")).to_path_buf(),
+            textures: HashMap::new(),
+            materials: HashMap::new(),
+        }
+    }
+
+    fn error<M: Into<String>>(&self, value: &JsonValue, message: M) -> LoadError {
+        LoadError::parse(&self.path, value.line, message)
+    }
+
+    fn load(mut self, document: JsonValue) -> Result<SceneDescription, LoadError> {
+        let mut fields = self.fields(document)?;
+
+        if let Some(textures) = fields.take("textures") {
+            for (name, texture) in self.named_values(textures)? {
+                let texture = self.texture(texture)?;
+
+                self.textures.insert(name, texture);
+            }
+        }
+
+        if let Some(materials) = fields.take("materials") {
+            for (name, material) in self.named_values(materials)? {
+                let material = self.material(material)?;
+
+                self.materials.insert(name, material);
+            }
+        }
+
+        let camera = self.camera(fields.require(&self, "camera")?)?;
+
+        let mut delta_lights = Vec::<Arc<dyn DeltaLight>>::new();
+
+        let background = match fields.take("background") {
+            Some(background) => self.background(background, &mut delta_lights)?,
+            None => Arc::new(|_ray: &Ray| BLACK),
+        };
+
+        let mut objects = Vec::<Arc<dyn Object>>::new();
+        let mut lights = Vec::<Arc<dyn Object>>::new();
+
+        for object in self.array(fields.require(&self, "objects")?)? {
+            self.object(object, &mut objects, &mut lights)?;
+        }
+
+        if let Some(values) = fields.take("lights") {
+            for value in self.array(values)? {
+                delta_lights.push(self.delta_light(value)?);
+            }
+        }
+
+        fields.finish(&self)?;
+
+        let scene = Scene::new(
+            Arc::new(Bvh::new(
+                objects,
+                camera.time,
+                camera.time + camera.shutter_speed,
+            )),
+            Arc::new(ObjectList::new(lights)),
+            background,
+        )
+        .with_delta_lights(delta_lights);
+
+        Ok(SceneDescription { camera, scene })
+    }
+
+    fn camera(&self, value: JsonValue) -> Result<Camera, LoadError> {
+        let mut fields = self.fields(value)?;
+
+        let look_from = self.vec3(fields.require(self, "look_from")?)?;
+        let look_at = self.vec3(fields.require(self, "look_at")?)?;
+        let focus_distance = match fields.take("focus_distance") {
+            Some(value) => self.number(value)?,
+            None => (look_at - look_from).len(),
+        };
+
+        let camera = Camera::new(
+            look_from,
+            look_at,
+            self.number(fields.require(self, "aspect_ratio")?)?,
+            self.number(fields.require(self, "vertical_fov")?)?,
+            self.optional_number(&mut fields, "time", 0.0)?,
+            self.optional_number(&mut fields, "shutter_speed", 0.0)?,
+            self.optional_number(&mut fields, "aperture", 0.0)?,
+            focus_distance,
+        );
+
+        fields.finish(self)?;
+
+        Ok(camera)
+    }
+
+    /// Reads the background, adding the sun of a sky to the delta lights.
+    fn background(
+        &self,
+        value: JsonValue,
+        delta_lights: &mut Vec<Arc<dyn DeltaLight>>,
+    ) -> Result<Arc<dyn Background>, LoadError> {
+        if let JsonKind::Array(_) = value.kind {
+            let color = self.color(value)?;
+
+            return Ok(Arc::new(move |_ray: &Ray| color));
+        }
+
+        let mut fields = self.fields(value)?;
+        let kind = fields.require(self, "type")?;
+
+        let background: Arc<dyn Background> = match &self.string(kind.clone())?[..] {
+            "gradient" => {
+                let bottom = self.color(fields.require(self, "bottom")?)?;
+                let top = self.color(fields.require(self, "top")?)?;
+
+                Arc::new(move |ray: &Ray| {
+                    let t = (ray.direction.unit().y + 1.0) / 2.0;
+
+                    (1.0 - t) * bottom + t * top
+                })
+            }
+            "environment" => {
+                let path = self.path(fields.require(self, "path")?)?;
+
+                let environment_map =
+                    EnvironmentMap::open(&path).map_err(|error| LoadError::image(&path, error))?;
+
+                Arc::new(
+                    environment_map
+                        .with_rotation(self.optional_number(&mut fields, "rotation", 0.0)?)
+                        .with_intensity(self.optional_number(&mut fields, "intensity", 1.0)?),
+                )
+            }
+            "sky" => {
+                let sun_direction = self.direction(fields.require(self, "sun_direction")?)?;
+                let turbidity = self.optional_number(&mut fields, "turbidity", 3.0)?;
+
+                let sky = Sky::new(sun_direction, turbidity).with_intensity(self.optional_number(
+                    &mut fields,
+                    "intensity",
+                    1.0,
+                )?);
+
+                if let Some(sun_light) = sky.sun_light() {
+                    delta_lights.push(Arc::new(sun_light));
+                }
+
+                Arc::new(sky)
+            }
+            other => return Err(self.error(&kind, format!("unknown background type {}", other))),
+        };
+
+        fields.finish(self)?;
+
+        Ok(background)
+    }
+
+    fn delta_light(&self, value: JsonValue) -> Result<Arc<dyn DeltaLight>, LoadError> {
+        let mut fields = self.fields(value)?;
+        let kind = fields.require(self, "type")?;
+
+        let color = self.optional_color(&mut fields, "color", WHITE)?;
+
+        let delta_light: Arc<dyn DeltaLight> = match &self.string(kind.clone())?[..] {
+            "point" => {
+                let position = self.vec3(fields.require(self, "position")?)?;
+
+                match self.intensity_or_power(&mut fields)? {
+                    LightAmount::Intensity(intensity) => {
+                        Arc::new(PointLight::new(position, color, intensity))
+                    }
+                    LightAmount::Power(power) => {
+                        Arc::new(PointLight::from_power(position, color, power))
+                    }
+                }
+            }
+            "spot" => {
+                let position = self.vec3(fields.require(self, "position")?)?;
+                let direction = self.direction(fields.require(self, "direction")?)?;
+                let cone_angle = self.number(fields.require(self, "cone_angle")?)?;
+                let falloff_start =
+                    self.optional_number(&mut fields, "falloff_start", cone_angle)?;
+
+                match self.intensity_or_power(&mut fields)? {
+                    LightAmount::Intensity(intensity) => Arc::new(SpotLight::new(
+                        position,
+                        direction,
+                        color,
+                        intensity,
+                        cone_angle,
+                        falloff_start,
+                    )),
+                    LightAmount::Power(power) => Arc::new(SpotLight::from_power(
+                        position,
+                        direction,
+                        color,
+                        power,
+                        cone_angle,
+                        falloff_start,
+                    )),
+                }
+            }
+            "directional" => {
+                let direction = self.direction(fields.require(self, "direction")?)?;
+                let illuminance = self.number(fields.require(self, "illuminance")?)?;
+
+                Arc::new(DirectionalLight::new(direction, color, illuminance))
+            }
+            other => return Err(self.error(&kind, format!("unknown light type {}", other))),
+        };
+
+        fields.finish(self)?;
+
+        Ok(delta_light)
+    }
+
+    /// Luminous intensity in candelas or power in lumens, whichever of the
+    /// two is given.
+    fn intensity_or_power(&self, fields: &mut Fields) -> Result<LightAmount, LoadError> {
+        match (fields.take("intensity"), fields.take("power")) {
+            (Some(intensity), None) => Ok(LightAmount::Intensity(self.number(intensity)?)),
+            (None, Some(power)) => Ok(LightAmount::Power(self.number(power)?)),
+            (Some(_), Some(power)) => {
+                Err(self.error(&power, "a light can't have both an intensity and a power"))
+            }
+            (None, None) => Err(LoadError::parse(
+                &self.path,
+                fields.line,
+                "missing field intensity or power",
+            )),
+        }
+    }
+
+    /// Reflectance of a metal, from an `albedo` color, a `preset` name or
+    /// the `eta` and `k` of its complex index of refraction.
+    fn conductor_fresnel(&self, fields: &mut Fields) -> Result<ConductorFresnel, LoadError> {
+        match (
+            fields.take("albedo"),
+            fields.take("preset"),
+            fields.take("eta"),
+        ) {
+            (Some(albedo), None, None) => Ok(ConductorFresnel::Schlick(self.color(albedo)?)),
+            (None, Some(preset), None) => {
+                let name = self.string(preset.clone())?;
+
+                let preset = MetalPreset::from_name(&name).ok_or_else(|| {
+                    self.error(
+                        &preset,
+                        format!(
+                            "unknown metal preset {}, expected one of: {}",
+                            name,
+                            MetalPreset::NAMES.join(", ")
+                        ),
+                    )
+                })?;
+
+                Ok(preset.fresnel())
+            }
+            (None, None, Some(eta)) => Ok(ConductorFresnel::Complex {
+                eta: self.color(eta)?,
+                k: self.color(fields.require(self, "k")?)?,
+            }),
+            (None, None, None) => Err(LoadError::parse(
+                &self.path,
+                fields.line,
+                "missing field albedo, preset or eta",
+            )),
+            _ => Err(LoadError::parse(
+                &self.path,
+                fields.line,
+                "only one of albedo, preset and eta can be set",
+            )),
+        }
+    }
+
+    fn texture(&self, value: JsonValue) -> Result<Arc<dyn Texture>, LoadError> {
+        let mut fields = self.fields(value)?;
+        let kind = fields.require(self, "type")?;
+
+        let texture: Arc<dyn Texture> = match &self.string(kind.clone())?[..] {
+            "solid" => Arc::new(SolidColor::from_rgb_color(
+                self.color(fields.require(self, "color")?)?,
+            )),
+            "image" => {
+                let path = self.path(fields.require(self, "path")?)?;
+
+                let transfer_function = self
+                    .optional_name(
+                        &mut fields,
+                        "color_space",
+                        "color space",
+                        TransferFunction::from_name,
+                        &TransferFunction::NAMES,
+                    )?
+                    .unwrap_or(TransferFunction::Srgb);
+
+                let mut texture =
+                    ImageTexture::open_with_transfer_function(&path, transfer_function)
+                        .map_err(|error| LoadError::image(&path, error))?;
+
+                if let Some(filter) = self.optional_name(
+                    &mut fields,
+                    "filter",
+                    "filter",
+                    TextureFilter::from_name,
+                    &TextureFilter::NAMES,
+                )? {
+                    texture = texture.with_filter(filter);
+                }
+
+                if let Some(wrap_mode) = self.optional_name(
+                    &mut fields,
+                    "wrap",
+                    "wrap mode",
+                    WrapMode::from_name,
+                    &WrapMode::NAMES,
+                )? {
+                    texture = texture.with_wrap_mode(wrap_mode);
+                }
+
+                Arc::new(texture)
+            }
+            "checker" => Arc::new(UvChecker::new(
+                self.texture_reference(fields.require(self, "even")?)?,
+                self.texture_reference(fields.require(self, "odd")?)?,
+                self.optional_number(&mut fields, "frequency", 10.0)?,
+            )),
+            "solid_checker" => Arc::new(SolidChecker::new(
+                self.texture_reference(fields.require(self, "even")?)?,
+                self.texture_reference(fields.require(self, "odd")?)?,
+                self.optional_number(&mut fields, "size", 1.0)?,
+            )),
+            "noise" => {
+                let mut noise = Noise::new(
+                    self.optional_seed(&mut fields)?,
+                    self.optional_color(&mut fields, "color", WHITE)?,
+                    self.optional_number(&mut fields, "scale", 1.0)?,
+                );
+                noise.turbulence_depth = self.optional_turbulence_depth(&mut fields)?;
+
+                Arc::new(noise)
+            }
+            "marble" => {
+                let mut marble = Marble::new(
+                    self.optional_seed(&mut fields)?,
+                    self.optional_color(&mut fields, "base", WHITE)?,
+                    self.optional_color(&mut fields, "vein", RgbColor::new(0.1, 0.1, 0.1))?,
+                    self.optional_number(&mut fields, "scale", 1.0)?,
+                );
+                marble.turbulence_depth = self.optional_turbulence_depth(&mut fields)?;
+                marble.distortion =
+                    self.optional_number(&mut fields, "distortion", marble.distortion)?;
+
+                Arc::new(marble)
+            }
+            "wood" => {
+                let mut wood = Wood::new(
+                    self.optional_seed(&mut fields)?,
+                    self.optional_color(&mut fields, "light", RgbColor::new(0.7, 0.5, 0.3))?,
+                    self.optional_color(&mut fields, "dark", RgbColor::new(0.4, 0.25, 0.12))?,
+                    self.optional_number(&mut fields, "ring_frequency", 8.0)?,
+                );
+                wood.turbulence_depth = self.optional_turbulence_depth(&mut fields)?;
+                wood.distortion =
+                    self.optional_number(&mut fields, "distortion", wood.distortion)?;
+
+                Arc::new(wood)
+            }
+            other => return Err(self.error(&kind, format!("unknown texture type {}", other))),
+        };
+
+        fields.finish(self)?;
+
+        Ok(texture)
+    }
+
+    /// Reads either the name of a texture or a color.
+    fn texture_reference(&self, value: JsonValue) -> Result<Arc<dyn Texture>, LoadError> {
+        match &value.kind {
+            JsonKind::String(name) => self
+                .textures
+                .get(name)
+                .cloned()
+                .ok_or_else(|| self.error(&value, format!("unknown texture {}", name))),
+            _ => Ok(Arc::new(SolidColor::from_rgb_color(self.color(value)?))),
+        }
+    }
+
+    /// Reads an optional field holding either a number or a texture
+    /// reference, for the parameters of materials.
+    fn optional_parameter(
+        &self,
+        fields: &mut Fields,
+        key: &str,
+        default: Arc<dyn Texture>,
+    ) -> Result<Arc<dyn Texture>, LoadError> {
+        match fields.take(key) {
+            Some(value) => match value.kind {
+                JsonKind::Number(number) => Ok(Arc::new(SolidColor::gray(number as f32))),
+                _ => self.texture_reference(value),
+            },
+            None => Ok(default),
+        }
+    }
+
+    fn optional_seed(&self, fields: &mut Fields) -> Result<u64, LoadError> {
+        fields
+            .take("seed")
+            .map_or(Ok(0), |value| self.unsigned_integer(value))
+    }
+
+    fn optional_turbulence_depth(&self, fields: &mut Fields) -> Result<u32, LoadError> {
+        fields
+            .take("turbulence_depth")
+            .map_or(Ok(DEFAULT_TURBULENCE_DEPTH), |value| {
+                Ok(self.unsigned_integer(value)? as u32)
+            })
+    }
+
+    /// Reads either a `texture` field naming a texture or a `color` field.
+    fn texture_or_color(
+        &self,
+        fields: &mut Fields,
+        default: RgbColor,
+    ) -> Result<Arc<dyn Texture>, LoadError> {
+        match (fields.take("texture"), fields.take("color")) {
+            (Some(texture), None) => {
+                let name = self.string(texture.clone())?;
+
+                self.textures
+                    .get(&name)
+                    .cloned()
+                    .ok_or_else(|| self.error(&texture, format!("unknown texture {}", name)))
+            }
+            (None, Some(color)) => Ok(Arc::new(SolidColor::from_rgb_color(self.color(color)?))),
+            (None, None) => Ok(Arc::new(SolidColor::from_rgb_color(default))),
+            (Some(texture), Some(_)) => {
+                Err(self.error(&texture, "only one of texture and color can be set"))
+            }
+        }
+    }
+
+    fn material(&self, value: JsonValue) -> Result<Arc<Material>, LoadError> {
+        let mut fields = self.fields(value)?;
+        let kind = fields.require(self, "type")?;
+
+        let material = match &self.string(kind.clone())?[..] {
+            "lambertian" => Material::Reflective(Arc::new(Lambertian::new(
+                self.texture_or_color(&mut fields, WHITE)?,
+            ))),
+            "metal" => {
+                let fresnel = self.conductor_fresnel(&mut fields)?;
+                let roughness = self.optional_number(&mut fields, "roughness", 0.0)?;
+
+                Material::Reflective(Arc::new(Metal::with_fresnel(fresnel, roughness)))
+            }
+            "dielectric" => {
+                let refractive_index = self.number(fields.require(self, "refractive_index")?)?;
+                let color = match fields.take("color") {
+                    Some(color) => self.color(color)?,
+                    None => WHITE,
+                };
+                let roughness = self.optional_number(&mut fields, "roughness", 0.0)?;
+                let absorption = self.optional_color(&mut fields, "absorption", BLACK)?;
+
+                Material::Reflective(Arc::new(
+                    Dielectric::new(refractive_index, color)
+                        .with_roughness(roughness)
+                        .with_absorption(absorption),
+                ))
+            }
+            "principled" => {
+                let base_color = match fields.take("base_color") {
+                    Some(base_color) => self.texture_reference(base_color)?,
+                    None => Arc::new(SolidColor::gray(0.8)),
+                };
+                let defaults = Principled::new(base_color);
+
+                Material::Reflective(Arc::new(Principled {
+                    metallic: self.optional_parameter(
+                        &mut fields,
+                        "metallic",
+                        defaults.metallic,
+                    )?,
+                    roughness: self.optional_parameter(
+                        &mut fields,
+                        "roughness",
+                        defaults.roughness,
+                    )?,
+                    specular: self.optional_parameter(
+                        &mut fields,
+                        "specular",
+                        defaults.specular,
+                    )?,
+                    sheen: self.optional_parameter(&mut fields, "sheen", defaults.sheen)?,
+                    clearcoat: self.optional_parameter(
+                        &mut fields,
+                        "clearcoat",
+                        defaults.clearcoat,
+                    )?,
+                    transmission: self.optional_parameter(
+                        &mut fields,
+                        "transmission",
+                        defaults.transmission,
+                    )?,
+                    refractive_index: self.optional_number(
+                        &mut fields,
+                        "refractive_index",
+                        defaults.refractive_index,
+                    )?,
+                    ..defaults
+                }))
+            }
+            "diffuse_light" => Material::Emissive(Arc::new(DiffuseLight::new(
+                self.texture_or_color(&mut fields, WHITE)?,
+            ))),
+            other => return Err(self.error(&kind, format!("unknown material type {}", other))),
+        };
+
+        fields.finish(self)?;
+
+        Ok(Arc::new(material))
+    }
+
+    fn object(
+        &self,
+        value: JsonValue,
+        objects: &mut Vec<Arc<dyn Object>>,
+        lights: &mut Vec<Arc<dyn Object>>,
+    ) -> Result<(), LoadError> {
+        let mut fields = self.fields(value)?;
+        let kind = fields.require(self, "type")?;
+        let kind_name = self.string(kind.clone())?;
+
+        if kind_name == "obj" {
+            let path = self.path(fields.require(self, "path")?)?;
+            let mut model = load_obj(path)?;
+
+            fields.finish(self)?;
+
+            objects.push(model.object);
+            lights.append(&mut model.lights);
+
+            return Ok(());
+        }
+
+        let material_value = fields.require(self, "material")?;
+        let material_name = self.string(material_value.clone())?;
+        let material = self.materials.get(&material_name).cloned().ok_or_else(|| {
+            self.error(
+                &material_value,
+                format!("unknown material {}", material_name),
+            )
+        })?;
+
+        let acceleration = self.optional_vec3(&mut fields, "acceleration")?;
+        let initial_velocity = self.optional_vec3(&mut fields, "initial_velocity")?;
+
+        let object: Arc<dyn Object> = match &kind_name[..] {
+            "sphere" => Arc::new(
+                SphereBuilder::new(
+                    self.vec3(fields.require(self, "center")?)?,
+                    self.number(fields.require(self, "radius")?)?,
+                    Arc::clone(&material),
+                )
+                .accelerate(acceleration, initial_velocity)
+                .build(),
+            ),
+            "rectangle" => Arc::new(
+                RectangleBuilder::new(
+                    self.vec3(fields.require(self, "top_left_corner")?)?,
+                    self.vec3(fields.require(self, "normal")?)?.unit(),
+                    self.vec3(fields.require(self, "up")?)?.unit(),
+                    self.number(fields.require(self, "height")?)?,
+                    self.number(fields.require(self, "width")?)?,
+                    Arc::clone(&material),
+                )
+                .accelerate(acceleration, initial_velocity)
+                .build(),
+            ),
+            "rectangular_prism" => Arc::new(
+                RectangularPrismBuilder::new(
+                    self.vec3(fields.require(self, "top_left_corner")?)?,
+                    self.vec3(fields.require(self, "normal")?)?.unit(),
+                    self.vec3(fields.require(self, "up")?)?.unit(),
+                    self.number(fields.require(self, "height")?)?,
+                    self.number(fields.require(self, "width")?)?,
+                    self.number(fields.require(self, "depth")?)?,
+                    Arc::clone(&material),
+                )
+                .accelerate(acceleration, initial_velocity)
+                .build(),
+            ),
+            "triangle" => {
+                let vertices = self.vec3_triple(fields.require(self, "vertices")?)?;
+                let mut builder = TriangleBuilder::new(vertices, Arc::clone(&material));
+
+                if let Some(normals) = fields.take("normals") {
+                    let [n0, n1, n2] = self.vec3_triple(normals)?;
+
+                    builder.normals([n0.unit(), n1.unit(), n2.unit()]);
+                }
+
+                if let Some(texture_coordinates) = fields.take("texture_coordinates") {
+                    let mut uvs = [(0.0, 0.0); 3];
+
+                    for (uv, value) in uvs.iter_mut().zip(self.array_of(texture_coordinates, 3)?) {
+                        let [u, v] = self.numbers(value)?;
+
+                        *uv = (u, v);
+                    }
+
+                    builder.texture_coordinates(uvs);
+                }
+
+                Arc::new(builder.accelerate(acceleration, initial_velocity).build())
+            }
+            other => return Err(self.error(&kind, format!("unknown object type {}", other))),
+        };
+
+        let is_light = match fields.take("light") {
+            Some(light) => self.boolean(light)?,
+            None => matches!(material.as_ref(), Material::Emissive(_)),
+        };
+
+        fields.finish(self)?;
+
+        if is_light {
+            lights.push(Arc::clone(&object));
+        }
+
+        objects.push(object);
+
+        Ok(())
+    }
+
+    fn fields(&self, value: JsonValue) -> Result<Fields, LoadError> {
+        let line = value.line;
+
+        match value.kind {
+            JsonKind::Object(fields) => Ok(Fields { line, fields }),
+            _ => Err(self.error(
+                &value,
+                format!("expected an object, found {}", value.type_name()),
+            )),
+        }
+    }
+
+    fn named_values(&self, value: JsonValue) -> Result<Vec<(String, JsonValue)>, LoadError> {
+        Ok(self.fields(value)?.fields)
+    }
+
+    fn array(&self, value: JsonValue) -> Result<Vec<JsonValue>, LoadError> {
+        match value.kind {
+            JsonKind::Array(elements) => Ok(elements),
+            _ => Err(self.error(
+                &value,
+                format!("expected an array, found {}", value.type_name()),
+            )),
+        }
+    }
+
+    fn array_of(&self, value: JsonValue, len: usize) -> Result<Vec<JsonValue>, LoadError> {
+        let line = value.line;
+        let elements = self.array(value)?;
+
+        if elements.len() == len {
+            Ok(elements)
+        } else {
+            Err(LoadError::parse(
+                &self.path,
+                line,
+                format!("expected {} elements, found {}", len, elements.len()),
+            ))
+        }
+    }
+
+    fn number(&self, value: JsonValue) -> Result<f32, LoadError> {
+        match value.kind {
+            JsonKind::Number(number) => Ok(number as f32),
+            _ => Err(self.error(
+                &value,
+                format!("expected a number, found {}", value.type_name()),
+            )),
+        }
+    }
+
+    fn optional_number(
+        &self,
+        fields: &mut Fields,
+        key: &str,
+        default: f32,
+    ) -> Result<f32, LoadError> {
+        fields
+            .take(key)
+            .map_or(Ok(default), |value| self.number(value))
+    }
+
+    fn numbers<const N: usize>(&self, value: JsonValue) -> Result<[f32; N], LoadError> {
+        let mut numbers = [0.0; N];
+
+        for (number, element) in numbers.iter_mut().zip(self.array_of(value, N)?) {
+            *number = self.number(element)?;
+        }
+
+        Ok(numbers)
+    }
+
+    fn vec3(&self, value: JsonValue) -> Result<Vec3, LoadError> {
+        let [x, y, z] = self.numbers(value)?;
+
+        Ok(Vec3::new(x, y, z))
+    }
+
+    fn direction(&self, value: JsonValue) -> Result<Vec3, LoadError> {
+        let direction = self.vec3(value.clone())?;
+
+        if direction.len_squared() == 0.0 {
+            return Err(self.error(&value, "expected a direction, found a zero vector"));
+        }
+
+        Ok(direction)
+    }
+
+    fn optional_vec3(&self, fields: &mut Fields, key: &str) -> Result<Vec3, LoadError> {
+        fields
+            .take(key)
+            .map_or(Ok(Vec3::zero()), |value| self.vec3(value))
+    }
+
+    fn vec3_triple(&self, value: JsonValue) -> Result<[Vec3; 3], LoadError> {
+        let mut vectors = [Vec3::zero(); 3];
+
+        for (vector, element) in vectors.iter_mut().zip(self.array_of(value, 3)?) {
+            *vector = self.vec3(element)?;
+        }
+
+        Ok(vectors)
+    }
+
+    fn unsigned_integer(&self, value: JsonValue) -> Result<u64, LoadError> {
+        match value.kind {
+            JsonKind::Number(number)
+                if number >= 0.0 && number.fract() == 0.0 && number <= u32::MAX as f64 =>
+            {
+                Ok(number as u64)
+            }
+            _ => Err(self.error(
+                &value,
+                format!("expected an unsigned integer, found {}", value.type_name()),
+            )),
+        }
+    }
+
+    fn color(&self, value: JsonValue) -> Result<RgbColor, LoadError> {
+        let [red, green, blue] = self.numbers(value)?;
+
+        Ok(RgbColor::new(red, green, blue))
+    }
+
+    fn optional_color(
+        &self,
+        fields: &mut Fields,
+        key: &str,
+        default: RgbColor,
+    ) -> Result<RgbColor, LoadError> {
+        fields
+            .take(key)
+            .map_or(Ok(default), |value| self.color(value))
+    }
+
+    fn string(&self, value: JsonValue) -> Result<String, LoadError> {
+        match value.kind {
+            JsonKind::String(string) => Ok(string),
+            _ => Err(self.error(
+                &value,
+                format!("expected a string, found {}", value.type_name()),
+            )),
+        }
+    }
+
+    fn boolean(&self, value: JsonValue) -> Result<bool, LoadError> {
+        match value.kind {
+            JsonKind::Bool(boolean) => Ok(boolean),
+            _ => Err(self.error(
+                &value,
+                format!("expected a boolean, found {}", value.type_name()),
+            )),
+        }
+    }
+
+    /// Reads an optional string field naming one of a few options.
+    fn optional_name<T, F: Fn(&str) -> Option<T>>(
+        &self,
+        fields: &mut Fields,
+        key: &str,
+        kind: &str,
+        from_name: F,
+        names: &[&str],
+    ) -> Result<Option<T>, LoadError> {
+        let value = match fields.take(key) {
+            Some(value) => value,
+            None => return Ok(None),
+        };
+
+        let name = self.string(value.clone())?;
+
+        from_name(&name).map(Some).ok_or_else(|| {
+            self.error(
+                &value,
+                format!(
+                    "unknown {} {}, expected one of: {}",
+                    kind,
+                    name,
+                    names.join(", ")
+                ),
+            )
+        })
+    }
+
+    fn path(&self, value: JsonValue) -> Result<PathBuf, LoadError> {
+        Ok(self.directory.join(self.string(value)?))
+    }
+}
+
+enum LightAmount {
+    Intensity(f32),
+    Power(f32),
+}
+
+/// The fields of a JSON object, taken one by one so that unknown fields
+/// can be reported once everything expected has been read.
+struct Fields {
+    line: usize,
+    fields: Vec<(String, JsonValue)>,
+}
+
+impl Fields {
+    fn take(&mut self, key: &str) -> Option<JsonValue> {
+        let index = self.fields.iter().position(|(field, _)| field == key)?;
+
+        Some(self.fields.remove(index).1)
+    }
+
+    fn require(&mut self, loader: &SceneFileLoader, key: &str) -> Result<JsonValue, LoadError> {
+        self.take(key).ok_or_else(|| {
+            LoadError::parse(&loader.path, self.line, format!("missing field {}", key))
+        })
+    }
+
+    fn finish(self, loader: &SceneFileLoader) -> Result<(), LoadError> {
+        match self.fields.first() {
+            None => Ok(()),
+            Some((key, value)) => Err(loader.error(value, format!("unknown field {}", key))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::ray::MAX_T;
+
+    const CAMERA: &str = r#""camera": {
+        "look_from": [0, 0, -5], "look_at": [0, 0, 0], "aspect_ratio": 1, "vertical_fov": 40
+    }"#;
+
+    fn parse(source: &str) -> Result<SceneDescription, LoadError> {
+        parse_scene(Path::new("scenes/test.json"), source)
+    }
+
+    fn parse_error(source: &str) -> String {
+        match parse(source) {
+            Ok(_) => panic!("expected an error loading {}", source),
+            Err(error) => error.to_string(),
+        }
+    }
+
+    #[test]
+    fn loads_objects_materials_and_lights() {
+        let description = parse(&format!(
+            r#"{{
+                {},
+                "background": [0.1, 0.2, 0.3],
+                "textures": {{
+                    "tiles": {{ "type": "checker", "even": [1, 1, 1], "odd": [0, 0, 0] }}
+                }},
+                "materials": {{
+                    "floor": {{ "type": "lambertian", "texture": "tiles" }},
+                    "plastic": {{ "type": "principled", "base_color": "tiles", "roughness": 0.3,
+                                  "clearcoat": "tiles" }},
+                    "lamp": {{ "type": "diffuse_light", "color": [4, 4, 4] }}
+                }},
+                "objects": [
+                    {{ "type": "sphere", "center": [0, 0, 0], "radius": 1, "material": "plastic" }},
+                    {{ "type": "sphere", "center": [0, -101, 0], "radius": 100, "material": "floor" }},
+                    {{ "type": "sphere", "center": [0, 5, 0], "radius": 1, "material": "lamp" }},
+                    {{ "type": "sphere", "center": [0, 9, 0], "radius": 1, "material": "lamp",
+                       "light": false }}
+                ],
+                "lights": [
+                    {{ "type": "point", "position": [0, 3, 0], "power": 100 }},
+                    {{ "type": "directional", "direction": [0, -1, 0], "illuminance": 10 }}
+                ]
+            }}"#,
+            CAMERA
+        ))
+        .unwrap_or_else(|error| panic!("{}", error));
+
+        let scene = description.scene;
+
+        assert_eq!(scene.lights.objects.len(), 1);
+        assert_eq!(scene.delta_lights.len(), 2);
+
+        let ray = Ray::new(Vec3::new(0.0, 0.0, -5.0), Vec3::new(0.0, 0.0, 1.0), 0.0);
+
+        assert!((scene.objects.hit(&ray, MAX_T).unwrap().t - 4.0).abs() < 1e-4);
+        assert_eq!(scene.background(&ray), RgbColor::new(0.1, 0.2, 0.3));
+    }
+
+    #[test]
+    fn adds_the_sun_of_a_sky_to_the_lights() {
+        let scene = |sun_direction: &str| {
+            parse(&format!(
+                r#"{{
+                    {},
+                    "background": {{ "type": "sky", "sun_direction": {}, "turbidity": 4 }},
+                    "objects": []
+                }}"#,
+                CAMERA, sun_direction
+            ))
+            .unwrap_or_else(|error| panic!("{}", error))
+            .scene
+        };
+
+        let day = scene("[1, 2, 0]");
+
+        assert!(day.background.is_sampled());
+        assert_eq!(day.delta_lights.len(), 1);
+
+        let sample = day.delta_lights[0].illuminate(Vec3::zero()).unwrap();
+
+        assert!((sample.direction - Vec3::new(1.0, 2.0, 0.0).unit()).len() < 1e-6);
+
+        assert!(scene("[1, -2, 0]").delta_lights.is_empty());
+    }
+
+    #[test]
+    fn reports_json_errors_with_their_line() {
+        assert_eq!(
+            parse_error("{\n\"camera\": {\n}\n,}"),
+            "scenes/test.json:4: expected a field name"
+        );
+    }
+
+    #[test]
+    fn reports_unknown_fields() {
+        assert_eq!(
+            parse_error(&format!(
+                "{{\n{},\n\"objects\": [],\n\"lamps\": []\n}}",
+                CAMERA
+            )),
+            "scenes/test.json:6: unknown field lamps"
+        );
+        assert_eq!(
+            parse_error(&format!(
+                "{{\n{},\n\"materials\": {{\n\"red\": {{ \"type\": \"lambertian\",\n\"colour\": [1, 0, 0] }}\n}},\n\"objects\": []\n}}",
+                CAMERA
+            )),
+            "scenes/test.json:7: unknown field colour"
+        );
+    }
+
+    #[test]
+    fn reports_missing_fields_at_their_object() {
+        assert_eq!(
+            parse_error(&format!("{{\n{}\n}}", CAMERA)),
+            "scenes/test.json:1: missing field objects"
+        );
+        assert_eq!(
+            parse_error("{\n\"camera\": {\n\"look_from\": [0, 0, 0]\n},\n\"objects\": []\n}"),
+            "scenes/test.json:2: missing field look_at"
+        );
+        assert_eq!(
+            parse_error(&format!(
+                "{{\n{},\n\"lights\": [\n{{ \"type\": \"point\", \"position\": [0, 0, 0] }}\n],\n\"objects\": []\n}}",
+                CAMERA
+            )),
+            "scenes/test.json:6: missing field intensity or power"
+        );
+    }
+
+    #[test]
+    fn reports_invalid_values() {
+        let scene = |objects: &str| {
+            format!(
+                "{{\n{},\n\"materials\": {{ \"red\": {{ \"type\": \"lambertian\" }} }},\n\"objects\": [\n{}\n]\n}}",
+                CAMERA, objects
+            )
+        };
+
+        assert_eq!(
+            parse_error(&scene(
+                r#"{ "type": "sphere", "center": [0, 0, 0], "radius": 1, "material": "gold" }"#
+            )),
+            "scenes/test.json:7: unknown material gold"
+        );
+        assert_eq!(
+            parse_error(&scene(r#"{ "type": "cone", "material": "red" }"#)),
+            "scenes/test.json:7: unknown object type cone"
+        );
+        assert_eq!(
+            parse_error(&scene(
+                "{ \"type\": \"sphere\", \"center\": [0, 0, 0],\n\"radius\": \"1\", \"material\": \"red\" }"
+            )),
+            "scenes/test.json:8: expected a number, found a string"
+        );
+        assert_eq!(
+            parse_error(&scene(
+                "{ \"type\": \"triangle\", \"material\": \"red\",\n\"vertices\": [[0, 0, 0], [1, 0, 0]] }"
+            )),
+            "scenes/test.json:8: expected 3 elements, found 2"
+        );
+        assert_eq!(
+            parse_error(&scene(
+                "{ \"type\": \"sphere\", \"center\": [0, 0, 0], \"radius\": 1, \"material\": \"red\",\n\"light\": 1 }"
+            )),
+            "scenes/test.json:8: expected a boolean, found a number"
+        );
+    }
+}
@@ -1,22 +1,68 @@
+mod cli;
 mod demo;
 
-use demo::Demo;
+use std::process;
+use std::sync::Arc;
+
+use tracer::{loaders::load_scene, render::Renderer};
+
+use cli::{Command, SceneSource};
+
+const USAGE_ERROR_EXIT_CODE: i32 = 2;
+const RENDER_ERROR_EXIT_CODE: i32 = 1;
 
 fn main() {
-    let args: Vec<String> = std::env::args().collect();
-
-    let cornell_box = demo::CornellBox::new();
-
-    cornell_box
-        .render(
-            &args[1],
-            args[2].parse::<u32>().unwrap(),
-            args[3].parse::<u32>().unwrap(),
-            args.get(4)
-                .unwrap_or(&String::from("10"))
-                .parse::<u32>()
-                .unwrap(),
-            1.0,
-        )
-        .unwrap();
+    let options = match cli::parse_args(std::env::args().skip(1)) {
+        Ok(Command::Render(options)) => options,
+        Ok(Command::Help) => {
+            print!("{}", cli::USAGE);
+
+            return;
+        }
+        Err(error) => {
+            eprintln!(
+                "error: {}\n\nTry 'tracer --help' for more information.",
+                error
+            );
+
+            process::exit(USAGE_ERROR_EXIT_CODE);
+        }
+    };
+
+    if let Some(threads) = options.threads {
+        if let Err(error) = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build_global()
+        {
+            eprintln!("error: {}", error);
+
+            process::exit(RENDER_ERROR_EXIT_CODE);
+        }
+    }
+
+    let renderer = match &options.source {
+        SceneSource::Demo(name) => {
+            let demo = demo::find_demo(name).expect("demo names are validated by the parser");
+
+            Renderer::new(Arc::new(demo.camera()), Arc::new(demo.scene()))
+        }
+        SceneSource::File(path) => match load_scene(path) {
+            Ok(description) => {
+                Renderer::new(Arc::new(description.camera), Arc::new(description.scene))
+            }
+            Err(error) => {
+                eprintln!("error: {}", error);
+
+                process::exit(RENDER_ERROR_EXIT_CODE);
+            }
+        },
+    };
+
+    if let Err(error) = renderer.render(&options.output, &options.settings) {
+        eprintln!("\nerror: {}: {}", options.output, error);
+
+        process::exit(RENDER_ERROR_EXIT_CODE);
+    }
+
+    eprintln!();
 }
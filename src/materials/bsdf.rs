@@ -0,0 +1,191 @@
+use crate::{rgb_color::RgbColor, vec3::Vec3};
+
+/// Bidirectional scattering distribution function of a point of a surface.
+/// Directions point away from the surface, in the local frame of its normal,
+/// which is along z and on the side of `wo`.
+pub trait Bsdf: Send + Sync {
+    /// Fraction of the light arriving from `wi` that's scattered towards
+    /// `wo`, times the cosine of `wi` with the normal. Delta lobes are black.
+    fn eval(&self, wo: Vec3, wi: Vec3) -> RgbColor;
+
+    /// Samples a direction the light towards `wo` comes from, from a
+    /// uniformly distributed point of the unit square.
+    fn sample(&self, wo: Vec3, u: (f32, f32)) -> Option<BsdfSample>;
+
+    /// Density of sampling `wi`, zero for delta lobes.
+    fn pdf(&self, wo: Vec3, wi: Vec3) -> f32;
+
+    fn flags(&self) -> BsdfFlags;
+}
+
+/// Kinds of lobes of a BSDF.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct BsdfFlags {
+    /// Lobes that scatter into single directions, which can only be found by
+    /// sampling the BSDF.
+    pub delta: bool,
+    /// Lobes spread over directions, which can be evaluated towards lights.
+    pub non_delta: bool,
+}
+
+impl BsdfFlags {
+    pub const DELTA: Self = Self {
+        delta: true,
+        non_delta: false,
+    };
+    pub const NON_DELTA: Self = Self {
+        delta: false,
+        non_delta: true,
+    };
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct BsdfSample {
+    pub wi: Vec3,
+    /// `eval` of the sampled direction. For delta lobes, which can't be
+    /// evaluated, the fraction of the light they scatter times `pdf`.
+    pub value: RgbColor,
+    /// Density of the direction, or the probability of picking the lobe for
+    /// delta lobes.
+    pub pdf: f32,
+    pub is_delta: bool,
+}
+
+impl BsdfSample {
+    pub const fn new(wi: Vec3, value: RgbColor, pdf: f32) -> Self {
+        Self {
+            wi,
+            value,
+            pdf,
+            is_delta: false,
+        }
+    }
+
+    pub const fn delta(wi: Vec3, value: RgbColor, pdf: f32) -> Self {
+        Self {
+            wi,
+            value,
+            pdf,
+            is_delta: true,
+        }
+    }
+
+    /// Throughput of the path continued along the sample.
+    pub fn weight(&self) -> RgbColor {
+        self.value / self.pdf
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::f32::consts::PI;
+
+    use super::*;
+
+    use crate::{pdfs::random_cosine_direction, rgb_color::BLACK};
+
+    /// Grid of points of the unit square, one at the center of each cell.
+    pub(crate) fn unit_square_grid(size: u32) -> impl Iterator<Item = (f32, f32)> {
+        (0..size * size).map(move |index| {
+            (
+                ((index % size) as f32 + 0.5) / size as f32,
+                ((index / size) as f32 + 0.5) / size as f32,
+            )
+        })
+    }
+
+    /// Directions spread over the sphere by the midpoint rule in the cosine
+    /// and the azimuth, with the solid angle around each of them.
+    pub(crate) fn sphere_grid(size: u32) -> impl Iterator<Item = (Vec3, f32)> {
+        let solid_angle = 4.0 * PI / (size * size) as f32;
+
+        unit_square_grid(size).map(move |(u1, u2)| {
+            let z = 1.0 - 2.0 * u1;
+            let r = f32::sqrt(f32::max(1.0 - z * z, 0.0));
+            let phi = 2.0 * PI * u2;
+
+            (Vec3::new(r * phi.cos(), r * phi.sin(), z), solid_angle)
+        })
+    }
+
+    pub(crate) fn integrate_sphere(size: u32, function: impl Fn(Vec3) -> f32) -> f32 {
+        sphere_grid(size)
+            .map(|(direction, solid_angle)| function(direction) * solid_angle)
+            .sum()
+    }
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() <= 1e-3 * f32::max(expected.abs(), 1.0),
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    /// Checks that samples report the `eval` and `pdf` of their direction,
+    /// or are delta only if the flags say the BSDF has delta lobes.
+    pub(crate) fn assert_samples_match_eval_and_pdf(bsdf: &dyn Bsdf, wo: Vec3) {
+        let flags = bsdf.flags();
+
+        for u in unit_square_grid(32) {
+            let sample = match bsdf.sample(wo, u) {
+                Some(sample) => sample,
+                None => continue,
+            };
+
+            assert!((sample.wi.len() - 1.0).abs() < 1e-4, "{:?}", sample.wi);
+
+            if sample.is_delta {
+                assert!(flags.delta, "delta sample of a BSDF without delta lobes");
+                assert!(sample.pdf > 0.0 && sample.pdf <= 1.0);
+            } else {
+                assert!(flags.non_delta, "non-delta sample of a delta BSDF");
+                assert_close(sample.pdf, bsdf.pdf(wo, sample.wi));
+
+                let eval = bsdf.eval(wo, sample.wi);
+
+                assert_close(sample.value.red, eval.red);
+                assert_close(sample.value.green, eval.green);
+                assert_close(sample.value.blue, eval.blue);
+            }
+        }
+
+        // Delta lobes can't be evaluated towards a given direction.
+        if !flags.non_delta {
+            for u in unit_square_grid(8) {
+                let wi = random_cosine_direction(u);
+
+                assert_eq!(bsdf.eval(wo, wi), BLACK);
+                assert_eq!(bsdf.pdf(wo, wi), 0.0);
+            }
+        }
+    }
+
+    /// Checks that the density integrates to the fraction of the samples that
+    /// the BSDF returns, which is one unless some of them are dropped.
+    pub(crate) fn assert_pdf_integrates_to_sampled_fraction(bsdf: &dyn Bsdf, wo: Vec3) {
+        let sampled = unit_square_grid(128)
+            .filter(|&u| bsdf.sample(wo, u).is_some())
+            .count() as f32
+            / (128 * 128) as f32;
+
+        let integral = integrate_sphere(800, |wi| bsdf.pdf(wo, wi));
+
+        assert!(
+            (integral - sampled).abs() < 0.02,
+            "density integrates to {} but {} of the samples are kept",
+            integral,
+            sampled
+        );
+    }
+
+    #[test]
+    fn weights_samples_by_their_density() {
+        let sample = BsdfSample::new(Vec3::z(), RgbColor::new(0.5, 1.0, 2.0), 2.0);
+
+        assert_eq!(sample.weight(), RgbColor::new(0.25, 0.5, 1.0));
+        assert!(!sample.is_delta);
+        assert!(BsdfSample::delta(Vec3::z(), BLACK, 1.0).is_delta);
+    }
+}
@@ -1,16 +1,31 @@
+use std::sync::Arc;
+
 use crate::{
-    helpers::random,
     hittable::Hit,
-    materials::{ReflectiveMaterial, ScatterResult},
+    materials::{
+        fresnel_dielectric, Bsdf, BsdfFlags, BsdfSample, GgxDistribution, ReflectiveMaterial,
+        MIN_ALPHA,
+    },
     ray::Ray,
-    rgb_color::RgbColor,
-    vec3::dot,
+    rgb_color::{RgbColor, BLACK},
+    samplers::Sampler,
+    vec3::{dot, Vec3},
 };
 
+/// Glass-like material that reflects and refracts light. Rough dielectrics
+/// have a GGX distribution of microfacets, which gives frosted glass.
 #[derive(Copy, Clone)]
 pub struct Dielectric {
     pub refractive_index: f32,
+    /// Flat tint applied at every reflection and refraction, on top of the
+    /// absorption. It doesn't depend on the thickness, so colored glass
+    /// should be white and get its color from `absorption` instead.
     pub color: RgbColor,
+    /// Perceptual roughness, between 0 and 1.
+    pub roughness: f32,
+    /// Absorption coefficient of every channel per unit of distance traveled
+    /// inside, so that thick parts are tinted more than thin ones.
+    pub absorption: RgbColor,
 }
 
 impl Dielectric {
@@ -18,45 +33,523 @@ impl Dielectric {
         Self {
             refractive_index,
             color,
+            roughness: 0.0,
+            absorption: BLACK,
         }
     }
 
-    fn reflectance(cos_theta: f32, refraction_ratio: f32) -> f32 {
-        let r0 = ((1.0 - refraction_ratio) / (1.0 + refraction_ratio)).powi(2);
+    pub const fn with_roughness(self, roughness: f32) -> Self {
+        Self { roughness, ..self }
+    }
 
-        r0 + (1.0 - r0) * (1.0 - cos_theta).powi(5)
+    pub const fn with_absorption(self, absorption: RgbColor) -> Self {
+        Self { absorption, ..self }
+    }
+
+    /// Fraction of the light that goes through `distance` of the medium.
+    fn transmittance(&self, distance: f32) -> RgbColor {
+        RgbColor::new(
+            f32::exp(-self.absorption.red * distance),
+            f32::exp(-self.absorption.green * distance),
+            f32::exp(-self.absorption.blue * distance),
+        )
     }
 }
 
 impl ReflectiveMaterial for Dielectric {
-    fn scatter(&self, ray: &Ray, hit: &Hit) -> ScatterResult {
-        let refraction_ratio = if hit.front_face {
+    fn bsdf(&self, ray: &Ray, hit: &Hit, sampler: &mut dyn Sampler) -> Arc<dyn Bsdf> {
+        // Index of refraction of the side the light is transmitted to over
+        // the one of the side the ray comes from.
+        let eta = if hit.front_face {
+            self.refractive_index
+        } else {
             1.0 / self.refractive_index
+        };
+
+        // Rays that hit the inside of the surface have traveled through the
+        // medium since the previous bounce.
+        let color = if hit.front_face {
+            self.color
         } else {
-            self.refractive_index
+            self.color * self.transmittance(hit.t * ray.direction.len())
         };
 
-        let unit_direction = ray.direction;
+        if self.roughness * self.roughness < MIN_ALPHA {
+            Arc::new(SmoothDielectricBsdf::new(eta, color))
+        } else {
+            Arc::new(DielectricBsdf::new(
+                GgxDistribution::from_roughness(self.roughness),
+                eta,
+                color,
+                sampler.next_1d(),
+            ))
+        }
+    }
+}
 
-        let cos_theta = f32::min(dot(-unit_direction, hit.normal), 1.0);
-        let sin_theta = f32::sqrt(1.0 - cos_theta.powi(2));
+/// Perfectly smooth dielectric, which either reflects or refracts the light
+/// depending on its Fresnel reflectance.
+pub(crate) struct SmoothDielectricBsdf {
+    eta: f32,
+    color: RgbColor,
+}
 
-        let can_refract = refraction_ratio * sin_theta <= 1.0;
+impl SmoothDielectricBsdf {
+    /// Takes `eta` as the index of refraction of the side opposite to `wo`
+    /// over the one of its side.
+    pub(crate) fn new(eta: f32, color: RgbColor) -> Self {
+        Self { eta, color }
+    }
+}
 
-        let specular_direction = if can_refract
-            && Dielectric::reflectance(cos_theta, refraction_ratio) <= random(0.0..1.0)
-        {
-            unit_direction.refract(hit.normal, refraction_ratio)
+impl Bsdf for SmoothDielectricBsdf {
+    fn eval(&self, _wo: Vec3, _wi: Vec3) -> RgbColor {
+        BLACK
+    }
+
+    fn sample(&self, wo: Vec3, (u1, _): (f32, f32)) -> Option<BsdfSample> {
+        let reflectance = fresnel_dielectric(wo.z, self.eta);
+
+        // Total internal reflection has a reflectance of one, so the light
+        // is never refracted then.
+        if u1 < reflectance {
+            let wi = Vec3::new(-wo.x, -wo.y, wo.z);
+
+            Some(BsdfSample::delta(wi, self.color * reflectance, reflectance))
+        } else {
+            let wi = (-wo).refract(Vec3::z(), 1.0 / self.eta).unit();
+            let transmittance = 1.0 - reflectance;
+
+            Some(BsdfSample::delta(
+                wi,
+                self.color * transmittance,
+                transmittance,
+            ))
+        }
+    }
+
+    fn pdf(&self, _wo: Vec3, _wi: Vec3) -> f32 {
+        0.0
+    }
+
+    fn flags(&self) -> BsdfFlags {
+        BsdfFlags::DELTA
+    }
+}
+
+/// Rough dielectric, reflecting and refracting the light through its
+/// microfacets, following Walter et al. "Microfacet Models for Refraction
+/// through Rough Surfaces".
+pub(crate) struct DielectricBsdf {
+    distribution: GgxDistribution,
+    eta: f32,
+    color: RgbColor,
+    /// Chooses between reflecting and refracting the sampled direction, with
+    /// the probability given by the Fresnel reflectance of its microfacet.
+    reflection_choice: f32,
+}
+
+impl DielectricBsdf {
+    /// Takes `eta` as the index of refraction of the side opposite to `wo`
+    /// over the one of its side.
+    pub(crate) fn new(
+        distribution: GgxDistribution,
+        eta: f32,
+        color: RgbColor,
+        reflection_choice: f32,
+    ) -> Self {
+        Self {
+            distribution,
+            eta,
+            color,
+            reflection_choice,
+        }
+    }
+
+    /// Microfacet normal that reflects `wo` into `wi`, if both are above
+    /// the surface.
+    fn reflection_half_vector(wo: Vec3, wi: Vec3) -> Option<Vec3> {
+        let half_vector = wo + wi;
+
+        if wo.z <= 0.0 || wi.z <= 0.0 || half_vector.len_squared() == 0.0 {
+            return None;
+        }
+
+        let half_vector = half_vector.unit();
+
+        if half_vector.z <= 0.0 || dot(wo, half_vector) <= 0.0 {
+            None
+        } else {
+            Some(half_vector)
+        }
+    }
+
+    /// Microfacet normal that refracts `wo` into `wi`, if `wi` is below the
+    /// surface.
+    fn refraction_half_vector(&self, wo: Vec3, wi: Vec3) -> Option<Vec3> {
+        let half_vector = wo + self.eta * wi;
+
+        if wo.z <= 0.0 || wi.z >= 0.0 || half_vector.len_squared() == 0.0 {
+            return None;
+        }
+
+        let half_vector = if half_vector.z < 0.0 {
+            -half_vector.unit()
+        } else {
+            half_vector.unit()
+        };
+
+        // The directions have to be on opposite sides of the microfacet.
+        if dot(wo, half_vector) <= 0.0 || dot(wi, half_vector) >= 0.0 {
+            None
+        } else {
+            Some(half_vector)
+        }
+    }
+
+    /// Square root of the denominator of the Jacobian of the refraction.
+    fn refraction_denominator(&self, wo: Vec3, wi: Vec3, half_vector: Vec3) -> f32 {
+        dot(wo, half_vector) + self.eta * dot(wi, half_vector)
+    }
+}
+
+impl Bsdf for DielectricBsdf {
+    fn eval(&self, wo: Vec3, wi: Vec3) -> RgbColor {
+        // The cosine of `wi` cancels out with the one in the denominator.
+        // Like the smooth dielectric, the radiance isn't scaled by the
+        // squared ratio of the indices of refraction when transmitted.
+        let value = if wi.z > 0.0 {
+            let half_vector = match Self::reflection_half_vector(wo, wi) {
+                Some(half_vector) => half_vector,
+                None => return BLACK,
+            };
+
+            fresnel_dielectric(dot(wo, half_vector), self.eta)
+                * self.distribution.d(half_vector)
+                * self.distribution.g(wo, wi)
+                / (4.0 * wo.z)
+        } else if wi.z < 0.0 {
+            let half_vector = match self.refraction_half_vector(wo, wi) {
+                Some(half_vector) => half_vector,
+                None => return BLACK,
+            };
+
+            let cos_wo_half_vector = dot(wo, half_vector);
+            let reflectance = fresnel_dielectric(cos_wo_half_vector, self.eta);
+            let denominator = self.refraction_denominator(wo, wi, half_vector);
+
+            (1.0 - reflectance)
+                * self.distribution.d(half_vector)
+                * self.distribution.g(wo, wi)
+                * self.eta
+                * self.eta
+                * dot(wi, half_vector).abs()
+                * cos_wo_half_vector
+                / (wo.z * denominator * denominator)
+        } else {
+            return BLACK;
+        };
+
+        self.color * value
+    }
+
+    fn sample(&self, wo: Vec3, u: (f32, f32)) -> Option<BsdfSample> {
+        let normal = self.distribution.sample_visible_normal(wo, u);
+        let reflectance = fresnel_dielectric(dot(wo, normal), self.eta);
+
+        // Total internal reflection has a reflectance of one, so the normal
+        // always refracts when chosen to.
+        let wi = if self.reflection_choice < reflectance {
+            (-wo).reflect(normal)
         } else {
-            unit_direction.reflect(hit.normal)
+            (-wo).refract(normal, 1.0 / self.eta)
         }
         .unit();
 
-        let specular_ray = ray.secondary(hit.point, specular_direction);
+        // Normals near the horizon can reflect below the surface or refract
+        // above it, where neither scatters light.
+        if (wi.z > 0.0) != (dot(wi, normal) > 0.0) {
+            return None;
+        }
+
+        Some(BsdfSample::new(wi, self.eval(wo, wi), self.pdf(wo, wi)))
+    }
+
+    fn pdf(&self, wo: Vec3, wi: Vec3) -> f32 {
+        let reflection_pdf = match Self::reflection_half_vector(wo, wi) {
+            Some(half_vector) => {
+                let cos_wo_half_vector = dot(wo, half_vector);
+
+                fresnel_dielectric(cos_wo_half_vector, self.eta)
+                    * self.distribution.visible_normal_pdf(wo, half_vector)
+                    / (4.0 * cos_wo_half_vector)
+            }
+            None => 0.0,
+        };
+
+        let refraction_pdf = match self.refraction_half_vector(wo, wi) {
+            Some(half_vector) => {
+                let reflectance = fresnel_dielectric(dot(wo, half_vector), self.eta);
+                let denominator = self.refraction_denominator(wo, wi, half_vector);
+
+                (1.0 - reflectance)
+                    * self.distribution.visible_normal_pdf(wo, half_vector)
+                    * self.eta
+                    * self.eta
+                    * dot(wi, half_vector).abs()
+                    / (denominator * denominator)
+            }
+            None => 0.0,
+        };
+
+        reflection_pdf + refraction_pdf
+    }
+
+    fn flags(&self) -> BsdfFlags {
+        BsdfFlags::NON_DELTA
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        materials::{
+            bsdf::tests::{assert_samples_match_eval_and_pdf, sphere_grid, unit_square_grid},
+            Material,
+        },
+        rgb_color::WHITE,
+        samplers::IndependentSampler,
+    };
+
+    fn directions() -> [Vec3; 3] {
+        [
+            Vec3::z(),
+            Vec3::new(0.6, 0.0, 0.8),
+            Vec3::new(0.0, -0.95, 0.1).unit(),
+        ]
+    }
+
+    #[test]
+    fn reflects_and_refracts_smooth_dielectrics_as_delta_lobes() {
+        for &eta in [1.5, 1.0 / 1.5].iter() {
+            let bsdf = SmoothDielectricBsdf { eta, color: WHITE };
+
+            assert_eq!(bsdf.flags(), BsdfFlags::DELTA);
+
+            for wo in directions().iter() {
+                assert_samples_match_eval_and_pdf(&bsdf, *wo);
+
+                // Both lobes are picked with the probability of the light
+                // they scatter, so the weight of every sample is the tint.
+                for &u1 in [0.01, 0.99].iter() {
+                    let sample = bsdf.sample(*wo, (u1, 0.5)).unwrap();
+
+                    assert!(sample.is_delta);
+                    assert!((sample.weight().red - 1.0).abs() < 1e-5);
+                }
+            }
+        }
+    }
+
+    /// Rough BSDFs for every choice between reflection and refraction.
+    fn rough_bsdfs(roughness: f32, eta: f32) -> Vec<DielectricBsdf> {
+        (0..16)
+            .map(|index| {
+                DielectricBsdf::new(
+                    GgxDistribution::from_roughness(roughness),
+                    eta,
+                    WHITE,
+                    (index as f32 + 0.5) / 16.0,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn samples_rough_dielectrics_by_their_density() {
+        for &eta in [1.5, 1.0 / 1.5].iter() {
+            // Smoother refraction lobes are too narrow for the integration
+            // grid around the normal.
+            for &roughness in [0.6, 0.9].iter() {
+                let bsdfs = rough_bsdfs(roughness, eta);
+
+                for wo in directions().iter() {
+                    let mut reflected = 0;
+                    let mut sampled = 0;
+
+                    for bsdf in bsdfs.iter() {
+                        assert_eq!(bsdf.flags(), BsdfFlags::NON_DELTA);
+                        assert_samples_match_eval_and_pdf(bsdf, *wo);
+
+                        for u in unit_square_grid(32) {
+                            if let Some(sample) = bsdf.sample(*wo, u) {
+                                sampled += 1;
 
-        ScatterResult::Specular {
-            ray: specular_ray,
-            attenuation: self.color,
+                                if sample.wi.z > 0.0 {
+                                    reflected += 1;
+                                }
+                            }
+                        }
+                    }
+
+                    // Reflection is chosen with the Fresnel reflectance of the
+                    // sampled normal, which the density has to account for.
+                    // Samples are only dropped on the wrong side of the
+                    // surface, where the density is zero.
+                    let count = (bsdfs.len() * 32 * 32) as f32;
+                    let (mut reflection_pdf, mut refraction_pdf) = (0.0, 0.0);
+
+                    for (wi, solid_angle) in sphere_grid(800) {
+                        if wi.z > 0.0 {
+                            reflection_pdf += bsdfs[0].pdf(*wo, wi) * solid_angle;
+                        } else {
+                            refraction_pdf += bsdfs[0].pdf(*wo, wi) * solid_angle;
+                        }
+                    }
+
+                    for &(fraction, expected, kind) in [
+                        (reflected as f32 / count, reflection_pdf, "reflected"),
+                        (
+                            (sampled - reflected) as f32 / count,
+                            refraction_pdf,
+                            "refracted",
+                        ),
+                    ]
+                    .iter()
+                    {
+                        assert!(
+                            (fraction - expected).abs() < 0.02,
+                            "eta {}, roughness {}, {:?}: {} {} expected {}",
+                            eta,
+                            roughness,
+                            wo,
+                            kind,
+                            fraction,
+                            expected
+                        );
+                    }
+                }
+            }
         }
     }
+
+    #[test]
+    fn refracts_only_to_the_other_side() {
+        for &eta in [1.5, 1.0 / 1.5].iter() {
+            let bsdf = &rough_bsdfs(0.5, eta)[0];
+
+            for wo in directions().iter() {
+                for (wi, _) in sphere_grid(64) {
+                    let refraction = bsdf.refraction_half_vector(*wo, wi);
+
+                    if wi.z >= 0.0 {
+                        assert!(refraction.is_none(), "{:?} refracts to {:?}", wo, wi);
+                    } else if refraction.is_some() && bsdf.pdf(*wo, wi) > 0.0 {
+                        assert!(bsdf.eval(*wo, wi).red > 0.0);
+                    }
+                }
+            }
+        }
+
+        // Light entering the glass head-on is mostly transmitted.
+        let bsdf = &rough_bsdfs(0.3, 1.5)[15];
+
+        assert!(bsdf.pdf(Vec3::z(), -Vec3::z()) > 0.0);
+        assert!(bsdf.sample(Vec3::z(), (0.5, 0.5)).unwrap().wi.z < 0.0);
+    }
+
+    #[test]
+    fn reflects_totally_beyond_the_critical_angle() {
+        // Seen from inside glass, 60° is past the critical angle of 41.8°,
+        // and a smooth enough surface has no microfacet to refract through.
+        let wo = Vec3::new(
+            f32::to_radians(60.0).sin(),
+            0.0,
+            f32::to_radians(60.0).cos(),
+        );
+
+        for bsdf in rough_bsdfs(0.1, 1.0 / 1.5).iter() {
+            for u in unit_square_grid(16) {
+                let sample = bsdf.sample(wo, u).unwrap();
+
+                assert!(sample.wi.z > 0.0, "{:?} refracts to {:?}", wo, sample.wi);
+            }
+        }
+
+        let smooth = SmoothDielectricBsdf {
+            eta: 1.0 / 1.5,
+            color: WHITE,
+        };
+
+        for u in unit_square_grid(16) {
+            let sample = smooth.sample(wo, u).unwrap();
+
+            assert_eq!(sample.wi, Vec3::new(-wo.x, -wo.y, wo.z));
+            assert_eq!(sample.weight(), WHITE);
+        }
+    }
+
+    /// Weight of a path that reflects off the hit, and the value of a
+    /// rough reflection towards the normal.
+    fn scattering(dielectric: Dielectric, ray: &Ray, t: f32) -> (RgbColor, RgbColor) {
+        let dielectric = Arc::new(dielectric);
+        let hit = Hit::new(
+            ray,
+            t,
+            Vec3::z(),
+            Arc::new(Material::Reflective(dielectric.clone())),
+            0.0,
+            0.0,
+        );
+        let wo = Vec3::new(0.6, 0.0, 0.8);
+        let mut sampler = IndependentSampler::new(1);
+
+        let smooth = dielectric.bsdf(ray, &hit, &mut sampler);
+        let rough = dielectric.with_roughness(0.5).bsdf(ray, &hit, &mut sampler);
+
+        (
+            smooth.sample(wo, (0.0, 0.5)).unwrap().weight(),
+            rough.eval(wo, Vec3::z()),
+        )
+    }
+
+    #[test]
+    fn absorbs_light_along_the_distance_traveled_inside() {
+        let clear = Dielectric::new(1.5, WHITE);
+        let absorbing = clear.with_absorption(RgbColor::new(0.1, 0.5, 1.0));
+
+        // Leaves the medium after 1.5 times a direction of length 2.
+        let inside = Ray::new(Vec3::zero(), Vec3::new(0.0, 0.0, 2.0), 0.0);
+        let (clear_weight, clear_value) = scattering(clear, &inside, 1.5);
+        let (weight, value) = scattering(absorbing, &inside, 1.5);
+
+        let expected = RgbColor::new(f32::exp(-0.3), f32::exp(-1.5), f32::exp(-3.0));
+
+        for &(actual, expected) in [
+            (weight.red / clear_weight.red, expected.red),
+            (weight.green / clear_weight.green, expected.green),
+            (weight.blue / clear_weight.blue, expected.blue),
+            (value.red / clear_value.red, expected.red),
+            (value.blue / clear_value.blue, expected.blue),
+        ]
+        .iter()
+        {
+            assert!(
+                (actual - expected).abs() < 1e-5,
+                "{} != {}",
+                actual,
+                expected
+            );
+        }
+
+        // Rays arriving from the outside haven't gone through the medium.
+        let outside = Ray::new(Vec3::new(0.0, 0.0, 4.0), Vec3::new(0.0, 0.0, -2.0), 0.0);
+
+        assert_eq!(
+            scattering(absorbing, &outside, 1.5),
+            scattering(clear, &outside, 1.5)
+        );
+    }
 }
@@ -1,12 +1,14 @@
-use std::sync::Arc;
+use std::{f32::consts::PI, sync::Arc};
 
 use crate::{
     hittable::Hit,
-    materials::{ReflectiveMaterial, ScatterResult},
-    pdfs::CosinePdf,
+    materials::{Bsdf, BsdfFlags, BsdfSample, ReflectiveMaterial},
+    pdfs::random_cosine_direction,
     ray::Ray,
-    rgb_color::RgbColor,
+    rgb_color::{RgbColor, BLACK},
+    samplers::Sampler,
     textures::{SolidColor, Texture},
+    vec3::Vec3,
 };
 
 pub struct Lambertian {
@@ -28,10 +30,72 @@ impl Lambertian {
 }
 
 impl ReflectiveMaterial for Lambertian {
-    fn scatter(&self, _ray: &Ray, hit: &Hit) -> ScatterResult {
-        ScatterResult::Diffuse {
-            pdf: Arc::new(CosinePdf::new(hit.normal)),
-            attenuation: self.texture.value(hit.u, hit.v, hit.point),
+    fn bsdf(&self, _ray: &Ray, hit: &Hit, _sampler: &mut dyn Sampler) -> Arc<dyn Bsdf> {
+        Arc::new(LambertianBsdf {
+            albedo: self.texture.value(hit.u, hit.v, hit.point),
+        })
+    }
+}
+
+/// Reflects the same radiance in every direction, sampled by the cosine.
+struct LambertianBsdf {
+    albedo: RgbColor,
+}
+
+impl Bsdf for LambertianBsdf {
+    fn eval(&self, _wo: Vec3, wi: Vec3) -> RgbColor {
+        if wi.z <= 0.0 {
+            BLACK
+        } else {
+            self.albedo * (wi.z / PI)
+        }
+    }
+
+    fn sample(&self, wo: Vec3, u: (f32, f32)) -> Option<BsdfSample> {
+        let wi = random_cosine_direction(u);
+
+        Some(BsdfSample::new(wi, self.eval(wo, wi), self.pdf(wo, wi)))
+    }
+
+    fn pdf(&self, _wo: Vec3, wi: Vec3) -> f32 {
+        if wi.z <= 0.0 {
+            0.0
+        } else {
+            wi.z / PI
         }
     }
+
+    fn flags(&self) -> BsdfFlags {
+        BsdfFlags::NON_DELTA
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::materials::bsdf::tests::{
+        assert_pdf_integrates_to_sampled_fraction, assert_samples_match_eval_and_pdf,
+    };
+
+    #[test]
+    fn samples_the_cosine() {
+        let bsdf = LambertianBsdf {
+            albedo: RgbColor::new(0.2, 0.4, 0.8),
+        };
+
+        for &wo in [
+            Vec3::z(),
+            Vec3::new(0.6, 0.0, 0.8),
+            Vec3::new(0.0, -0.99, 0.1),
+        ]
+        .iter()
+        {
+            assert_samples_match_eval_and_pdf(&bsdf, wo);
+            assert_pdf_integrates_to_sampled_fraction(&bsdf, wo);
+        }
+
+        assert_eq!(bsdf.flags(), BsdfFlags::NON_DELTA);
+        assert_eq!(bsdf.eval(Vec3::z(), Vec3::new(0.6, 0.0, -0.8)), BLACK);
+    }
 }
@@ -1,6 +1,6 @@
 use std::sync::Arc;
 
-use crate::{hittable::Hit, pdfs::ScatteringPdf, ray::Ray, rgb_color::RgbColor};
+use crate::{hittable::Hit, materials::Bsdf, ray::Ray, rgb_color::RgbColor, samplers::Sampler};
 
 #[derive(Clone)]
 pub enum Material {
@@ -9,19 +9,9 @@ pub enum Material {
 }
 
 pub trait ReflectiveMaterial: Send + Sync {
-    fn scatter(&self, ray: &Ray, hit: &Hit) -> ScatterResult;
-}
-
-#[derive(Clone)]
-pub enum ScatterResult {
-    Diffuse {
-        pdf: Arc<dyn ScatteringPdf>,
-        attenuation: RgbColor,
-    },
-    Specular {
-        ray: Ray,
-        attenuation: RgbColor,
-    },
+    /// Scattering of the light that reaches the hit along the ray, in the
+    /// local frame of the normal of the hit.
+    fn bsdf(&self, ray: &Ray, hit: &Hit, sampler: &mut dyn Sampler) -> Arc<dyn Bsdf>;
 }
 
 pub trait EmissiveMaterial: Send + Sync {
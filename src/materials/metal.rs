@@ -1,34 +1,353 @@
+use std::sync::Arc;
+
 use crate::{
     hittable::Hit,
-    materials::{ReflectiveMaterial, ScatterResult},
+    materials::{
+        fresnel_conductor, fresnel_schlick, Bsdf, BsdfFlags, BsdfSample, GgxDistribution,
+        ReflectiveMaterial, MIN_ALPHA,
+    },
     ray::Ray,
-    rgb_color::RgbColor,
-    vec3::Vec3,
+    rgb_color::{RgbColor, BLACK},
+    samplers::Sampler,
+    vec3::{dot, Vec3},
 };
 
+/// How much light a metal reflects depending on the angle of incidence.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum ConductorFresnel {
+    /// Complex index of refraction `eta + i k` of every channel.
+    Complex { eta: RgbColor, k: RgbColor },
+    /// Schlick's approximation from the reflectance at normal incidence.
+    Schlick(RgbColor),
+}
+
+impl ConductorFresnel {
+    pub fn reflectance(&self, cos_theta: f32) -> RgbColor {
+        match *self {
+            Self::Complex { eta, k } => RgbColor::new(
+                fresnel_conductor(cos_theta, eta.red, k.red),
+                fresnel_conductor(cos_theta, eta.green, k.green),
+                fresnel_conductor(cos_theta, eta.blue, k.blue),
+            ),
+            Self::Schlick(normal_reflectance) => fresnel_schlick(cos_theta, normal_reflectance),
+        }
+    }
+}
+
+/// Complex indices of refraction of common metals, as the RGB constants
+/// that PBRT and Mitsuba integrate from measured spectra rather than values
+/// at single wavelengths.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum MetalPreset {
+    Gold,
+    Copper,
+    Aluminium,
+}
+
+impl MetalPreset {
+    pub const NAMES: [&'static str; 3] = ["gold", "copper", "aluminium"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "gold" => Some(Self::Gold),
+            "copper" => Some(Self::Copper),
+            "aluminium" => Some(Self::Aluminium),
+            _ => None,
+        }
+    }
+
+    pub fn fresnel(&self) -> ConductorFresnel {
+        let (eta, k) = match self {
+            Self::Gold => (
+                RgbColor::new(0.143, 0.374, 1.442),
+                RgbColor::new(3.983, 2.386, 1.603),
+            ),
+            Self::Copper => (
+                RgbColor::new(0.200, 0.924, 1.102),
+                RgbColor::new(3.912, 2.452, 2.142),
+            ),
+            Self::Aluminium => (
+                RgbColor::new(1.657, 0.880, 0.521),
+                RgbColor::new(9.224, 6.270, 4.837),
+            ),
+        };
+
+        ConductorFresnel::Complex { eta, k }
+    }
+}
+
+/// Conductor with a GGX distribution of microfacets. A roughness of zero is
+/// a perfect mirror.
 #[derive(Copy, Clone)]
 pub struct Metal {
-    pub albedo: RgbColor,
-    pub fuzziness: f32,
+    pub fresnel: ConductorFresnel,
+    /// Perceptual roughness, between 0 and 1.
+    pub roughness: f32,
 }
 
 impl Metal {
-    pub const fn new(albedo: RgbColor, fuzziness: f32) -> Self {
-        Self { albedo, fuzziness }
+    /// Metal that reflects `albedo` at normal incidence, with Schlick's
+    /// approximation. `roughness` is the perceptual GGX roughness, not the
+    /// fuzziness that `Metal::new` used to take: fuzz values have to be
+    /// converted, and tend to look smoother when used unchanged.
+    pub const fn new(albedo: RgbColor, roughness: f32) -> Self {
+        Self::with_fresnel(ConductorFresnel::Schlick(albedo), roughness)
+    }
+
+    pub const fn with_fresnel(fresnel: ConductorFresnel, roughness: f32) -> Self {
+        Self { fresnel, roughness }
+    }
+
+    pub fn from_preset(preset: MetalPreset, roughness: f32) -> Self {
+        Self::with_fresnel(preset.fresnel(), roughness)
     }
 }
 
 impl ReflectiveMaterial for Metal {
-    fn scatter(&self, ray: &Ray, hit: &Hit) -> ScatterResult {
-        let reflected_direction = ray.direction.reflect(hit.normal).unit();
+    fn bsdf(&self, _ray: &Ray, _hit: &Hit, _sampler: &mut dyn Sampler) -> Arc<dyn Bsdf> {
+        if self.roughness * self.roughness < MIN_ALPHA {
+            Arc::new(MirrorBsdf::new(self.fresnel))
+        } else {
+            Arc::new(ConductorBsdf::new(
+                GgxDistribution::from_roughness(self.roughness),
+                self.fresnel,
+            ))
+        }
+    }
+}
+
+/// Perfectly smooth metal.
+pub(crate) struct MirrorBsdf {
+    fresnel: ConductorFresnel,
+}
+
+impl MirrorBsdf {
+    pub(crate) fn new(fresnel: ConductorFresnel) -> Self {
+        Self { fresnel }
+    }
+}
 
-        let specular_direction =
-            (reflected_direction + Vec3::random_in_sphere(1.0) * self.fuzziness).unit();
-        let specular_ray = ray.secondary(hit.point, specular_direction);
+impl Bsdf for MirrorBsdf {
+    fn eval(&self, _wo: Vec3, _wi: Vec3) -> RgbColor {
+        BLACK
+    }
+
+    fn sample(&self, wo: Vec3, _u: (f32, f32)) -> Option<BsdfSample> {
+        let wi = Vec3::new(-wo.x, -wo.y, wo.z);
+
+        Some(BsdfSample::delta(wi, self.fresnel.reflectance(wo.z), 1.0))
+    }
+
+    fn pdf(&self, _wo: Vec3, _wi: Vec3) -> f32 {
+        0.0
+    }
 
-        ScatterResult::Specular {
-            ray: specular_ray,
-            attenuation: self.albedo,
+    fn flags(&self) -> BsdfFlags {
+        BsdfFlags::DELTA
+    }
+}
+
+/// Rough metal, reflecting the light off its microfacets.
+pub(crate) struct ConductorBsdf {
+    distribution: GgxDistribution,
+    fresnel: ConductorFresnel,
+}
+
+impl ConductorBsdf {
+    pub(crate) fn new(distribution: GgxDistribution, fresnel: ConductorFresnel) -> Self {
+        Self {
+            distribution,
+            fresnel,
         }
     }
+
+    fn half_vector(wo: Vec3, wi: Vec3) -> Option<Vec3> {
+        let half_vector = wo + wi;
+
+        if wo.z <= 0.0 || wi.z <= 0.0 || half_vector.len_squared() == 0.0 {
+            None
+        } else {
+            Some(half_vector.unit())
+        }
+    }
+}
+
+impl Bsdf for ConductorBsdf {
+    fn eval(&self, wo: Vec3, wi: Vec3) -> RgbColor {
+        let half_vector = match Self::half_vector(wo, wi) {
+            Some(half_vector) => half_vector,
+            None => return BLACK,
+        };
+
+        let d = self.distribution.d(half_vector);
+        let g = self.distribution.g(wo, wi);
+
+        // The cosine of `wi` cancels out with the one in the denominator.
+        self.fresnel.reflectance(dot(wo, half_vector)) * (d * g / (4.0 * wo.z))
+    }
+
+    fn sample(&self, wo: Vec3, u: (f32, f32)) -> Option<BsdfSample> {
+        let normal = self.distribution.sample_visible_normal(wo, u);
+        let wi = (-wo).reflect(normal);
+
+        // Normals near the horizon can reflect below the surface.
+        if wi.z <= 0.0 {
+            return None;
+        }
+
+        Some(BsdfSample::new(wi, self.eval(wo, wi), self.pdf(wo, wi)))
+    }
+
+    fn pdf(&self, wo: Vec3, wi: Vec3) -> f32 {
+        match Self::half_vector(wo, wi) {
+            // Reflecting the sampled normals divides their density by the
+            // Jacobian of the reflection.
+            Some(half_vector) => {
+                self.distribution.visible_normal_pdf(wo, half_vector) / (4.0 * dot(wo, half_vector))
+            }
+            None => 0.0,
+        }
+    }
+
+    fn flags(&self) -> BsdfFlags {
+        BsdfFlags::NON_DELTA
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        materials::{
+            bsdf::tests::{
+                assert_pdf_integrates_to_sampled_fraction, assert_samples_match_eval_and_pdf,
+                unit_square_grid,
+            },
+            Material,
+        },
+        rgb_color::WHITE,
+        samplers::IndependentSampler,
+    };
+
+    fn directions() -> [Vec3; 3] {
+        [
+            Vec3::z(),
+            Vec3::new(0.6, 0.0, 0.8),
+            Vec3::new(0.0, -0.95, 0.1).unit(),
+        ]
+    }
+
+    #[test]
+    fn reflects_smooth_metal_as_a_delta_lobe() {
+        let bsdf = MirrorBsdf {
+            fresnel: MetalPreset::Gold.fresnel(),
+        };
+
+        assert_eq!(bsdf.flags(), BsdfFlags::DELTA);
+
+        for wo in directions().iter() {
+            assert_samples_match_eval_and_pdf(&bsdf, *wo);
+
+            let sample = bsdf.sample(*wo, (0.5, 0.5)).unwrap();
+
+            assert!(sample.is_delta);
+            assert_eq!(sample.wi, Vec3::new(-wo.x, -wo.y, wo.z));
+        }
+    }
+
+    #[test]
+    fn samples_rough_metal_by_its_density() {
+        for &roughness in [0.3, 0.6, 1.0].iter() {
+            for fresnel in [
+                ConductorFresnel::Schlick(RgbColor::new(0.9, 0.6, 0.3)),
+                MetalPreset::Copper.fresnel(),
+            ]
+            .iter()
+            {
+                let bsdf = ConductorBsdf::new(GgxDistribution::from_roughness(roughness), *fresnel);
+
+                assert_eq!(bsdf.flags(), BsdfFlags::NON_DELTA);
+
+                for wo in directions().iter() {
+                    assert_samples_match_eval_and_pdf(&bsdf, *wo);
+                    assert_pdf_integrates_to_sampled_fraction(&bsdf, *wo);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn reflects_at_most_the_light_it_receives() {
+        // White furnace: a white metal can only lose the light that
+        // microfacets mask or shadow, which grows with the roughness.
+        for &roughness in [0.8, 1.0].iter() {
+            let bsdf = ConductorBsdf::new(
+                GgxDistribution::from_roughness(roughness),
+                ConductorFresnel::Schlick(WHITE),
+            );
+
+            for wo in directions().iter() {
+                let albedo = unit_square_grid(256)
+                    .filter_map(|u| bsdf.sample(*wo, u))
+                    .map(|sample| sample.weight().red)
+                    .sum::<f32>()
+                    / (256 * 256) as f32;
+
+                assert!(albedo <= 1.0 + 1e-3, "{}, {:?}: {}", roughness, wo, albedo);
+                assert!(albedo > 0.25, "{}, {:?}: {}", roughness, wo, albedo);
+            }
+        }
+    }
+
+    #[test]
+    fn reflects_the_albedo_of_presets_at_normal_incidence() {
+        for preset in MetalPreset::NAMES
+            .iter()
+            .map(|name| MetalPreset::from_name(name).unwrap())
+        {
+            let (eta, k) = match preset.fresnel() {
+                ConductorFresnel::Complex { eta, k } => (eta, k),
+                ConductorFresnel::Schlick(_) => panic!("{:?} isn't a complex index", preset),
+            };
+            let reflectance = preset.fresnel().reflectance(1.0);
+            let expected = |eta: f32, k: f32| {
+                ((eta - 1.0) * (eta - 1.0) + k * k) / ((eta + 1.0) * (eta + 1.0) + k * k)
+            };
+
+            assert!((reflectance.red - expected(eta.red, k.red)).abs() < 1e-5);
+            assert!((reflectance.green - expected(eta.green, k.green)).abs() < 1e-5);
+            assert!((reflectance.blue - expected(eta.blue, k.blue)).abs() < 1e-5);
+        }
+
+        // Gold reflects more red than blue, aluminium is nearly white.
+        let gold = MetalPreset::Gold.fresnel().reflectance(1.0);
+        let aluminium = MetalPreset::Aluminium.fresnel().reflectance(1.0);
+
+        assert!(gold.red > 0.9 && gold.blue < 0.5);
+        assert!(aluminium.red > 0.9 && aluminium.blue > 0.9);
+    }
+
+    #[test]
+    fn picks_the_lobe_from_the_roughness() {
+        let flags = |roughness: f32| {
+            let metal = Arc::new(Metal::new(RgbColor::new(0.9, 0.9, 0.9), roughness));
+            let ray = Ray::new(Vec3::z(), -Vec3::z(), 0.0);
+            let hit = Hit::new(
+                &ray,
+                1.0,
+                Vec3::z(),
+                Arc::new(Material::Reflective(metal.clone())),
+                0.0,
+                0.0,
+            );
+
+            metal
+                .bsdf(&ray, &hit, &mut IndependentSampler::new(1))
+                .flags()
+        };
+
+        assert_eq!(flags(0.0), BsdfFlags::DELTA);
+        assert_eq!(flags(0.5), BsdfFlags::NON_DELTA);
+    }
 }
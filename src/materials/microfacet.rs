@@ -0,0 +1,296 @@
+use std::f32::consts::PI;
+
+use crate::{
+    rgb_color::RgbColor,
+    vec3::{cross, dot, Vec3},
+};
+
+/// Lowest GGX alpha, below which the distribution is too peaked for floats
+/// and surfaces are better treated as perfectly smooth.
+pub const MIN_ALPHA: f32 = 1e-3;
+
+/// Isotropic GGX (Trowbridge-Reitz) distribution of microfacet normals.
+/// Directions are in a local frame with the macrosurface normal along z.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct GgxDistribution {
+    pub alpha: f32,
+}
+
+impl GgxDistribution {
+    /// Takes the perceptual roughness, whose square is the alpha of the
+    /// distribution.
+    pub fn from_roughness(roughness: f32) -> Self {
+        Self {
+            alpha: f32::max(roughness * roughness, MIN_ALPHA),
+        }
+    }
+
+    /// Density of microfacet normals, projected onto the macrosurface.
+    pub fn d(&self, normal: Vec3) -> f32 {
+        if normal.z <= 0.0 {
+            return 0.0;
+        }
+
+        let alpha_squared = self.alpha * self.alpha;
+        let denominator = normal.z * normal.z * (alpha_squared - 1.0) + 1.0;
+
+        alpha_squared / (PI * denominator * denominator)
+    }
+
+    /// Smith's auxiliary function, the microfacet area masked from the
+    /// direction over the visible area.
+    fn lambda(&self, direction: Vec3) -> f32 {
+        let cos_squared = direction.z * direction.z;
+
+        if cos_squared == 0.0 {
+            return f32::INFINITY;
+        }
+
+        let tan_squared = f32::max(1.0 - cos_squared, 0.0) / cos_squared;
+
+        (-1.0 + f32::sqrt(1.0 + self.alpha * self.alpha * tan_squared)) / 2.0
+    }
+
+    /// Fraction of the microfacets visible from the direction.
+    pub fn g1(&self, direction: Vec3) -> f32 {
+        1.0 / (1.0 + self.lambda(direction))
+    }
+
+    /// Height correlated fraction of the microfacets visible from both
+    /// directions.
+    pub fn g(&self, wo: Vec3, wi: Vec3) -> f32 {
+        1.0 / (1.0 + self.lambda(wo) + self.lambda(wi))
+    }
+
+    /// Density of the microfacet normals visible from `wo`.
+    pub fn visible_normal_pdf(&self, wo: Vec3, normal: Vec3) -> f32 {
+        let cos_wo_normal = dot(wo, normal);
+
+        if wo.z <= 0.0 || cos_wo_normal <= 0.0 {
+            return 0.0;
+        }
+
+        self.g1(wo) * cos_wo_normal * self.d(normal) / wo.z
+    }
+
+    /// Samples a microfacet normal visible from `wo`, following Heitz's
+    /// "Sampling the GGX Distribution of Visible Normals".
+    pub fn sample_visible_normal(&self, wo: Vec3, (u1, u2): (f32, f32)) -> Vec3 {
+        // Stretches the view so that the distribution becomes a hemisphere.
+        let view = Vec3::new(self.alpha * wo.x, self.alpha * wo.y, wo.z).unit();
+
+        let length_squared = view.x * view.x + view.y * view.y;
+        let t1 = if length_squared > 0.0 {
+            Vec3::new(-view.y, view.x, 0.0) / length_squared.sqrt()
+        } else {
+            Vec3::x()
+        };
+        let t2 = cross(view, t1);
+
+        // Uniform point on the disk, squeezed onto the visible half of it.
+        let r = u1.sqrt();
+        let phi = 2.0 * PI * u2;
+        let p1 = r * phi.cos();
+        let s = (1.0 + view.z) / 2.0;
+        let p2 = (1.0 - s) * f32::sqrt(1.0 - p1 * p1) + s * r * phi.sin();
+
+        let hemisphere_normal =
+            p1 * t1 + p2 * t2 + f32::sqrt(f32::max(1.0 - p1 * p1 - p2 * p2, 0.0)) * view;
+
+        Vec3::new(
+            self.alpha * hemisphere_normal.x,
+            self.alpha * hemisphere_normal.y,
+            f32::max(hemisphere_normal.z, 0.0),
+        )
+        .unit()
+    }
+}
+
+/// Fresnel reflectance of a conductor with a complex index of refraction
+/// `eta + i k`, relative to the outside medium.
+pub fn fresnel_conductor(cos_theta: f32, eta: f32, k: f32) -> f32 {
+    let cos_squared = cos_theta.clamp(0.0, 1.0).powi(2);
+    let sin_squared = 1.0 - cos_squared;
+
+    let eta_squared = eta * eta;
+    let k_squared = k * k;
+
+    let t0 = eta_squared - k_squared - sin_squared;
+    let a_squared_plus_b_squared = f32::sqrt(t0 * t0 + 4.0 * eta_squared * k_squared);
+    let a = f32::sqrt(f32::max((a_squared_plus_b_squared + t0) / 2.0, 0.0));
+
+    let t1 = a_squared_plus_b_squared + cos_squared;
+    let t2 = 2.0 * cos_squared.sqrt() * a;
+    let perpendicular = (t1 - t2) / (t1 + t2);
+
+    let t3 = cos_squared * a_squared_plus_b_squared + sin_squared * sin_squared;
+    let t4 = t2 * sin_squared;
+    let parallel = perpendicular * (t3 - t4) / (t3 + t4);
+
+    (perpendicular + parallel) / 2.0
+}
+
+/// Schlick's approximation of the Fresnel reflectance, from the reflectance
+/// at normal incidence.
+pub fn fresnel_schlick(cos_theta: f32, normal_reflectance: RgbColor) -> RgbColor {
+    let weight = (1.0 - cos_theta.clamp(0.0, 1.0)).powi(5);
+
+    normal_reflectance * (1.0 - weight) + RgbColor::new(weight, weight, weight)
+}
+
+/// Fresnel reflectance of a dielectric boundary, where `eta` is the index of
+/// refraction on the transmitted side over the one on the incident side.
+pub fn fresnel_dielectric(cos_theta: f32, eta: f32) -> f32 {
+    let cos_incident = cos_theta.clamp(0.0, 1.0);
+    let sin_squared_transmitted = (1.0 - cos_incident * cos_incident) / (eta * eta);
+
+    // Total internal reflection.
+    if sin_squared_transmitted >= 1.0 {
+        return 1.0;
+    }
+
+    let cos_transmitted = f32::sqrt(1.0 - sin_squared_transmitted);
+
+    let parallel = (eta * cos_incident - cos_transmitted) / (eta * cos_incident + cos_transmitted);
+    let perpendicular =
+        (cos_incident - eta * cos_transmitted) / (cos_incident + eta * cos_transmitted);
+
+    (parallel * parallel + perpendicular * perpendicular) / 2.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::materials::bsdf::tests::{sphere_grid, unit_square_grid};
+
+    const ALPHAS: [f32; 3] = [0.05, 0.3, 1.0];
+
+    fn directions() -> [Vec3; 3] {
+        [
+            Vec3::z(),
+            Vec3::new(0.6, 0.0, 0.8),
+            Vec3::new(0.0, -0.95, 0.1).unit(),
+        ]
+    }
+
+    #[test]
+    fn projects_normals_onto_the_whole_macrosurface() {
+        for &alpha in ALPHAS.iter() {
+            let distribution = GgxDistribution { alpha };
+
+            // Midpoint rule over the cosine of the normals, which are
+            // isotropic.
+            let steps = 100_000;
+            let integral: f32 = (0..steps)
+                .map(|step| {
+                    let cos_theta = (step as f32 + 0.5) / steps as f32;
+                    let sin_theta = f32::sqrt(1.0 - cos_theta * cos_theta);
+
+                    distribution.d(Vec3::new(sin_theta, 0.0, cos_theta)) * cos_theta
+                })
+                .sum::<f32>()
+                * 2.0
+                * PI
+                / steps as f32;
+
+            assert!((integral - 1.0).abs() < 1e-3, "{}: {}", alpha, integral);
+        }
+    }
+
+    #[test]
+    fn masks_less_towards_the_normal() {
+        for &alpha in ALPHAS.iter() {
+            let distribution = GgxDistribution { alpha };
+
+            assert_eq!(distribution.g1(Vec3::z()), 1.0);
+
+            for wo in directions().iter() {
+                for wi in directions().iter() {
+                    let g = distribution.g(*wo, *wi);
+
+                    assert!(g > 0.0 && g <= f32::min(distribution.g1(*wo), distribution.g1(*wi)));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn samples_visible_normals_by_their_density() {
+        // Fraction of the normals in every cell of a grid over the cosine
+        // and the azimuth of the hemisphere.
+        let cell = |normal: Vec3| {
+            let azimuth = (normal.y.atan2(normal.x) / (2.0 * PI) + 1.0).fract();
+
+            usize::min((normal.z * 4.0) as usize, 3) * 8 + usize::min((azimuth * 8.0) as usize, 7)
+        };
+
+        // Smoother distributions are too peaked for the integration grid.
+        for &alpha in ALPHAS[1..].iter() {
+            let distribution = GgxDistribution { alpha };
+
+            for wo in directions().iter() {
+                let mut sampled = [0.0; 32];
+
+                for u in unit_square_grid(256) {
+                    let normal = distribution.sample_visible_normal(*wo, u);
+
+                    assert!((normal.len() - 1.0).abs() < 1e-4 && normal.z >= 0.0);
+
+                    sampled[cell(normal)] += 1.0 / (256 * 256) as f32;
+                }
+
+                let mut expected = [0.0; 32];
+
+                for (normal, solid_angle) in sphere_grid(800) {
+                    if normal.z > 0.0 {
+                        expected[cell(normal)] +=
+                            distribution.visible_normal_pdf(*wo, normal) * solid_angle;
+                    }
+                }
+
+                for (index, (fraction, expected)) in sampled.iter().zip(expected.iter()).enumerate()
+                {
+                    assert!(
+                        (fraction - expected).abs() < 0.01,
+                        "alpha {}, {:?}, cell {}: sampled {} expected {}",
+                        alpha,
+                        wo,
+                        index,
+                        fraction,
+                        expected
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn reflects_conductors_from_their_complex_index() {
+        for &(eta, k) in [(0.143, 3.983), (1.657, 9.224), (1.5, 0.0)].iter() {
+            let expected =
+                ((eta - 1.0) * (eta - 1.0) + k * k) / ((eta + 1.0) * (eta + 1.0) + k * k);
+
+            assert!((fresnel_conductor(1.0, eta, k) - expected).abs() < 1e-5);
+            assert!((fresnel_conductor(0.0, eta, k) - 1.0).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn reflects_dielectrics_and_totally_inside_them() {
+        assert!((fresnel_dielectric(1.0, 1.5) - 0.04).abs() < 1e-5);
+        assert!((fresnel_dielectric(1.0, 1.0 / 1.5) - 0.04).abs() < 1e-5);
+
+        // The critical angle of glass seen from the inside is about 41.8°.
+        assert!(fresnel_dielectric(f32::to_radians(40.0).cos(), 1.0 / 1.5) < 1.0);
+        assert_eq!(
+            fresnel_dielectric(f32::to_radians(43.0).cos(), 1.0 / 1.5),
+            1.0
+        );
+
+        let schlick = fresnel_schlick(1.0, RgbColor::new(0.2, 0.4, 0.6));
+
+        assert_eq!(schlick, RgbColor::new(0.2, 0.4, 0.6));
+        assert_eq!(fresnel_schlick(0.0, schlick), RgbColor::new(1.0, 1.0, 1.0));
+    }
+}
@@ -1,7 +1,13 @@
+mod bsdf;
 mod dielectric;
 mod diffuse_light;
 mod lambertian;
 mod material;
 mod metal;
+mod microfacet;
+mod principled;
 
-pub use self::{dielectric::*, diffuse_light::*, lambertian::*, material::*, metal::*};
+pub use self::{
+    bsdf::*, dielectric::*, diffuse_light::*, lambertian::*, material::*, metal::*, microfacet::*,
+    principled::*,
+};
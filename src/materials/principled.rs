@@ -0,0 +1,586 @@
+use std::{f32::consts::PI, sync::Arc};
+
+use crate::{
+    hittable::Hit,
+    materials::{
+        fresnel_schlick, Bsdf, BsdfFlags, BsdfSample, ConductorBsdf, ConductorFresnel,
+        DielectricBsdf, GgxDistribution, MirrorBsdf, ReflectiveMaterial, SmoothDielectricBsdf,
+        MIN_ALPHA,
+    },
+    pdfs::random_cosine_direction,
+    ray::Ray,
+    rgb_color::{RgbColor, BLACK, WHITE},
+    samplers::{Sampler, ONE_MINUS_EPSILON},
+    textures::{SolidColor, Texture},
+    vec3::{dot, Vec3},
+};
+
+/// Reflectance at normal incidence of the clearcoat, a varnish with an index
+/// of refraction of 1.5.
+const CLEARCOAT_NORMAL_REFLECTANCE: RgbColor = RgbColor::new(0.04, 0.04, 0.04);
+const CLEARCOAT_ROUGHNESS: f32 = 0.1;
+/// Reflectance at normal incidence of dielectrics with a `specular` of one.
+const MAX_SPECULAR_REFLECTANCE: f32 = 0.08;
+
+/// Material that blends diffuse, metallic, glossy and transmissive surfaces,
+/// after Burley's "Physically Based Shading at Disney". Every parameter but
+/// the base color is read from the luminance of its texture, between 0 and 1.
+#[derive(Clone)]
+pub struct Principled {
+    pub base_color: Arc<dyn Texture>,
+    /// Blends from a dielectric to a metal that reflects the base color.
+    pub metallic: Arc<dyn Texture>,
+    /// Perceptual roughness of the specular reflection and transmission.
+    pub roughness: Arc<dyn Texture>,
+    /// Reflectance of dielectrics, where 0.5 is the 4% of common materials.
+    pub specular: Arc<dyn Texture>,
+    /// White reflection at grazing angles, for cloth.
+    pub sheen: Arc<dyn Texture>,
+    /// Smooth varnish layer over the rest of the material.
+    pub clearcoat: Arc<dyn Texture>,
+    /// Blends the dielectric base into glass tinted by the base color.
+    pub transmission: Arc<dyn Texture>,
+    pub refractive_index: f32,
+}
+
+impl Principled {
+    pub fn new(base_color: Arc<dyn Texture>) -> Self {
+        Self {
+            base_color,
+            metallic: Arc::new(SolidColor::gray(0.0)),
+            roughness: Arc::new(SolidColor::gray(0.5)),
+            specular: Arc::new(SolidColor::gray(0.5)),
+            sheen: Arc::new(SolidColor::gray(0.0)),
+            clearcoat: Arc::new(SolidColor::gray(0.0)),
+            transmission: Arc::new(SolidColor::gray(0.0)),
+            refractive_index: 1.5,
+        }
+    }
+
+    pub fn from_rgb_color(color: RgbColor) -> Self {
+        Self::new(Arc::new(SolidColor::from_rgb_color(color)))
+    }
+
+    pub fn with_metallic(self, metallic: Arc<dyn Texture>) -> Self {
+        Self { metallic, ..self }
+    }
+
+    pub fn with_roughness(self, roughness: Arc<dyn Texture>) -> Self {
+        Self { roughness, ..self }
+    }
+
+    pub fn with_specular(self, specular: Arc<dyn Texture>) -> Self {
+        Self { specular, ..self }
+    }
+
+    pub fn with_sheen(self, sheen: Arc<dyn Texture>) -> Self {
+        Self { sheen, ..self }
+    }
+
+    pub fn with_clearcoat(self, clearcoat: Arc<dyn Texture>) -> Self {
+        Self { clearcoat, ..self }
+    }
+
+    pub fn with_transmission(self, transmission: Arc<dyn Texture>) -> Self {
+        Self {
+            transmission,
+            ..self
+        }
+    }
+
+    pub fn with_refractive_index(self, refractive_index: f32) -> Self {
+        Self {
+            refractive_index,
+            ..self
+        }
+    }
+}
+
+fn parameter(texture: &Arc<dyn Texture>, hit: &Hit) -> f32 {
+    texture
+        .value(hit.u, hit.v, hit.point)
+        .luminance()
+        .clamp(0.0, 1.0)
+}
+
+impl Principled {
+    fn principled_bsdf(&self, ray: &Ray, hit: &Hit, sampler: &mut dyn Sampler) -> PrincipledBsdf {
+        let base_color = self.base_color.value(hit.u, hit.v, hit.point);
+        let metallic = parameter(&self.metallic, hit);
+        let roughness = parameter(&self.roughness, hit);
+        let specular = parameter(&self.specular, hit);
+        let sheen = parameter(&self.sheen, hit);
+        let clearcoat = parameter(&self.clearcoat, hit);
+        let transmission = parameter(&self.transmission, hit);
+
+        let cos_wo = dot(-ray.direction.unit(), hit.normal);
+        let distribution = GgxDistribution::from_roughness(roughness);
+
+        // Light reflected by the clearcoat doesn't reach the layers below.
+        let clearcoat_reflectance =
+            clearcoat * fresnel_schlick(cos_wo, CLEARCOAT_NORMAL_REFLECTANCE).luminance();
+        let base_weight = 1.0 - clearcoat_reflectance;
+
+        let diffuse_weight = (1.0 - metallic) * (1.0 - transmission) * base_weight;
+        let transmission_weight = (1.0 - metallic) * transmission * base_weight;
+        let specular_weight = (1.0 - (1.0 - metallic) * transmission) * base_weight;
+
+        let dielectric_reflectance = MAX_SPECULAR_REFLECTANCE * specular;
+        let specular_color = RgbColor::new(
+            dielectric_reflectance,
+            dielectric_reflectance,
+            dielectric_reflectance,
+        ) * (1.0 - metallic)
+            + base_color * metallic;
+
+        let eta = if hit.front_face {
+            self.refractive_index
+        } else {
+            1.0 / self.refractive_index
+        };
+
+        // Like `Metal` and `Dielectric`, surfaces too smooth for the
+        // distribution reflect and refract through delta lobes, which
+        // sampling the lights can't find.
+        let reflection_choice = sampler.next_1d();
+        let (specular_bsdf, transmission_bsdf): (Arc<dyn Bsdf>, Arc<dyn Bsdf>) =
+            if roughness * roughness < MIN_ALPHA {
+                (
+                    Arc::new(MirrorBsdf::new(ConductorFresnel::Schlick(specular_color))),
+                    Arc::new(SmoothDielectricBsdf::new(eta, base_color)),
+                )
+            } else {
+                (
+                    Arc::new(ConductorBsdf::new(
+                        distribution,
+                        ConductorFresnel::Schlick(specular_color),
+                    )),
+                    Arc::new(DielectricBsdf::new(
+                        distribution,
+                        eta,
+                        base_color,
+                        reflection_choice,
+                    )),
+                )
+            };
+
+        // Every lobe is picked with a probability that follows the fraction
+        // of the light it roughly scatters.
+        let candidates: Vec<(f32, f32, Arc<dyn Bsdf>)> = vec![
+            (
+                diffuse_weight * (base_color.luminance() + sheen),
+                diffuse_weight,
+                Arc::new(DiffuseSheenBsdf {
+                    color: base_color,
+                    sheen,
+                    roughness,
+                }),
+            ),
+            (
+                specular_weight * fresnel_schlick(cos_wo, specular_color).luminance(),
+                specular_weight,
+                specular_bsdf,
+            ),
+            (
+                transmission_weight * base_color.luminance(),
+                transmission_weight,
+                transmission_bsdf,
+            ),
+            (
+                clearcoat_reflectance,
+                clearcoat,
+                Arc::new(ConductorBsdf::new(
+                    GgxDistribution::from_roughness(CLEARCOAT_ROUGHNESS),
+                    ConductorFresnel::Schlick(CLEARCOAT_NORMAL_REFLECTANCE),
+                )),
+            ),
+        ];
+
+        let total: f32 = candidates.iter().map(|(albedo, _, _)| albedo).sum();
+
+        // Black materials are left without lobes, and scatter nothing.
+        let lobes = candidates
+            .into_iter()
+            .filter(|(albedo, _, _)| *albedo > 0.0)
+            .map(|(albedo, weight, bsdf)| Lobe {
+                probability: albedo / total,
+                weight,
+                bsdf,
+            })
+            .collect();
+
+        PrincipledBsdf { lobes }
+    }
+}
+
+impl ReflectiveMaterial for Principled {
+    fn bsdf(&self, ray: &Ray, hit: &Hit, sampler: &mut dyn Sampler) -> Arc<dyn Bsdf> {
+        Arc::new(self.principled_bsdf(ray, hit, sampler))
+    }
+}
+
+struct Lobe {
+    probability: f32,
+    weight: f32,
+    bsdf: Arc<dyn Bsdf>,
+}
+
+/// Sum of the lobes of a principled material, sampled by picking one of
+/// them.
+struct PrincipledBsdf {
+    lobes: Vec<Lobe>,
+}
+
+impl Bsdf for PrincipledBsdf {
+    fn eval(&self, wo: Vec3, wi: Vec3) -> RgbColor {
+        self.lobes.iter().fold(BLACK, |sum, lobe| {
+            sum + lobe.bsdf.eval(wo, wi) * lobe.weight
+        })
+    }
+
+    fn sample(&self, wo: Vec3, (u1, u2): (f32, f32)) -> Option<BsdfSample> {
+        // The sample that picks the lobe is stretched back to the unit
+        // interval, like in `MixturePdf`.
+        let mut u1 = u1;
+
+        for (index, lobe) in self.lobes.iter().enumerate() {
+            if u1 < lobe.probability || index + 1 == self.lobes.len() {
+                let u1 = f32::min(u1 / lobe.probability, ONE_MINUS_EPSILON);
+                let sample = lobe.bsdf.sample(wo, (u1, u2))?;
+
+                // Only the picked lobe scatters into a delta direction.
+                if sample.is_delta {
+                    return Some(BsdfSample::delta(
+                        sample.wi,
+                        sample.value * lobe.weight,
+                        sample.pdf * lobe.probability,
+                    ));
+                }
+
+                // The direction could have been sampled by any of the lobes
+                // that aren't delta.
+                let wi = sample.wi;
+
+                return Some(BsdfSample::new(wi, self.eval(wo, wi), self.pdf(wo, wi)));
+            }
+
+            u1 -= lobe.probability;
+        }
+
+        None
+    }
+
+    fn pdf(&self, wo: Vec3, wi: Vec3) -> f32 {
+        self.lobes
+            .iter()
+            .map(|lobe| lobe.probability * lobe.bsdf.pdf(wo, wi))
+            .sum()
+    }
+
+    fn flags(&self) -> BsdfFlags {
+        self.lobes.iter().fold(
+            BsdfFlags {
+                delta: false,
+                non_delta: false,
+            },
+            |flags, lobe| {
+                let lobe_flags = lobe.bsdf.flags();
+
+                BsdfFlags {
+                    delta: flags.delta || lobe_flags.delta,
+                    non_delta: flags.non_delta || lobe_flags.non_delta,
+                }
+            },
+        )
+    }
+}
+
+/// Burley's diffuse reflection, which gets brighter at grazing angles on
+/// rough surfaces and darker on smooth ones, plus the sheen.
+struct DiffuseSheenBsdf {
+    color: RgbColor,
+    sheen: f32,
+    roughness: f32,
+}
+
+impl Bsdf for DiffuseSheenBsdf {
+    fn eval(&self, wo: Vec3, wi: Vec3) -> RgbColor {
+        let half_vector = wo + wi;
+
+        if wo.z <= 0.0 || wi.z <= 0.0 || half_vector.len_squared() == 0.0 {
+            return BLACK;
+        }
+
+        let cos_difference = dot(wi, half_vector.unit());
+        let grazing_weight = |cos_theta: f32| (1.0 - cos_theta).powi(5);
+
+        let retroreflection = 0.5 + 2.0 * self.roughness * cos_difference * cos_difference;
+        let diffuse = (1.0 + (retroreflection - 1.0) * grazing_weight(wi.z))
+            * (1.0 + (retroreflection - 1.0) * grazing_weight(wo.z))
+            / PI;
+        let sheen = self.sheen * grazing_weight(cos_difference);
+
+        (self.color * diffuse + WHITE * sheen) * wi.z
+    }
+
+    fn sample(&self, wo: Vec3, u: (f32, f32)) -> Option<BsdfSample> {
+        let wi = random_cosine_direction(u);
+
+        Some(BsdfSample::new(wi, self.eval(wo, wi), self.pdf(wo, wi)))
+    }
+
+    fn pdf(&self, _wo: Vec3, wi: Vec3) -> f32 {
+        if wi.z <= 0.0 {
+            0.0
+        } else {
+            wi.z / PI
+        }
+    }
+
+    fn flags(&self) -> BsdfFlags {
+        BsdfFlags::NON_DELTA
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        materials::{
+            bsdf::tests::{
+                assert_samples_match_eval_and_pdf, integrate_sphere, sphere_grid, unit_square_grid,
+            },
+            Lambertian, Material, Metal,
+        },
+        samplers::IndependentSampler,
+    };
+
+    fn gray(value: f32) -> Arc<dyn Texture> {
+        Arc::new(SolidColor::gray(value))
+    }
+
+    /// Material seen along `-wo`, on a surface whose normal is along z.
+    fn hit(material: Arc<dyn ReflectiveMaterial>, wo: Vec3) -> (Ray, Hit) {
+        let ray = Ray::new(wo, -wo, 0.0);
+        let hit = Hit::new(
+            &ray,
+            1.0,
+            Vec3::z(),
+            Arc::new(Material::Reflective(material)),
+            0.0,
+            0.0,
+        );
+
+        (ray, hit)
+    }
+
+    fn principled_bsdf(principled: Principled, wo: Vec3) -> PrincipledBsdf {
+        let principled = Arc::new(principled);
+        let (ray, hit) = hit(principled.clone(), wo);
+
+        principled.principled_bsdf(&ray, &hit, &mut IndependentSampler::new(1))
+    }
+
+    fn bsdf(material: Arc<dyn ReflectiveMaterial>, wo: Vec3) -> Arc<dyn Bsdf> {
+        let (ray, hit) = hit(material.clone(), wo);
+
+        material.bsdf(&ray, &hit, &mut IndependentSampler::new(1))
+    }
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() <= 1e-4 * f32::max(expected.abs(), 1.0),
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    fn mixed(roughness: f32) -> Principled {
+        Principled::from_rgb_color(RgbColor::new(0.8, 0.4, 0.2))
+            .with_metallic(gray(0.3))
+            .with_roughness(gray(roughness))
+            .with_sheen(gray(0.5))
+            .with_clearcoat(gray(0.5))
+            .with_transmission(gray(0.5))
+    }
+
+    fn directions() -> [Vec3; 3] {
+        [
+            Vec3::z(),
+            Vec3::new(0.6, 0.0, 0.8),
+            Vec3::new(0.0, -0.8, 0.6),
+        ]
+    }
+
+    #[test]
+    fn matches_metal_when_metallic() {
+        let color = RgbColor::new(0.9, 0.6, 0.3);
+
+        for &roughness in [0.0, 0.5].iter() {
+            let principled = Principled::from_rgb_color(color)
+                .with_metallic(gray(1.0))
+                .with_roughness(gray(roughness));
+
+            for wo in directions().iter() {
+                let principled = principled_bsdf(principled.clone(), *wo);
+                let metal = bsdf(Arc::new(Metal::new(color, roughness)), *wo);
+
+                assert_eq!(principled.lobes.len(), 1);
+                assert_eq!(principled.flags(), metal.flags());
+
+                for (wi, _) in sphere_grid(32) {
+                    let (value, expected) = (principled.eval(*wo, wi), metal.eval(*wo, wi));
+
+                    assert_close(value.red, expected.red);
+                    assert_close(value.blue, expected.blue);
+                    assert_close(principled.pdf(*wo, wi), metal.pdf(*wo, wi));
+                }
+
+                let sample = principled.sample(*wo, (0.3, 0.7)).unwrap();
+                let expected = metal.sample(*wo, (0.3, 0.7)).unwrap();
+
+                assert!((sample.wi - expected.wi).len() < 1e-5);
+                assert_close(sample.weight().green, expected.weight().green);
+            }
+        }
+    }
+
+    #[test]
+    fn matches_lambertian_without_other_lobes() {
+        let color = RgbColor::new(0.8, 0.4, 0.2);
+
+        // Burley's diffuse is Lambertian at normal incidence, where the
+        // dielectric reflection of a zero `specular` vanishes too.
+        for &roughness in [0.25, 1.0].iter() {
+            let principled = principled_bsdf(
+                Principled::from_rgb_color(color)
+                    .with_roughness(gray(roughness))
+                    .with_specular(gray(0.0)),
+                Vec3::z(),
+            );
+            let lambertian = bsdf(Arc::new(Lambertian::from_rgb_color(color)), Vec3::z());
+
+            assert_eq!(principled.lobes.len(), 1);
+            assert_eq!(principled.flags(), BsdfFlags::NON_DELTA);
+
+            let (value, expected) = (
+                principled.eval(Vec3::z(), Vec3::z()),
+                lambertian.eval(Vec3::z(), Vec3::z()),
+            );
+
+            assert_close(value.red, expected.red);
+            assert_close(value.blue, expected.blue);
+
+            for (wi, _) in sphere_grid(32) {
+                assert_close(principled.pdf(Vec3::z(), wi), lambertian.pdf(Vec3::z(), wi));
+            }
+        }
+    }
+
+    #[test]
+    fn samples_the_mixture_of_lobes_by_its_density() {
+        for wo in directions().iter() {
+            let bsdf = principled_bsdf(mixed(0.7), *wo);
+
+            assert_eq!(bsdf.lobes.len(), 4);
+            assert_eq!(bsdf.flags(), BsdfFlags::NON_DELTA);
+            assert_samples_match_eval_and_pdf(&bsdf, *wo);
+
+            // The clearcoat is too smooth for the integration grid.
+            let principled = Arc::new(mixed(0.7).with_clearcoat(gray(0.0)));
+            let (ray, hit) = hit(principled.clone(), *wo);
+
+            // Which way the transmission lobe scatters is drawn with the
+            // BSDF, so the samples of several of them are needed to follow
+            // its density.
+            let bsdfs: Vec<_> = (0..16)
+                .map(|seed| {
+                    principled.principled_bsdf(&ray, &hit, &mut IndependentSampler::new(seed))
+                })
+                .collect();
+
+            let kept = bsdfs
+                .iter()
+                .flat_map(|bsdf| unit_square_grid(128).filter_map(move |u| bsdf.sample(*wo, u)))
+                .count() as f32
+                / (16 * 128 * 128) as f32;
+            let integral = integrate_sphere(800, |wi| bsdfs[0].pdf(*wo, wi));
+
+            assert!(
+                (integral - kept).abs() < 0.02,
+                "{:?}: density integrates to {} but {} of the samples are kept",
+                wo,
+                integral,
+                kept
+            );
+        }
+    }
+
+    #[test]
+    fn picks_lobes_with_probabilities_that_sum_to_one() {
+        for principled in [
+            mixed(0.5),
+            mixed(0.0),
+            Principled::from_rgb_color(RgbColor::new(0.5, 0.5, 0.5)),
+            Principled::from_rgb_color(WHITE).with_metallic(gray(1.0)),
+            Principled::from_rgb_color(BLACK).with_clearcoat(gray(1.0)),
+        ]
+        .iter()
+        {
+            for wo in directions().iter() {
+                let bsdf = principled_bsdf(principled.clone(), *wo);
+                let total: f32 = bsdf.lobes.iter().map(|lobe| lobe.probability).sum();
+
+                assert!(!bsdf.lobes.is_empty());
+                assert!((total - 1.0).abs() < 1e-5, "{}", total);
+                assert!(bsdf.lobes.iter().all(|lobe| lobe.probability > 0.0));
+            }
+        }
+    }
+
+    #[test]
+    fn mixes_delta_and_non_delta_lobes_when_smooth() {
+        let metal = principled_bsdf(
+            Principled::from_rgb_color(WHITE)
+                .with_metallic(gray(1.0))
+                .with_roughness(gray(0.0)),
+            Vec3::new(0.6, 0.0, 0.8),
+        );
+
+        assert_eq!(metal.flags(), BsdfFlags::DELTA);
+
+        for wo in directions().iter() {
+            let bsdf = principled_bsdf(mixed(0.0), *wo);
+
+            assert_eq!(
+                bsdf.flags(),
+                BsdfFlags {
+                    delta: true,
+                    non_delta: true
+                }
+            );
+            assert_samples_match_eval_and_pdf(&bsdf, *wo);
+        }
+    }
+
+    #[test]
+    fn leaves_black_materials_without_lobes() {
+        for principled in [
+            Principled::from_rgb_color(BLACK).with_specular(gray(0.0)),
+            Principled::from_rgb_color(BLACK).with_metallic(gray(1.0)),
+        ]
+        .iter()
+        {
+            // Schlick's approximation only vanishes at normal incidence.
+            let bsdf = principled_bsdf(principled.clone(), Vec3::z());
+
+            assert!(bsdf.lobes.is_empty());
+            assert!(bsdf.sample(Vec3::z(), (0.5, 0.5)).is_none());
+            assert_eq!(bsdf.eval(Vec3::z(), Vec3::z()), BLACK);
+            assert_eq!(bsdf.pdf(Vec3::z(), Vec3::z()), 0.0);
+            assert!(!bsdf.flags().delta && !bsdf.flags().non_delta);
+        }
+    }
+}
@@ -0,0 +1,503 @@
+use std::sync::Arc;
+
+use crate::{
+    aabb::Aabb,
+    hittable::{Hit, Hittable},
+    objects::Object,
+    ray::Ray,
+    samplers::sample_discrete,
+    vec3::{Point3, Vec3},
+};
+
+const MAX_PRIMITIVES_IN_LEAF: usize = 4;
+const SAH_BUCKET_COUNT: usize = 12;
+// Cost of visiting a node relative to the cost of intersecting a primitive.
+const SAH_TRAVERSAL_COST: f32 = 0.125;
+const TRAVERSAL_STACK_CAPACITY: usize = 64;
+
+#[derive(Copy, Clone, Debug)]
+enum BvhNodeKind {
+    Leaf { first: usize, count: usize },
+    Interior { second_child: usize, axis: usize },
+}
+
+#[derive(Copy, Clone, Debug)]
+struct BvhNode {
+    bounds: Aabb,
+    kind: BvhNodeKind,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct BuildPrimitive {
+    index: usize,
+    bounds: Aabb,
+    centroid: Point3,
+}
+
+/// Bounding volume hierarchy over primitives identified by their index,
+/// built with the surface area heuristic and stored depth-first, so that
+/// the first child of an interior node is always the next node.
+#[derive(Clone, Debug)]
+pub struct BvhTree {
+    nodes: Vec<BvhNode>,
+    primitive_indices: Vec<usize>,
+}
+
+impl BvhTree {
+    pub fn new(bounds: &[Aabb]) -> Self {
+        let mut primitives: Vec<_> = bounds
+            .iter()
+            .enumerate()
+            .map(|(index, &bounds)| BuildPrimitive {
+                index,
+                bounds,
+                centroid: bounds.centroid(),
+            })
+            .collect();
+
+        let mut tree = Self {
+            nodes: Vec::with_capacity(2 * bounds.len()),
+            primitive_indices: Vec::with_capacity(bounds.len()),
+        };
+
+        if !primitives.is_empty() {
+            tree.build(&mut primitives[..]);
+        }
+
+        tree
+    }
+
+    pub fn bounds(&self) -> Option<Aabb> {
+        self.nodes.first().map(|node| node.bounds)
+    }
+
+    fn build(&mut self, primitives: &mut [BuildPrimitive]) -> usize {
+        let node_index = self.nodes.len();
+
+        let bounds = primitives
+            .iter()
+            .skip(1)
+            .fold(primitives[0].bounds, |bounds, primitive| {
+                bounds.surrounding(&primitive.bounds)
+            });
+
+        self.nodes.push(BvhNode {
+            bounds,
+            kind: BvhNodeKind::Leaf { first: 0, count: 0 },
+        });
+
+        let split = match Self::split(primitives, &bounds) {
+            Some(split) => split,
+            None => {
+                let first = self.primitive_indices.len();
+
+                self.primitive_indices
+                    .extend(primitives.iter().map(|primitive| primitive.index));
+                self.nodes[node_index].kind = BvhNodeKind::Leaf {
+                    first,
+                    count: primitives.len(),
+                };
+
+                return node_index;
+            }
+        };
+
+        let (axis, middle) = split;
+
+        self.build(&mut primitives[..middle]);
+        let second_child = self.build(&mut primitives[middle..]);
+
+        self.nodes[node_index].kind = BvhNodeKind::Interior { second_child, axis };
+
+        node_index
+    }
+
+    /// Partitions the primitives and returns the split axis and the index of
+    /// the first primitive of the second half, or `None` if the primitives
+    /// should be kept in a single leaf.
+    fn split(primitives: &mut [BuildPrimitive], bounds: &Aabb) -> Option<(usize, usize)> {
+        if primitives.len() == 1 {
+            return None;
+        }
+
+        let centroids: Vec<_> = primitives
+            .iter()
+            .map(|primitive| primitive.centroid)
+            .collect();
+        let centroid_bounds = Aabb::from_points(&centroids[..]);
+
+        let axis = centroid_bounds.longest_axis();
+        let extent = centroid_bounds.diagonal()[axis];
+
+        if extent <= 0.0 {
+            return if primitives.len() <= MAX_PRIMITIVES_IN_LEAF {
+                None
+            } else {
+                Some((axis, primitives.len() / 2))
+            };
+        }
+
+        let bucket_of = |primitive: &BuildPrimitive| {
+            let offset = (primitive.centroid[axis] - centroid_bounds.min[axis]) / extent;
+
+            usize::min(
+                (offset * SAH_BUCKET_COUNT as f32) as usize,
+                SAH_BUCKET_COUNT - 1,
+            )
+        };
+
+        let mut buckets: [(usize, Option<Aabb>); SAH_BUCKET_COUNT] = [(0, None); SAH_BUCKET_COUNT];
+
+        for primitive in primitives.iter() {
+            let bucket = &mut buckets[bucket_of(primitive)];
+
+            bucket.0 += 1;
+            bucket.1 = Some(match bucket.1 {
+                Some(bounds) => bounds.surrounding(&primitive.bounds),
+                None => primitive.bounds,
+            });
+        }
+
+        let (best_bucket, best_cost) = (0..(SAH_BUCKET_COUNT - 1))
+            .map(|split_bucket| {
+                let (below, above) = buckets.split_at(split_bucket + 1);
+
+                let cost = SAH_TRAVERSAL_COST
+                    + (Self::bucket_cost(below) + Self::bucket_cost(above)) / bounds.surface_area();
+
+                (split_bucket, cost)
+            })
+            .fold((0, f32::INFINITY), |best, candidate| {
+                if candidate.1 < best.1 {
+                    candidate
+                } else {
+                    best
+                }
+            });
+
+        let leaf_cost = primitives.len() as f32;
+
+        if primitives.len() <= MAX_PRIMITIVES_IN_LEAF && best_cost >= leaf_cost {
+            return None;
+        }
+
+        let mut middle = 0;
+
+        for index in 0..primitives.len() {
+            if bucket_of(&primitives[index]) <= best_bucket {
+                primitives.swap(index, middle);
+                middle += 1;
+            }
+        }
+
+        if middle == 0 || middle == primitives.len() {
+            middle = primitives.len() / 2;
+
+            primitives.select_nth_unstable_by(middle, |a, b| {
+                a.centroid[axis]
+                    .partial_cmp(&b.centroid[axis])
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            });
+        }
+
+        Some((axis, middle))
+    }
+
+    fn bucket_cost(buckets: &[(usize, Option<Aabb>)]) -> f32 {
+        let (count, bounds) = buckets.iter().fold(
+            (0, None),
+            |(count, bounds): (usize, Option<Aabb>), bucket| {
+                let bounds = match (bounds, bucket.1) {
+                    (Some(a), Some(b)) => Some(a.surrounding(&b)),
+                    (a, b) => a.or(b),
+                };
+
+                (count + bucket.0, bounds)
+            },
+        );
+
+        bounds.map_or(0.0, |bounds| count as f32 * bounds.surface_area())
+    }
+
+    /// Finds the closest hit along the ray, calling `hit_primitive` with
+    /// the index of every primitive whose leaf the ray goes through.
+    pub fn hit<F>(&self, ray: &Ray, max_t: f32, hit_primitive: F) -> Option<Hit>
+    where
+        F: Fn(usize, &Ray, f32) -> Option<Hit>,
+    {
+        if self.nodes.is_empty() {
+            return None;
+        }
+
+        let inverse_direction = Vec3::new(
+            1.0 / ray.direction.x,
+            1.0 / ray.direction.y,
+            1.0 / ray.direction.z,
+        );
+
+        let mut closest_t = max_t;
+        let mut closest_hit = None;
+
+        let mut stack = Vec::with_capacity(TRAVERSAL_STACK_CAPACITY);
+        let mut node_index = 0;
+
+        loop {
+            let node = &self.nodes[node_index];
+
+            if node.bounds.hit(ray, inverse_direction, closest_t) {
+                match node.kind {
+                    BvhNodeKind::Leaf { first, count } => {
+                        for &index in self.primitive_indices[first..(first + count)].iter() {
+                            if let Some(hit) = hit_primitive(index, ray, closest_t) {
+                                closest_t = hit.t;
+                                closest_hit = Some(hit);
+                            }
+                        }
+                    }
+                    BvhNodeKind::Interior { second_child, axis } => {
+                        // Visit the child closest to the ray origin first so
+                        // that `closest_t` shrinks as early as possible.
+                        let (near, far) = if inverse_direction[axis] < 0.0 {
+                            (second_child, node_index + 1)
+                        } else {
+                            (node_index + 1, second_child)
+                        };
+
+                        stack.push(far);
+                        node_index = near;
+
+                        continue;
+                    }
+                }
+            }
+
+            match stack.pop() {
+                Some(index) => node_index = index,
+                None => break,
+            }
+        }
+
+        closest_hit
+    }
+}
+
+#[derive(Clone)]
+pub struct Bvh {
+    pub objects: Vec<Arc<dyn Object>>,
+    tree: BvhTree,
+    unbounded_objects: Vec<usize>,
+}
+
+impl Bvh {
+    /// Builds the hierarchy around the space the objects sweep between
+    /// `time_start` and `time_end`, which should cover the camera shutter.
+    pub fn new(objects: Vec<Arc<dyn Object>>, time_start: f32, time_end: f32) -> Self {
+        let mut bounded_objects = Vec::new();
+        let mut bounds = Vec::new();
+        let mut unbounded_objects = Vec::new();
+
+        for (index, object) in objects.iter().enumerate() {
+            match object.bounding_box(time_start, time_end) {
+                Some(aabb) => {
+                    bounded_objects.push(index);
+                    bounds.push(aabb);
+                }
+                None => unbounded_objects.push(index),
+            }
+        }
+
+        let mut tree = BvhTree::new(&bounds[..]);
+
+        for index in tree.primitive_indices.iter_mut() {
+            *index = bounded_objects[*index];
+        }
+
+        Self {
+            objects,
+            tree,
+            unbounded_objects,
+        }
+    }
+}
+
+impl Object for Bvh {}
+
+impl Hittable for Bvh {
+    fn hit(&self, ray: &Ray, max_t: f32) -> Option<Hit> {
+        let mut closest_hit = self.tree.hit(ray, max_t, |index, ray, max_t| {
+            self.objects[index].hit(ray, max_t)
+        });
+
+        for &index in self.unbounded_objects.iter() {
+            let smallest_t = closest_hit.as_ref().map_or(max_t, |hit| hit.t);
+
+            if let Some(hit) = self.objects[index].hit(ray, smallest_t) {
+                closest_hit = Some(hit);
+            }
+        }
+
+        closest_hit
+    }
+
+    // The hierarchy is only valid for the time interval it was built for.
+    fn bounding_box(&self, _time_start: f32, _time_end: f32) -> Option<Aabb> {
+        if self.unbounded_objects.is_empty() {
+            self.tree.bounds()
+        } else {
+            None
+        }
+    }
+
+    fn ray_to_self_probability(&self, ray: &Ray) -> f32 {
+        if self.objects.is_empty() {
+            return 0.0;
+        }
+
+        let weight = 1.0 / self.objects.len() as f32;
+
+        self.objects.iter().fold(0.0, |accumulator, object| {
+            accumulator + weight * object.ray_to_self_probability(ray)
+        })
+    }
+
+    fn random_direction_to_self(&self, origin: Point3, time: f32, (u1, u2): (f32, f32)) -> Vec3 {
+        if self.objects.is_empty() {
+            Vec3::random_unit((u1, u2))
+        } else {
+            let (index, u1) = sample_discrete(u1, self.objects.len());
+
+            self.objects[index].random_direction_to_self(origin, time, (u1, u2))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng as _;
+
+    use super::*;
+
+    use crate::{
+        materials::{Lambertian, Material},
+        objects::{ObjectList, RectangleBuilder, SphereBuilder, TriangleBuilder},
+        ray::MAX_T,
+        rng::Rng,
+    };
+
+    fn random_point(rng: &mut Rng, extent: f32) -> Point3 {
+        Point3::new(
+            rng.gen_range(-extent..extent),
+            rng.gen_range(-extent..extent),
+            rng.gen_range(-extent..extent),
+        )
+    }
+
+    fn random_objects(rng: &mut Rng) -> Vec<Arc<dyn Object>> {
+        let material = Arc::new(Material::Reflective(Arc::new(Lambertian::from_rgb(
+            0.5, 0.5, 0.5,
+        ))));
+        let mut objects = Vec::<Arc<dyn Object>>::new();
+
+        for i in 0..150 {
+            // Hollow spheres have a negative radius.
+            let radius = rng.gen_range(0.1..1.5) * if i % 3 == 0 { -1.0 } else { 1.0 };
+            let mut builder =
+                SphereBuilder::new(random_point(rng, 10.0), radius, Arc::clone(&material));
+
+            if i % 4 == 0 {
+                builder.accelerate(random_point(rng, 1.0), random_point(rng, 2.0));
+            }
+
+            objects.push(Arc::new(builder.build()));
+        }
+
+        for _ in 0..50 {
+            let corner = random_point(rng, 10.0);
+            let vertices = [
+                corner,
+                corner + random_point(rng, 2.0),
+                corner + random_point(rng, 2.0),
+            ];
+
+            objects.push(Arc::new(
+                TriangleBuilder::new(vertices, Arc::clone(&material)).build(),
+            ));
+        }
+
+        for _ in 0..20 {
+            let normal = random_point(rng, 1.0).unit();
+
+            objects.push(Arc::new(
+                RectangleBuilder::new(
+                    random_point(rng, 10.0),
+                    normal,
+                    Vec3::new(normal.y, normal.z, normal.x),
+                    rng.gen_range(0.5..3.0),
+                    rng.gen_range(0.5..3.0),
+                    Arc::clone(&material),
+                )
+                .build(),
+            ));
+        }
+
+        objects
+    }
+
+    #[test]
+    fn hits_the_same_objects_as_a_list() {
+        let mut rng = Rng::from_seed(1);
+        let objects = random_objects(&mut rng);
+
+        let bvh = Bvh::new(objects.clone(), 0.0, 1.0);
+        let list = ObjectList::new(objects);
+
+        let mut hit_count = 0;
+
+        for _ in 0..20_000 {
+            // Spheres expect rays with unit directions, like the camera's.
+            let ray = Ray::new(
+                random_point(&mut rng, 15.0),
+                random_point(&mut rng, 1.0).unit(),
+                rng.gen_range(0.0..1.0),
+            );
+
+            match (bvh.hit(&ray, MAX_T), list.hit(&ray, MAX_T)) {
+                (None, None) => {}
+                (Some(bvh_hit), Some(list_hit)) => {
+                    hit_count += 1;
+
+                    assert!(
+                        (bvh_hit.t - list_hit.t).abs() <= 1e-5 * list_hit.t,
+                        "{:?}: {} != {}",
+                        ray,
+                        bvh_hit.t,
+                        list_hit.t
+                    );
+                    assert!((bvh_hit.normal - list_hit.normal).len() < 1e-3, "{:?}", ray);
+                }
+                (bvh_hit, list_hit) => panic!(
+                    "{:?}: the BVH hit {:?} and the list {:?}",
+                    ray,
+                    bvh_hit.map(|hit| hit.t),
+                    list_hit.map(|hit| hit.t)
+                ),
+            }
+        }
+
+        // Most rays should hit something for the comparison to mean much.
+        assert!(hit_count > 2_000, "only {} rays hit", hit_count);
+    }
+
+    #[test]
+    fn bounds_hollow_spheres() {
+        let material = Arc::new(Material::Reflective(Arc::new(Lambertian::from_rgb(
+            0.5, 0.5, 0.5,
+        ))));
+        let sphere = SphereBuilder::new(Point3::new(1.0, 2.0, 3.0), -2.0, material).build();
+
+        let bounds = sphere.bounding_box(0.0, 1.0).unwrap();
+
+        assert_eq!(bounds.min, Point3::new(-1.0, 0.0, 1.0));
+        assert_eq!(bounds.max, Point3::new(3.0, 4.0, 5.0));
+    }
+}
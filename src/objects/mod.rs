@@ -1,7 +1,13 @@
+mod bvh;
 mod object;
 mod object_list;
 mod rectangle;
 mod rectangular_prism;
 mod sphere;
+mod triangle;
+mod triangle_mesh;
 
-pub use self::{object::*, object_list::*, rectangle::*, rectangular_prism::*, sphere::*};
+pub use self::{
+    bvh::*, object::*, object_list::*, rectangle::*, rectangular_prism::*, sphere::*, triangle::*,
+    triangle_mesh::*,
+};
@@ -1,10 +1,11 @@
 use std::sync::Arc;
 
 use crate::{
-    helpers::random,
+    aabb::Aabb,
     hittable::{Hit, Hittable},
     objects::Object,
     ray::Ray,
+    samplers::sample_discrete,
     vec3::{Point3, Vec3},
 };
 
@@ -36,6 +37,15 @@ impl Hittable for ObjectList {
         closest_hit
     }
 
+    fn bounding_box(&self, time_start: f32, time_end: f32) -> Option<Aabb> {
+        let (first, rest) = self.objects.split_first()?;
+
+        rest.iter()
+            .try_fold(first.bounding_box(time_start, time_end)?, |aabb, object| {
+                Some(aabb.surrounding(&object.bounding_box(time_start, time_end)?))
+            })
+    }
+
     fn ray_to_self_probability(&self, ray: &Ray) -> f32 {
         if self.objects.is_empty() {
             return 0.0;
@@ -50,11 +60,13 @@ impl Hittable for ObjectList {
         sum
     }
 
-    fn random_direction_to_self(&self, origin: Point3, time: f32) -> Vec3 {
+    fn random_direction_to_self(&self, origin: Point3, time: f32, (u1, u2): (f32, f32)) -> Vec3 {
         if self.objects.is_empty() {
-            Vec3::random()
+            Vec3::random_unit((u1, u2))
         } else {
-            self.objects[random(0..self.objects.len())].random_direction_to_self(origin, time)
+            let (index, u1) = sample_discrete(u1, self.objects.len());
+
+            self.objects[index].random_direction_to_self(origin, time, (u1, u2))
         }
     }
 }
@@ -1,7 +1,8 @@
 use std::sync::Arc;
 
 use crate::{
-    helpers::{position, random},
+    aabb::Aabb,
+    helpers::{position, position_bounds},
     hittable::{intersects, Hit, Hittable},
     materials::Material,
     objects::Object,
@@ -9,6 +10,8 @@ use crate::{
     vec3::{cross, dot, Point3, Vec3},
 };
 
+const BOUNDING_BOX_PADDING: f32 = 0.0001;
+
 #[derive(Clone)]
 pub struct Rectangle {
     pub initial_top_left_corner: Point3,
@@ -23,6 +26,7 @@ pub struct Rectangle {
 }
 
 impl Rectangle {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         top_left_corner: Point3,
         normal: Vec3,
@@ -33,7 +37,11 @@ impl Rectangle {
         acceleration: Vec3,
         initial_velocity: Vec3,
     ) -> Self {
-        let right = -cross(normal, up);
+        // `up` only has to point roughly upwards, the edges are made
+        // perpendicular to the normal so that they lie on the rectangle.
+        let normal = normal.unit();
+        let right = cross(up, normal).unit();
+        let up = cross(normal, right);
 
         Self {
             initial_top_left_corner: top_left_corner,
@@ -57,10 +65,8 @@ impl Rectangle {
         )
     }
 
-    fn random_point_in_self(&self, time: f32) -> Point3 {
-        self.top_left_corner(time)
-            + random(0.0..1.0) * self.vertical_edge
-            + random(0.0..1.0) * self.horizontal_edge
+    fn random_point_in_self(&self, time: f32, (u1, u2): (f32, f32)) -> Point3 {
+        self.top_left_corner(time) + u1 * self.vertical_edge + u2 * self.horizontal_edge
     }
 
     fn area(&self) -> f32 {
@@ -104,6 +110,26 @@ impl Hittable for Rectangle {
         ))
     }
 
+    fn bounding_box(&self, time_start: f32, time_end: f32) -> Option<Aabb> {
+        let (min_corner, max_corner) = position_bounds(
+            time_start,
+            time_end,
+            self.acceleration,
+            self.initial_velocity,
+            self.initial_top_left_corner,
+        );
+
+        let edges = Aabb::from_points(&[
+            Vec3::zero(),
+            self.vertical_edge,
+            self.horizontal_edge,
+            self.vertical_edge + self.horizontal_edge,
+        ]);
+
+        // Padded so that axis-aligned rectangles don't get a flat box.
+        Some(Aabb::new(min_corner + edges.min, max_corner + edges.max).padded(BOUNDING_BOX_PADDING))
+    }
+
     fn ray_to_self_probability(&self, ray: &Ray) -> f32 {
         match self.hit(ray, MAX_T) {
             Some(hit) => {
@@ -119,8 +145,8 @@ impl Hittable for Rectangle {
         }
     }
 
-    fn random_direction_to_self(&self, origin: Point3, time: f32) -> Vec3 {
-        (self.random_point_in_self(time) - origin).unit()
+    fn random_direction_to_self(&self, origin: Point3, time: f32, u: (f32, f32)) -> Vec3 {
+        (self.random_point_in_self(time, u) - origin).unit()
     }
 }
 
@@ -157,11 +183,7 @@ impl RectangleBuilder {
         }
     }
 
-    pub fn accelerate<'a>(
-        &'a mut self,
-        acceleration: Vec3,
-        initial_velocity: Vec3,
-    ) -> &'a mut Self {
+    pub fn accelerate(&mut self, acceleration: Vec3, initial_velocity: Vec3) -> &mut Self {
         self.acceleration = acceleration;
         self.initial_velocity = initial_velocity;
 
@@ -181,3 +203,54 @@ impl RectangleBuilder {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::materials::Lambertian;
+
+    #[test]
+    fn samples_points_on_the_plane_it_intersects() {
+        let material = Arc::new(Material::Reflective(Arc::new(Lambertian::from_rgb(
+            0.5, 0.5, 0.5,
+        ))));
+
+        // `up` isn't perpendicular to the normal, like a tilted light.
+        let rectangle = RectangleBuilder::new(
+            Point3::new(-1.0, 2.0, 0.5),
+            Vec3::new(0.0, -1.0, 1.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            2.0,
+            3.0,
+            material,
+        )
+        .build();
+
+        assert!((rectangle.vertical_edge.len() - 2.0).abs() < 1e-5);
+        assert!((rectangle.horizontal_edge.len() - 3.0).abs() < 1e-5);
+
+        let origin = Point3::new(0.5, -3.0, 4.0);
+
+        for i in 0..8 {
+            for j in 0..8 {
+                let u = ((i as f32 + 0.5) / 8.0, (j as f32 + 0.5) / 8.0);
+                let point = rectangle.random_point_in_self(0.0, u);
+
+                assert!(dot(point - rectangle.top_left_corner(0.0), rectangle.normal).abs() < 1e-5);
+
+                let direction = rectangle.random_direction_to_self(origin, 0.0, u);
+                let hit = rectangle
+                    .hit(&Ray::new(origin, direction, 0.0), MAX_T)
+                    .unwrap();
+
+                assert!(
+                    (hit.point - point).len() < 1e-4,
+                    "{:?} != {:?}",
+                    hit.point,
+                    point
+                );
+            }
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use std::sync::Arc;
 
 use crate::{
+    aabb::Aabb,
     hittable::{Hit, Hittable},
     materials::Material,
     objects::{Object, ObjectList, Rectangle},
@@ -15,6 +16,7 @@ pub struct RectangularPrism {
 }
 
 impl RectangularPrism {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         top_left_corner: Point3,
         normal: Vec3,
@@ -118,12 +120,16 @@ impl Hittable for RectangularPrism {
         self.sides.hit(ray, max_t)
     }
 
+    fn bounding_box(&self, time_start: f32, time_end: f32) -> Option<Aabb> {
+        self.sides.bounding_box(time_start, time_end)
+    }
+
     fn ray_to_self_probability(&self, ray: &Ray) -> f32 {
         self.sides.ray_to_self_probability(ray)
     }
 
-    fn random_direction_to_self(&self, origin: Point3, time: f32) -> Vec3 {
-        self.sides.random_direction_to_self(origin, time)
+    fn random_direction_to_self(&self, origin: Point3, time: f32, u: (f32, f32)) -> Vec3 {
+        self.sides.random_direction_to_self(origin, time, u)
     }
 }
 
@@ -163,11 +169,7 @@ impl RectangularPrismBuilder {
         }
     }
 
-    pub fn accelerate<'a>(
-        &'a mut self,
-        acceleration: Vec3,
-        initial_velocity: Vec3,
-    ) -> &'a mut Self {
+    pub fn accelerate(&mut self, acceleration: Vec3, initial_velocity: Vec3) -> &mut Self {
         self.acceleration = acceleration;
         self.initial_velocity = initial_velocity;
 
@@ -2,7 +2,8 @@ use std::f32::consts::PI;
 use std::sync::Arc;
 
 use crate::{
-    helpers::{position, random},
+    aabb::Aabb,
+    helpers::{position, position_bounds},
     hittable::{intersects, Hit, Hittable},
     materials::Material,
     objects::Object,
@@ -52,7 +53,7 @@ impl Sphere {
         }
 
         let hit_point = ray.at(t);
-        let outward_normal = hit_point - center;
+        let outward_normal = (hit_point - center) / self.radius;
 
         let (u, v) = Self::texture_uv(hit_point, center);
 
@@ -101,6 +102,19 @@ impl Hittable for Sphere {
         }
     }
 
+    fn bounding_box(&self, time_start: f32, time_end: f32) -> Option<Aabb> {
+        let (min_center, max_center) = position_bounds(
+            time_start,
+            time_end,
+            self.acceleration,
+            self.initial_velocity,
+            self.initial_center,
+        );
+
+        // Negative radii flip the normals of hollow spheres, not their extent.
+        Some(Aabb::new(min_center, max_center).padded(self.radius.abs()))
+    }
+
     fn ray_to_self_probability(&self, ray: &Ray) -> f32 {
         match self.hit(ray, MAX_T) {
             Some(_) => {
@@ -116,12 +130,9 @@ impl Hittable for Sphere {
         }
     }
 
-    fn random_direction_to_self(&self, origin: Point3, time: f32) -> Vec3 {
+    fn random_direction_to_self(&self, origin: Point3, time: f32, (r1, r2): (f32, f32)) -> Vec3 {
         let w = self.center(time) - origin;
 
-        let r1 = random(0.0..1.0);
-        let r2 = random(0.0..1.0);
-
         let z = 1.0 + r2 * (f32::sqrt(1.0 - self.radius.powi(2) / w.len_squared()) - 1.0);
 
         let phi = 2.0 * PI * r1;
@@ -154,7 +165,7 @@ impl SphereBuilder {
         }
     }
 
-    fn accelerate<'a>(&'a mut self, acceleration: Vec3, initial_velocity: Vec3) -> &'a mut Self {
+    pub fn accelerate(&mut self, acceleration: Vec3, initial_velocity: Vec3) -> &mut Self {
         self.acceleration = acceleration;
         self.initial_velocity = initial_velocity;
 
@@ -171,3 +182,50 @@ impl SphereBuilder {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::materials::Lambertian;
+
+    fn sphere(radius: f32) -> Sphere {
+        let material = Arc::new(Material::Reflective(Arc::new(Lambertian::from_rgb(
+            0.5, 0.5, 0.5,
+        ))));
+
+        SphereBuilder::new(Point3::new(1.0, 2.0, 3.0), radius, material).build()
+    }
+
+    #[test]
+    fn reports_unit_normals() {
+        for &radius in [0.25, 1.0, 4.0].iter() {
+            let ray = Ray::new(Point3::new(1.0, 2.0, -10.0), Vec3::new(0.0, 0.0, 1.0), 0.0);
+            let hit = sphere(radius).hit(&ray, MAX_T).unwrap();
+
+            assert!((hit.t - (13.0 - radius)).abs() < 1e-4);
+            assert!((hit.normal - Vec3::new(0.0, 0.0, -1.0)).len() < 1e-5);
+            assert!(hit.front_face);
+        }
+    }
+
+    #[test]
+    fn points_the_normals_of_hollow_spheres_inwards() {
+        let hollow = sphere(-2.0);
+
+        // Seen from the inside, the surface faces the ray.
+        let ray = Ray::new(Point3::new(1.0, 2.0, 3.0), Vec3::new(1.0, 0.0, 0.0), 0.0);
+        let hit = hollow.hit(&ray, MAX_T).unwrap();
+
+        assert!((hit.t - 2.0).abs() < 1e-5);
+        assert!((hit.normal - Vec3::new(-1.0, 0.0, 0.0)).len() < 1e-5);
+        assert!(hit.front_face);
+
+        // Seen from the outside, the ray hits the back of the surface.
+        let ray = Ray::new(Point3::new(10.0, 2.0, 3.0), Vec3::new(-1.0, 0.0, 0.0), 0.0);
+        let hit = hollow.hit(&ray, MAX_T).unwrap();
+
+        assert!((hit.normal.len() - 1.0).abs() < 1e-5);
+        assert!(!hit.front_face);
+    }
+}
@@ -0,0 +1,413 @@
+use std::sync::Arc;
+
+use crate::{
+    aabb::Aabb,
+    helpers::{position, position_bounds},
+    hittable::{intersects, Hit, Hittable},
+    materials::Material,
+    objects::Object,
+    ray::{Ray, MAX_T},
+    vec3::{cross, dot, Point3, Vec3},
+};
+
+const BOUNDING_BOX_PADDING: f32 = 0.0001;
+
+pub type TextureCoordinates = (f32, f32);
+
+pub const DEFAULT_TEXTURE_COORDINATES: [TextureCoordinates; 3] =
+    [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0)];
+
+#[derive(Clone)]
+pub struct Triangle {
+    pub initial_vertices: [Point3; 3],
+    pub normals: Option<[Vec3; 3]>,
+    pub texture_coordinates: [TextureCoordinates; 3],
+    pub initial_velocity: Vec3,
+    pub acceleration: Vec3,
+    pub material: Arc<Material>,
+}
+
+impl Triangle {
+    pub const fn new(
+        vertices: [Point3; 3],
+        normals: Option<[Vec3; 3]>,
+        texture_coordinates: [TextureCoordinates; 3],
+        material: Arc<Material>,
+        acceleration: Vec3,
+        initial_velocity: Vec3,
+    ) -> Self {
+        Self {
+            initial_vertices: vertices,
+            normals,
+            texture_coordinates,
+            acceleration,
+            initial_velocity,
+            material,
+        }
+    }
+
+    pub fn vertices(&self, time: f32) -> [Point3; 3] {
+        let offset = position(time, self.acceleration, self.initial_velocity, Vec3::zero());
+
+        [
+            self.initial_vertices[0] + offset,
+            self.initial_vertices[1] + offset,
+            self.initial_vertices[2] + offset,
+        ]
+    }
+
+    fn area(&self) -> f32 {
+        let [p0, p1, p2] = self.initial_vertices;
+
+        cross(p1 - p0, p2 - p0).len() / 2.0
+    }
+}
+
+impl Object for Triangle {}
+
+impl Hittable for Triangle {
+    fn hit(&self, ray: &Ray, max_t: f32) -> Option<Hit> {
+        let vertices = self.vertices(ray.time);
+        let (t, b1, b2) = intersect_triangle(ray, vertices, max_t)?;
+
+        Some(triangle_hit(
+            ray,
+            t,
+            [1.0 - b1 - b2, b1, b2],
+            vertices,
+            self.normals,
+            self.texture_coordinates,
+            Arc::clone(&self.material),
+        ))
+    }
+
+    fn bounding_box(&self, time_start: f32, time_end: f32) -> Option<Aabb> {
+        let (min_offset, max_offset) = position_bounds(
+            time_start,
+            time_end,
+            self.acceleration,
+            self.initial_velocity,
+            Vec3::zero(),
+        );
+
+        let aabb = Aabb::from_points(&self.initial_vertices);
+
+        Some(Aabb::new(aabb.min + min_offset, aabb.max + max_offset).padded(BOUNDING_BOX_PADDING))
+    }
+
+    fn ray_to_self_probability(&self, ray: &Ray) -> f32 {
+        match self.hit(ray, MAX_T) {
+            Some(hit) => {
+                triangle_solid_angle_probability(ray, &hit, self.vertices(ray.time), self.area())
+            }
+            None => 0.0,
+        }
+    }
+
+    fn random_direction_to_self(&self, origin: Point3, time: f32, u: (f32, f32)) -> Vec3 {
+        (random_point_in_triangle(self.vertices(time), u) - origin).unit()
+    }
+}
+
+/// Möller–Trumbore ray-triangle intersection. Returns the distance along the
+/// ray and the barycentric coordinates of the second and third vertices.
+pub(crate) fn intersect_triangle(
+    ray: &Ray,
+    [p0, p1, p2]: [Point3; 3],
+    max_t: f32,
+) -> Option<(f32, f32, f32)> {
+    let edge1 = p1 - p0;
+    let edge2 = p2 - p0;
+
+    let p = cross(ray.direction, edge2);
+    let determinant = dot(edge1, p);
+
+    if determinant == 0.0 || !determinant.is_finite() {
+        return None;
+    }
+
+    let inverse_determinant = 1.0 / determinant;
+
+    let s = ray.origin - p0;
+    let b1 = dot(s, p) * inverse_determinant;
+
+    if !(0.0..=1.0).contains(&b1) {
+        return None;
+    }
+
+    let q = cross(s, edge1);
+    let b2 = dot(ray.direction, q) * inverse_determinant;
+
+    if b2 < 0.0 || b1 + b2 > 1.0 {
+        return None;
+    }
+
+    let t = dot(edge2, q) * inverse_determinant;
+
+    if !intersects(t, max_t) {
+        return None;
+    }
+
+    Some((t, b1, b2))
+}
+
+pub(crate) fn triangle_hit(
+    ray: &Ray,
+    t: f32,
+    barycentrics: [f32; 3],
+    [p0, p1, p2]: [Point3; 3],
+    normals: Option<[Vec3; 3]>,
+    texture_coordinates: [TextureCoordinates; 3],
+    material: Arc<Material>,
+) -> Hit {
+    let [b0, b1, b2] = barycentrics;
+
+    let u = b0 * texture_coordinates[0].0
+        + b1 * texture_coordinates[1].0
+        + b2 * texture_coordinates[2].0;
+    let v = b0 * texture_coordinates[0].1
+        + b1 * texture_coordinates[1].1
+        + b2 * texture_coordinates[2].1;
+
+    let geometric_normal = cross(p1 - p0, p2 - p0).unit();
+
+    match normals {
+        None => Hit::new(ray, t, geometric_normal, material, u, v),
+        Some([n0, n1, n2]) => {
+            let shading_normal = (b0 * n0 + b1 * n1 + b2 * n2).unit();
+
+            // Vertex normals define which side is the outside, regardless of
+            // the winding order of the vertices.
+            let outward_normal = if dot(geometric_normal, shading_normal) < 0.0 {
+                -geometric_normal
+            } else {
+                geometric_normal
+            };
+
+            Hit::new(ray, t, outward_normal, material, u, v).with_shading_normal(shading_normal)
+        }
+    }
+}
+
+pub(crate) fn triangle_solid_angle_probability(
+    ray: &Ray,
+    hit: &Hit,
+    [p0, p1, p2]: [Point3; 3],
+    area: f32,
+) -> f32 {
+    let cosine = dot(ray.direction, cross(p1 - p0, p2 - p0).unit()).abs();
+
+    if cosine == 0.0 {
+        0.0
+    } else {
+        hit.t.powi(2) / (cosine * area)
+    }
+}
+
+pub(crate) fn random_point_in_triangle([p0, p1, p2]: [Point3; 3], (r1, r2): (f32, f32)) -> Point3 {
+    let sqrted_r1 = f32::sqrt(r1);
+
+    let b1 = r2 * sqrted_r1;
+    let b2 = 1.0 - sqrted_r1;
+
+    p0 + b1 * (p1 - p0) + b2 * (p2 - p0)
+}
+
+#[derive(Clone)]
+pub struct TriangleBuilder {
+    pub vertices: [Point3; 3],
+    pub normals: Option<[Vec3; 3]>,
+    pub texture_coordinates: [TextureCoordinates; 3],
+    pub material: Arc<Material>,
+    pub acceleration: Vec3,
+    pub initial_velocity: Vec3,
+}
+
+impl TriangleBuilder {
+    pub fn new(vertices: [Point3; 3], material: Arc<Material>) -> Self {
+        Self {
+            vertices,
+            normals: None,
+            texture_coordinates: DEFAULT_TEXTURE_COORDINATES,
+            material,
+            acceleration: Vec3::zero(),
+            initial_velocity: Vec3::zero(),
+        }
+    }
+
+    pub fn normals(&mut self, normals: [Vec3; 3]) -> &mut Self {
+        self.normals = Some(normals);
+
+        self
+    }
+
+    pub fn texture_coordinates(
+        &mut self,
+        texture_coordinates: [TextureCoordinates; 3],
+    ) -> &mut Self {
+        self.texture_coordinates = texture_coordinates;
+
+        self
+    }
+
+    pub fn accelerate(&mut self, acceleration: Vec3, initial_velocity: Vec3) -> &mut Self {
+        self.acceleration = acceleration;
+        self.initial_velocity = initial_velocity;
+
+        self
+    }
+
+    pub fn build(&self) -> Triangle {
+        Triangle::new(
+            self.vertices,
+            self.normals,
+            self.texture_coordinates,
+            Arc::clone(&self.material),
+            self.acceleration,
+            self.initial_velocity,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::materials::{
+        tests::{sphere_grid, unit_square_grid},
+        Lambertian,
+    };
+
+    fn material() -> Arc<Material> {
+        Arc::new(Material::Reflective(Arc::new(Lambertian::from_rgb(
+            0.5, 0.5, 0.5,
+        ))))
+    }
+
+    fn triangle() -> TriangleBuilder {
+        TriangleBuilder::new(
+            [
+                Point3::new(0.0, 0.0, 0.0),
+                Point3::new(1.0, 0.0, 0.0),
+                Point3::new(0.0, 1.0, 0.0),
+            ],
+            material(),
+        )
+    }
+
+    /// Ray going down onto the plane of the triangle at `(x, y)`.
+    fn ray_down(x: f32, y: f32) -> Ray {
+        Ray::new(Point3::new(x, y, 2.0), Vec3::new(0.0, 0.0, -1.0), 0.0)
+    }
+
+    #[test]
+    fn intersects_inside_of_the_edges() {
+        let vertices = triangle().vertices;
+
+        let (t, b1, b2) = intersect_triangle(&ray_down(0.25, 0.5), vertices, MAX_T).unwrap();
+        assert!((t - 2.0).abs() < 1e-6 && (b1 - 0.25).abs() < 1e-6 && (b2 - 0.5).abs() < 1e-6);
+
+        for &(x, y) in [(0.6, 0.6), (-0.1, 0.5), (0.5, -0.1)].iter() {
+            assert_eq!(intersect_triangle(&ray_down(x, y), vertices, MAX_T), None);
+        }
+
+        // Behind the ray, beyond its end, and parallel to the triangle.
+        let away = Ray::new(Point3::new(0.25, 0.25, 2.0), Vec3::new(0.0, 0.0, 1.0), 0.0);
+        assert_eq!(intersect_triangle(&away, vertices, MAX_T), None);
+        assert_eq!(
+            intersect_triangle(&ray_down(0.25, 0.25), vertices, 1.0),
+            None
+        );
+
+        let parallel = Ray::new(Point3::new(-1.0, 0.25, 0.0), Vec3::new(1.0, 0.0, 0.0), 0.0);
+        assert_eq!(intersect_triangle(&parallel, vertices, MAX_T), None);
+    }
+
+    #[test]
+    fn interpolates_texture_coordinates() {
+        let hit = triangle().build().hit(&ray_down(0.25, 0.5), MAX_T).unwrap();
+        assert!((hit.u - 0.25).abs() < 1e-6 && (hit.v - 0.5).abs() < 1e-6);
+
+        let hit = triangle()
+            .texture_coordinates([(0.5, 0.5), (1.0, 0.5), (0.5, 1.0)])
+            .build()
+            .hit(&ray_down(0.25, 0.5), MAX_T)
+            .unwrap();
+        assert!((hit.u - 0.625).abs() < 1e-6 && (hit.v - 0.75).abs() < 1e-6);
+    }
+
+    #[test]
+    fn interpolates_vertex_normals() {
+        let normals = [
+            Vec3::new(0.0, 0.0, 1.0),
+            Vec3::new(1.0, 0.0, 1.0).unit(),
+            Vec3::new(0.0, 1.0, 1.0).unit(),
+        ];
+        let triangle = triangle().normals(normals).build();
+        let expected = (0.25 * normals[0] + 0.25 * normals[1] + 0.5 * normals[2]).unit();
+
+        let hit = triangle.hit(&ray_down(0.25, 0.5), MAX_T).unwrap();
+        assert!((hit.normal - expected).len() < 1e-5);
+        assert!(hit.front_face);
+
+        let from_below = Ray::new(Point3::new(0.25, 0.5, -2.0), Vec3::new(0.0, 0.0, 1.0), 0.0);
+        let hit = triangle.hit(&from_below, MAX_T).unwrap();
+        assert!((hit.normal + expected).len() < 1e-5);
+        assert!(!hit.front_face);
+    }
+
+    #[test]
+    fn takes_the_outside_from_the_vertex_normals() {
+        let down = Vec3::new(0.0, 0.0, -1.0);
+        let triangle = triangle().normals([down; 3]).build();
+
+        let hit = triangle.hit(&ray_down(0.25, 0.25), MAX_T).unwrap();
+
+        assert!(!hit.front_face);
+        assert!((hit.normal + down).len() < 1e-6);
+    }
+
+    #[test]
+    fn samples_directions_to_itself_by_their_density() {
+        let triangle = TriangleBuilder::new(
+            [
+                Point3::new(0.0, 0.0, 0.0),
+                Point3::new(2.0, 0.0, 0.0),
+                Point3::new(0.0, 2.0, 0.5),
+            ],
+            material(),
+        )
+        .build();
+        let origin = Point3::new(0.3, 0.4, 1.5);
+        let probability =
+            |direction| triangle.ray_to_self_probability(&Ray::new(origin, direction, 0.0));
+
+        let mut pdf_integral = 0.0_f32;
+        let mut solid_angle = 0.0;
+
+        for (direction, cell_solid_angle) in sphere_grid(400) {
+            let pdf = probability(direction);
+
+            pdf_integral += pdf * cell_solid_angle;
+
+            if pdf > 0.0 {
+                solid_angle += cell_solid_angle;
+            }
+        }
+
+        assert!((pdf_integral - 1.0).abs() < 1e-2, "{}", pdf_integral);
+
+        // Directions sampled by their density weigh the solid angle they
+        // cover on average.
+        let samples: Vec<_> = unit_square_grid(64)
+            .map(|u| triangle.random_direction_to_self(origin, 0.0, u))
+            .collect();
+        let estimate = samples
+            .iter()
+            .map(|&direction| 1.0 / probability(direction))
+            .sum::<f32>()
+            / samples.len() as f32;
+
+        assert!((estimate - solid_angle).abs() < 1e-2 * solid_angle);
+    }
+}
@@ -0,0 +1,372 @@
+use std::cell::Cell;
+use std::sync::Arc;
+
+use crate::{
+    aabb::Aabb,
+    hittable::{Hit, Hittable},
+    materials::Material,
+    objects::{
+        intersect_triangle, random_point_in_triangle, triangle_hit,
+        triangle_solid_angle_probability, BvhTree, Object, TextureCoordinates,
+        DEFAULT_TEXTURE_COORDINATES,
+    },
+    ray::{Ray, MAX_T},
+    samplers::ONE_MINUS_EPSILON,
+    vec3::{cross, Point3, Vec3},
+};
+
+const BOUNDING_BOX_PADDING: f32 = 0.0001;
+
+/// Vertex attributes that can be shared by several meshes, for example the
+/// parts of a model that use different materials.
+#[derive(Clone, Debug, Default)]
+pub struct MeshBuffers {
+    pub positions: Vec<Point3>,
+    pub normals: Vec<Vec3>,
+    pub texture_coordinates: Vec<TextureCoordinates>,
+}
+
+impl MeshBuffers {
+    pub fn new(
+        positions: Vec<Point3>,
+        normals: Vec<Vec3>,
+        texture_coordinates: Vec<TextureCoordinates>,
+    ) -> Self {
+        Self {
+            positions,
+            normals,
+            texture_coordinates,
+        }
+    }
+}
+
+/// Indices into the buffers of a mesh for each vertex of a triangle.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct MeshFace {
+    pub positions: [usize; 3],
+    pub normals: Option<[usize; 3]>,
+    pub texture_coordinates: Option<[usize; 3]>,
+}
+
+impl MeshFace {
+    pub const fn new(
+        positions: [usize; 3],
+        normals: Option<[usize; 3]>,
+        texture_coordinates: Option<[usize; 3]>,
+    ) -> Self {
+        Self {
+            positions,
+            normals,
+            texture_coordinates,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct TriangleMesh {
+    pub buffers: Arc<MeshBuffers>,
+    pub faces: Vec<MeshFace>,
+    pub material: Arc<Material>,
+    tree: BvhTree,
+    cumulative_areas: Vec<f32>,
+}
+
+impl TriangleMesh {
+    pub fn new(buffers: Arc<MeshBuffers>, faces: Vec<MeshFace>, material: Arc<Material>) -> Self {
+        let bounds: Vec<_> = faces
+            .iter()
+            .map(|face| Aabb::from_points(&Self::positions(&buffers, face)))
+            .collect();
+
+        let cumulative_areas = faces
+            .iter()
+            .scan(0.0, |cumulative_area, face| {
+                let [p0, p1, p2] = Self::positions(&buffers, face);
+                *cumulative_area += cross(p1 - p0, p2 - p0).len() / 2.0;
+
+                Some(*cumulative_area)
+            })
+            .collect();
+
+        Self {
+            tree: BvhTree::new(&bounds[..]),
+            buffers,
+            faces,
+            material,
+            cumulative_areas,
+        }
+    }
+
+    pub fn area(&self) -> f32 {
+        self.cumulative_areas.last().copied().unwrap_or(0.0)
+    }
+
+    fn positions(buffers: &MeshBuffers, face: &MeshFace) -> [Point3; 3] {
+        [
+            buffers.positions[face.positions[0]],
+            buffers.positions[face.positions[1]],
+            buffers.positions[face.positions[2]],
+        ]
+    }
+
+    fn hit_face(&self, face_index: usize, ray: &Ray, max_t: f32) -> Option<Hit> {
+        let face = &self.faces[face_index];
+        let positions = Self::positions(&self.buffers, face);
+
+        let (t, b1, b2) = intersect_triangle(ray, positions, max_t)?;
+
+        let normals = face.normals.map(|[n0, n1, n2]| {
+            [
+                self.buffers.normals[n0],
+                self.buffers.normals[n1],
+                self.buffers.normals[n2],
+            ]
+        });
+
+        let texture_coordinates =
+            face.texture_coordinates
+                .map_or(DEFAULT_TEXTURE_COORDINATES, |[uv0, uv1, uv2]| {
+                    [
+                        self.buffers.texture_coordinates[uv0],
+                        self.buffers.texture_coordinates[uv1],
+                        self.buffers.texture_coordinates[uv2],
+                    ]
+                });
+
+        Some(triangle_hit(
+            ray,
+            t,
+            [1.0 - b1 - b2, b1, b2],
+            positions,
+            normals,
+            texture_coordinates,
+            Arc::clone(&self.material),
+        ))
+    }
+
+    fn closest_hit(&self, ray: &Ray, max_t: f32) -> Option<(usize, Hit)> {
+        let closest_face = Cell::new(0);
+
+        // Every hit the tree reports is closer than the previous ones, so the
+        // last face that was hit is the one of the closest hit.
+        let hit = self.tree.hit(ray, max_t, |face_index, ray, max_t| {
+            let hit = self.hit_face(face_index, ray, max_t)?;
+            closest_face.set(face_index);
+
+            Some(hit)
+        })?;
+
+        Some((closest_face.get(), hit))
+    }
+
+    /// Picks a face with a probability proportional to its area, and returns
+    /// it along with the sample remapped to `[0, 1)` inside of the face.
+    fn random_face(&self, u: f32) -> (usize, f32) {
+        let target = u * self.area();
+
+        let face_index = usize::min(
+            self.cumulative_areas
+                .partition_point(|&cumulative_area| cumulative_area <= target),
+            self.faces.len() - 1,
+        );
+
+        let face_start = match face_index {
+            0 => 0.0,
+            _ => self.cumulative_areas[face_index - 1],
+        };
+        let face_area = self.cumulative_areas[face_index] - face_start;
+
+        let u = if face_area > 0.0 {
+            f32::min((target - face_start) / face_area, ONE_MINUS_EPSILON)
+        } else {
+            0.0
+        };
+
+        (face_index, f32::max(u, 0.0))
+    }
+}
+
+impl Object for TriangleMesh {}
+
+impl Hittable for TriangleMesh {
+    fn hit(&self, ray: &Ray, max_t: f32) -> Option<Hit> {
+        self.closest_hit(ray, max_t).map(|(_, hit)| hit)
+    }
+
+    fn bounding_box(&self, _time_start: f32, _time_end: f32) -> Option<Aabb> {
+        self.tree
+            .bounds()
+            .map(|bounds| bounds.padded(BOUNDING_BOX_PADDING))
+    }
+
+    fn ray_to_self_probability(&self, ray: &Ray) -> f32 {
+        match self.closest_hit(ray, MAX_T) {
+            Some((face_index, hit)) => triangle_solid_angle_probability(
+                ray,
+                &hit,
+                Self::positions(&self.buffers, &self.faces[face_index]),
+                self.area(),
+            ),
+            None => 0.0,
+        }
+    }
+
+    fn random_direction_to_self(&self, origin: Point3, _time: f32, (u1, u2): (f32, f32)) -> Vec3 {
+        if self.faces.is_empty() || self.area() <= 0.0 {
+            return Vec3::random_unit((u1, u2));
+        }
+
+        let (face_index, u1) = self.random_face(u1);
+        let face = &self.faces[face_index];
+
+        (random_point_in_triangle(Self::positions(&self.buffers, face), (u1, u2)) - origin).unit()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::materials::{
+        tests::{sphere_grid, unit_square_grid},
+        Lambertian,
+    };
+
+    fn material() -> Arc<Material> {
+        Arc::new(Material::Reflective(Arc::new(Lambertian::from_rgb(
+            0.5, 0.5, 0.5,
+        ))))
+    }
+
+    /// Unit square of two faces, next to a triangle of area 2, all facing
+    /// up.
+    fn mesh() -> TriangleMesh {
+        let positions = vec![
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(1.0, 0.0, 0.0),
+            Point3::new(1.0, 1.0, 0.0),
+            Point3::new(0.0, 1.0, 0.0),
+            Point3::new(2.0, 0.0, 0.0),
+            Point3::new(4.0, 0.0, 0.0),
+            Point3::new(2.0, 2.0, 0.0),
+        ];
+
+        let faces = vec![
+            MeshFace::new([0, 1, 2], None, None),
+            MeshFace::new([0, 2, 3], None, None),
+            MeshFace::new([4, 5, 6], None, None),
+        ];
+
+        TriangleMesh::new(
+            Arc::new(MeshBuffers::new(positions, Vec::new(), Vec::new())),
+            faces,
+            material(),
+        )
+    }
+
+    #[test]
+    fn picks_faces_by_their_area() {
+        let mesh = mesh();
+        assert!((mesh.area() - 3.0).abs() < 1e-6);
+
+        let mut counts = [0; 3];
+
+        for index in 0..3000 {
+            let (face_index, u) = mesh.random_face((index as f32 + 0.5) / 3000.0);
+
+            assert!((0.0..1.0).contains(&u));
+            counts[face_index] += 1;
+        }
+
+        assert_eq!(counts, [500, 500, 2000]);
+
+        // The sample is stretched over the face it picked.
+        let (face_index, u) = mesh.random_face(0.75);
+        assert_eq!(face_index, 2);
+        assert!((u - 0.625).abs() < 1e-5);
+    }
+
+    #[test]
+    fn samples_directions_to_itself_by_their_density() {
+        let mesh = mesh();
+        let origin = Point3::new(1.5, 0.5, 2.0);
+        let probability =
+            |direction| mesh.ray_to_self_probability(&Ray::new(origin, direction, 0.0));
+
+        let mut pdf_integral = 0.0_f32;
+        let mut solid_angle = 0.0;
+
+        for (direction, cell_solid_angle) in sphere_grid(400) {
+            let pdf = probability(direction);
+
+            pdf_integral += pdf * cell_solid_angle;
+
+            if pdf > 0.0 {
+                solid_angle += cell_solid_angle;
+            }
+        }
+
+        assert!((pdf_integral - 1.0).abs() < 1e-2, "{}", pdf_integral);
+
+        let samples: Vec<_> = unit_square_grid(64)
+            .map(|u| mesh.random_direction_to_self(origin, 0.0, u))
+            .collect();
+        let estimate = samples
+            .iter()
+            .map(|&direction| 1.0 / probability(direction))
+            .sum::<f32>()
+            / samples.len() as f32;
+
+        assert!((estimate - solid_angle).abs() < 1e-2 * solid_angle);
+    }
+
+    #[test]
+    fn reports_the_closest_face_with_its_attributes() {
+        let buffers = Arc::new(MeshBuffers::new(
+            vec![
+                Point3::new(0.0, 0.0, 0.0),
+                Point3::new(1.0, 0.0, 0.0),
+                Point3::new(0.0, 1.0, 0.0),
+                Point3::new(0.0, 0.0, 1.0),
+                Point3::new(1.0, 0.0, 1.0),
+                Point3::new(0.0, 1.0, 1.0),
+            ],
+            vec![Vec3::new(0.0, 0.0, 1.0)],
+            vec![(0.0, 0.0), (0.5, 0.0), (0.0, 0.5)],
+        ));
+
+        let faces = vec![
+            MeshFace::new([0, 1, 2], None, None),
+            MeshFace::new([3, 4, 5], Some([0, 0, 0]), Some([0, 1, 2])),
+        ];
+        let mesh = TriangleMesh::new(buffers, faces, material());
+
+        let ray = Ray::new(Point3::new(0.25, 0.5, 3.0), Vec3::new(0.0, 0.0, -1.0), 0.0);
+        let hit = mesh.hit(&ray, MAX_T).unwrap();
+
+        assert!((hit.t - 2.0).abs() < 1e-6);
+        assert!((hit.u - 0.125).abs() < 1e-6 && (hit.v - 0.25).abs() < 1e-6);
+        assert!((hit.normal - Vec3::new(0.0, 0.0, 1.0)).len() < 1e-6);
+
+        // Below the top face, only the bottom one is left.
+        let ray = Ray::new(Point3::new(0.25, 0.5, 0.5), Vec3::new(0.0, 0.0, -1.0), 0.0);
+        let hit = mesh.hit(&ray, MAX_T).unwrap();
+        assert!((hit.t - 0.5).abs() < 1e-6);
+        assert!((hit.u - 0.25).abs() < 1e-6 && (hit.v - 0.5).abs() < 1e-6);
+
+        let miss = Ray::new(Point3::new(2.0, 2.0, 3.0), Vec3::new(0.0, 0.0, -1.0), 0.0);
+        assert!(mesh.hit(&miss, MAX_T).is_none());
+        assert_eq!(mesh.ray_to_self_probability(&miss), 0.0);
+    }
+
+    #[test]
+    fn samples_every_direction_without_faces() {
+        let mesh = TriangleMesh::new(Arc::new(MeshBuffers::default()), Vec::new(), material());
+
+        assert_eq!(mesh.area(), 0.0);
+        assert!(mesh.bounding_box(0.0, 1.0).is_none());
+
+        let direction = mesh.random_direction_to_self(Point3::zero(), 0.0, (0.3, 0.7));
+        assert!((direction.len() - 1.0).abs() < 1e-5);
+    }
+}
@@ -1,4 +1,4 @@
-use crate::vec3::{cross, Vec3};
+use crate::vec3::{cross, dot, Vec3};
 
 #[derive(Copy, Clone, Debug)]
 pub struct Onb {
@@ -37,4 +37,9 @@ impl Onb {
     pub fn local(&self, a: Vec3) -> Vec3 {
         self.u() * a.x + self.v() * a.y + self.w() * a.z
     }
+
+    /// Coordinates of a vector in the basis, the inverse of `local`.
+    pub fn to_local(&self, a: Vec3) -> Vec3 {
+        Vec3::new(dot(a, self.u()), dot(a, self.v()), dot(a, self.w()))
+    }
 }
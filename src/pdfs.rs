@@ -2,16 +2,19 @@ use std::f32::consts::PI;
 use std::sync::Arc;
 
 use crate::{
-    helpers::random,
+    backgrounds::Background,
     objects::Object,
     onb::Onb,
     ray::Ray,
+    samplers::ONE_MINUS_EPSILON,
     vec3::{dot, Point3, Vec3},
 };
 
-pub trait ScatteringPdf {
+pub trait ScatteringPdf: Send + Sync {
     fn value(&self, direction: Vec3) -> f32;
-    fn sample(&self) -> Vec3;
+    /// Samples a direction from a uniformly distributed point of the unit
+    /// square.
+    fn sample(&self, u: (f32, f32)) -> Vec3;
 }
 
 pub struct CosinePdf {
@@ -37,14 +40,13 @@ impl ScatteringPdf for CosinePdf {
         }
     }
 
-    fn sample(&self) -> Vec3 {
-        self.onb.local(random_cosine_direction()).unit()
+    fn sample(&self, u: (f32, f32)) -> Vec3 {
+        self.onb.local(random_cosine_direction(u)).unit()
     }
 }
 
-fn random_cosine_direction() -> Vec3 {
-    let r1 = random(0.0..1.0);
-    let r2 = random(0.0..1.0);
+/// Cosine distributed direction around z.
+pub fn random_cosine_direction((r1, r2): (f32, f32)) -> Vec3 {
     let z = f32::sqrt(1.0 - r2);
 
     let phi = 2.0 * PI * r1;
@@ -78,8 +80,9 @@ impl ScatteringPdf for EmissivePdf {
         self.object.ray_to_self_probability(&ray)
     }
 
-    fn sample(&self) -> Vec3 {
-        self.object.random_direction_to_self(self.origin, self.time)
+    fn sample(&self, u: (f32, f32)) -> Vec3 {
+        self.object
+            .random_direction_to_self(self.origin, self.time, u)
     }
 }
 
@@ -100,11 +103,48 @@ impl ScatteringPdf for MixturePdf {
         self.factor * self.pdf1.value(direction) + (1.0 - self.factor) * self.pdf2.value(direction)
     }
 
-    fn sample(&self) -> Vec3 {
-        if random(0.0..1.0) <= self.factor {
-            self.pdf1.sample()
+    fn sample(&self, (u1, u2): (f32, f32)) -> Vec3 {
+        // The sample that picks the PDF is stretched back to the unit
+        // interval, so the chosen one still gets a well distributed sample.
+        if u1 < self.factor {
+            self.pdf1.sample((u1 / self.factor, u2))
         } else {
-            self.pdf2.sample()
+            let u1 = f32::min((u1 - self.factor) / (1.0 - self.factor), ONE_MINUS_EPSILON);
+
+            self.pdf2.sample((u1, u2))
         }
     }
 }
+
+pub struct BackgroundPdf {
+    pub background: Arc<dyn Background>,
+}
+
+impl BackgroundPdf {
+    pub fn new(background: Arc<dyn Background>) -> Self {
+        Self { background }
+    }
+}
+
+impl ScatteringPdf for BackgroundPdf {
+    fn value(&self, direction: Vec3) -> f32 {
+        self.background.direction_probability(direction)
+    }
+
+    fn sample(&self, u: (f32, f32)) -> Vec3 {
+        self.background.random_direction(u)
+    }
+}
+
+/// Weight of a sample drawn with the first PDF, when it could also have
+/// been drawn with the second one.
+pub fn power_heuristic(pdf: f32, other_pdf: f32) -> f32 {
+    let pdf_squared = pdf * pdf;
+    let sum = pdf_squared + other_pdf * other_pdf;
+
+    if sum > 0.0 {
+        pdf_squared / sum
+    } else {
+        0.0
+    }
+}
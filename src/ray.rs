@@ -1,9 +1,12 @@
 use std::sync::Arc;
 
 use crate::{
-    materials::{Material, ScatterResult},
-    pdfs::{EmissivePdf, MixturePdf, ScatteringPdf},
-    rgb_color::{RgbColor, BLACK},
+    lights::DeltaLight,
+    materials::{Bsdf, Material},
+    onb::Onb,
+    pdfs::{power_heuristic, ScatteringPdf},
+    rgb_color::{RgbColor, BLACK, WHITE},
+    samplers::Sampler,
     scene::Scene,
     vec3::{Point3, Vec3},
 };
@@ -35,42 +38,223 @@ impl Ray {
         self.origin + t * self.direction
     }
 
-    pub fn trace(&self, scene: &Scene, depth: u32) -> RgbColor {
-        if depth == 0 {
-            return BLACK;
+    /// Follows the path started by the ray for at most `max_bounces`
+    /// segments. Bounces off BSDFs that aren't purely delta sample the lights
+    /// with a shadow ray and the BSDF with the continuation of the path,
+    /// weighting the light found by each with the power heuristic. After `roulette_depth`
+    /// bounces, paths are randomly cut short with a probability that grows
+    /// as their throughput fades, and the surviving ones are weighted up to
+    /// keep the estimate unbiased.
+    pub fn trace(
+        &self,
+        scene: &Scene,
+        max_bounces: u32,
+        roulette_depth: u32,
+        sampler: &mut dyn Sampler,
+    ) -> RgbColor {
+        let mut radiance = BLACK;
+        // Fraction of the light arriving at the current ray that reaches
+        // the camera.
+        let mut throughput = WHITE;
+        let mut ray = *self;
+        let mut last_bounce: Option<SampledBounce> = None;
+
+        for bounce in 0..max_bounces {
+            if bounce > 0 && bounce >= roulette_depth {
+                let survival_probability = f32::min(throughput.max_component(), 1.0);
+
+                if survival_probability <= 0.0 || sampler.next_1d() >= survival_probability {
+                    break;
+                }
+
+                throughput = throughput / survival_probability;
+            }
+
+            let hit = match scene.objects.hit(&ray, MAX_T) {
+                Some(hit) => hit,
+                None => {
+                    let weight = emission_weight(last_bounce.as_ref(), ray.direction);
+
+                    radiance += throughput * scene.background(&ray) * weight;
+
+                    break;
+                }
+            };
+
+            let material = match hit.material.as_ref() {
+                Material::Reflective(material) => material,
+                Material::Emissive(material) => {
+                    let weight = emission_weight(last_bounce.as_ref(), ray.direction);
+
+                    radiance += throughput * material.emitted(&ray, &hit) * weight;
+
+                    break;
+                }
+            };
+
+            let bsdf = material.bsdf(&ray, &hit, sampler);
+            let scattering = Scattering::new(bsdf.as_ref(), hit.normal, ray.direction);
+
+            // Only lobes that aren't delta scatter the light of a sampled
+            // direction.
+            let samples_lights = bsdf.flags().non_delta;
+            let light_pdf = if samples_lights {
+                scene.light_pdf(hit.point, ray.time)
+            } else {
+                None
+            };
+
+            // On the last bounce the continuation of the path can't find the
+            // lights either, so neither strategy counts them.
+            if bounce + 1 < max_bounces && samples_lights {
+                if let Some(light_pdf) = &light_pdf {
+                    let light =
+                        sample_light(scene, &ray, hit.point, light_pdf, &scattering, sampler);
+
+                    radiance += throughput * light;
+                }
+
+                for delta_light in scene.delta_lights.iter() {
+                    let light =
+                        illuminate(scene, &ray, hit.point, delta_light.as_ref(), &scattering);
+
+                    radiance += throughput * light;
+                }
+            }
+
+            let sample = match bsdf.sample(scattering.wo, sampler.next_2d()) {
+                Some(sample) if sample.pdf.is_normal() => sample,
+                _ => break,
+            };
+
+            let weight = sample.weight();
+
+            if weight.max_component() <= 0.0 {
+                break;
+            }
+
+            throughput *= weight;
+            ray = ray.secondary(hit.point, scattering.frame.local(sample.wi).unit());
+            last_bounce = if sample.is_delta {
+                None
+            } else {
+                light_pdf.map(|light_pdf| SampledBounce {
+                    material_pdf_value: sample.pdf,
+                    light_pdf,
+                })
+            };
         }
 
-        match scene.objects.hit(self, MAX_T) {
-            None => scene.background(self),
-
-            Some(hit) => match hit.material.as_ref() {
-                Material::Reflective(material) => match material.scatter(self, &hit) {
-                    ScatterResult::Diffuse { pdf, attenuation } => {
-                        let lights_pdf = Arc::new(EmissivePdf::new(
-                            Arc::clone(&scene.lights),
-                            hit.point,
-                            self.time,
-                        ));
-                        let mixture_pdf = MixturePdf::new(0.5, lights_pdf, Arc::clone(&pdf));
-
-                        let scattered_ray = self.secondary(hit.point, mixture_pdf.sample());
-                        let pdf_value = mixture_pdf.value(scattered_ray.direction);
-                        let material_pdf_value = pdf.value(scattered_ray.direction);
-
-                        if !pdf_value.is_normal() || !material_pdf_value.is_normal() {
-                            return BLACK;
-                        }
-
-                        attenuation
-                            * (scattered_ray.trace(scene, depth - 1) / pdf_value)
-                            * material_pdf_value
-                    }
-                    ScatterResult::Specular { ray, attenuation } => {
-                        attenuation * ray.trace(scene, depth - 1)
-                    }
-                },
-                Material::Emissive(material) => material.emitted(self, &hit),
-            },
+        radiance
+    }
+}
+
+/// BSDF of a hit, with the frame of its normal that turns the directions of
+/// the scene into its local ones.
+struct Scattering<'a> {
+    bsdf: &'a dyn Bsdf,
+    frame: Onb,
+    wo: Vec3,
+}
+
+impl<'a> Scattering<'a> {
+    fn new(bsdf: &'a dyn Bsdf, normal: Vec3, ray_direction: Vec3) -> Self {
+        let frame = Onb::from_w(normal);
+
+        Self {
+            bsdf,
+            frame,
+            wo: frame.to_local(-ray_direction.unit()),
         }
     }
+
+    fn eval(&self, direction: Vec3) -> RgbColor {
+        self.bsdf
+            .eval(self.wo, self.frame.to_local(direction.unit()))
+    }
+
+    fn pdf(&self, direction: Vec3) -> f32 {
+        self.bsdf
+            .pdf(self.wo, self.frame.to_local(direction.unit()))
+    }
+}
+
+/// Non-delta scattering that sampled the direction of the current ray,
+/// kept to weight the light it finds against sampling the lights instead.
+struct SampledBounce {
+    material_pdf_value: f32,
+    light_pdf: Arc<dyn ScatteringPdf>,
+}
+
+fn emission_weight(last_bounce: Option<&SampledBounce>, direction: Vec3) -> f32 {
+    match last_bounce {
+        Some(bounce) => {
+            power_heuristic(bounce.material_pdf_value, bounce.light_pdf.value(direction))
+        }
+        // Camera rays and delta lobes can't be found by sampling the
+        // lights.
+        None => 1.0,
+    }
+}
+
+/// Light arriving at the point from a direction sampled towards the lights
+/// and scattered along the ray, weighted by the power heuristic.
+fn sample_light(
+    scene: &Scene,
+    ray: &Ray,
+    point: Point3,
+    light_pdf: &Arc<dyn ScatteringPdf>,
+    scattering: &Scattering,
+    sampler: &mut dyn Sampler,
+) -> RgbColor {
+    let direction = light_pdf.sample(sampler.next_2d());
+    let light_pdf_value = light_pdf.value(direction);
+    let scattered = scattering.eval(direction);
+
+    if !light_pdf_value.is_normal() || scattered.max_component() <= 0.0 {
+        return BLACK;
+    }
+
+    let shadow_ray = ray.secondary(point, direction);
+
+    // Whatever the shadow ray finds first is the light arriving from its
+    // direction, which is black if it's blocked by a reflective object.
+    let emitted = match scene.objects.hit(&shadow_ray, MAX_T) {
+        None => scene.background(&shadow_ray),
+        Some(hit) => match hit.material.as_ref() {
+            Material::Emissive(material) => material.emitted(&shadow_ray, &hit),
+            Material::Reflective(_) => return BLACK,
+        },
+    };
+
+    emitted * scattered / light_pdf_value
+        * power_heuristic(light_pdf_value, scattering.pdf(direction))
+}
+
+/// Light arriving at the point from a delta light and scattered along the
+/// ray.
+fn illuminate(
+    scene: &Scene,
+    ray: &Ray,
+    point: Point3,
+    delta_light: &dyn DeltaLight,
+    scattering: &Scattering,
+) -> RgbColor {
+    let sample = match delta_light.illuminate(point) {
+        Some(sample) => sample,
+        None => return BLACK,
+    };
+
+    let scattered = scattering.eval(sample.direction);
+
+    if scattered.max_component() <= 0.0 {
+        return BLACK;
+    }
+
+    let shadow_ray = ray.secondary(point, sample.direction);
+
+    match scene.objects.hit(&shadow_ray, sample.distance) {
+        Some(_) => BLACK,
+        None => sample.irradiance * scattered,
+    }
 }
@@ -2,5 +2,8 @@ pub mod output;
 
 mod pixel;
 mod renderer;
+mod settings;
+mod tile;
+mod tone_mapper;
 
-pub use self::{pixel::*, renderer::*};
+pub use self::{pixel::*, renderer::*, settings::*, tile::*, tone_mapper::*};
@@ -1,9 +1,33 @@
-use std::path::Path;
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
 
-use crate::rgb_color::RawRgbColor;
+use crate::rgb_color::{RawRgbColor, RgbColor};
 
 const PPM_EXTENSION: &str = "ppm";
 
+/// Formats that store linear radiance as floats instead of display values.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum HdrFormat {
+    OpenExr,
+    Radiance,
+    Pfm,
+}
+
+impl HdrFormat {
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<Self> {
+        let extension = path.as_ref().extension()?.to_str()?.to_ascii_lowercase();
+
+        match &extension[..] {
+            "exr" => Some(Self::OpenExr),
+            "hdr" => Some(Self::Radiance),
+            "pfm" => Some(Self::Pfm),
+            _ => None,
+        }
+    }
+}
+
 pub fn save_image(
     path_str: &str,
     rgb_list: &[RawRgbColor],
@@ -16,13 +40,542 @@ pub fn save_image(
         .collect();
 
     let path = Path::new(path_str);
-    let extension = path.extension().unwrap();
 
-    if extension != PPM_EXTENSION {
-        image::save_buffer(path, &buffer[..], width, height, image::ColorType::Rgb8)?;
+    let save = |path: &Path| {
+        write_atomically(path, |temporary_path| {
+            image::save_buffer(
+                temporary_path,
+                &buffer[..],
+                width,
+                height,
+                image::ColorType::Rgb8,
+            )
+        })
+    };
+
+    if path
+        .extension()
+        .is_none_or(|extension| extension != PPM_EXTENSION)
+    {
+        save(path)?;
+    }
+
+    save(&path.with_extension(PPM_EXTENSION))
+}
+
+/// Saves linear colors, ordered from the top row down, in the given format.
+pub fn save_hdr_image(
+    path: &str,
+    format: HdrFormat,
+    rgb_list: &[RgbColor],
+    width: u32,
+    height: u32,
+) -> io::Result<()> {
+    write_atomically(Path::new(path), |temporary_path| {
+        let mut writer = BufWriter::new(File::create(temporary_path)?);
+
+        match format {
+            HdrFormat::OpenExr => write_exr(&mut writer, rgb_list, width, height)?,
+            HdrFormat::Radiance => write_radiance_hdr(&mut writer, rgb_list, width, height)?,
+            HdrFormat::Pfm => write_pfm(&mut writer, rgb_list, width, height)?,
+        }
+
+        writer.flush()
+    })
+}
+
+/// Writes to a sibling of `path` and renames it into place once complete,
+/// so that viewers reloading progressive snapshots never see a partial
+/// image.
+fn write_atomically<E, F>(path: &Path, write: F) -> Result<(), E>
+where
+    E: From<io::Error>,
+    F: FnOnce(&Path) -> Result<(), E>,
+{
+    let temporary_path = temporary_path(path);
+
+    if let Err(error) = write(&temporary_path) {
+        let _ = fs::remove_file(&temporary_path);
+
+        return Err(error);
     }
 
-    let ppm_path = path.with_extension(PPM_EXTENSION);
+    Ok(fs::rename(&temporary_path, path)?)
+}
+
+/// Hidden file next to `path`, in the same directory so that renaming it
+/// doesn't move data across file systems. It keeps the extension, which
+/// picks the format of the image.
+fn temporary_path(path: &Path) -> PathBuf {
+    let mut file_name = OsString::from(".tmp.");
+    file_name.push(path.file_name().unwrap_or_default());
+
+    path.with_file_name(file_name)
+}
+
+const EXR_MAGIC_NUMBER: [u8; 4] = [0x76, 0x2f, 0x31, 0x01];
+const EXR_VERSION: u32 = 2;
+const EXR_FLOAT_PIXEL_TYPE: i32 = 2;
+const EXR_NO_COMPRESSION: u8 = 0;
+const EXR_INCREASING_Y_LINE_ORDER: u8 = 0;
+
+type ChannelValue = fn(&RgbColor) -> f32;
+
+/// Single part, uncompressed, scanline OpenEXR image with 32-bit float
+/// `R`, `G` and `B` channels.
+fn write_exr<W: Write>(
+    writer: &mut W,
+    rgb_list: &[RgbColor],
+    width: u32,
+    height: u32,
+) -> io::Result<()> {
+    // Channels have to be listed, and stored, in alphabetical order.
+    let channels: [(&str, ChannelValue); 3] = [
+        ("B", |color| color.blue),
+        ("G", |color| color.green),
+        ("R", |color| color.red),
+    ];
+
+    let mut channel_list = Vec::new();
+
+    for (name, _) in channels.iter() {
+        channel_list.extend_from_slice(name.as_bytes());
+        channel_list.push(0);
+        channel_list.extend_from_slice(&EXR_FLOAT_PIXEL_TYPE.to_le_bytes());
+        // Perceptually linear flag, reserved bytes and sampling.
+        channel_list.extend_from_slice(&[0, 0, 0, 0]);
+        channel_list.extend_from_slice(&1_i32.to_le_bytes());
+        channel_list.extend_from_slice(&1_i32.to_le_bytes());
+    }
+
+    channel_list.push(0);
+
+    let window: Vec<_> = [0, 0, width as i32 - 1, height as i32 - 1]
+        .iter()
+        .flat_map(|value: &i32| value.to_le_bytes().to_vec())
+        .collect();
+
+    let mut header = Vec::new();
+
+    header.extend_from_slice(&EXR_MAGIC_NUMBER);
+    header.extend_from_slice(&EXR_VERSION.to_le_bytes());
+
+    let mut attribute = |name: &str, kind: &str, value: &[u8]| {
+        header.extend_from_slice(name.as_bytes());
+        header.push(0);
+        header.extend_from_slice(kind.as_bytes());
+        header.push(0);
+        header.extend_from_slice(&(value.len() as i32).to_le_bytes());
+        header.extend_from_slice(value);
+    };
+
+    attribute("channels", "chlist", &channel_list);
+    attribute("compression", "compression", &[EXR_NO_COMPRESSION]);
+    attribute("dataWindow", "box2i", &window);
+    attribute("displayWindow", "box2i", &window);
+    attribute("lineOrder", "lineOrder", &[EXR_INCREASING_Y_LINE_ORDER]);
+    attribute("pixelAspectRatio", "float", &1.0_f32.to_le_bytes());
+    attribute("screenWindowCenter", "v2f", &[0; 8]);
+    attribute("screenWindowWidth", "float", &1.0_f32.to_le_bytes());
+
+    header.push(0);
+
+    writer.write_all(&header)?;
+
+    // Uncompressed images store every scanline in its own chunk, made of
+    // its row, its size and the pixels of one channel after the other.
+    let line_size = width as usize * channels.len() * 4;
+    let chunk_size = 8 + line_size;
+    let chunks_start = header.len() + height as usize * 8;
+
+    for row in 0..height as usize {
+        let offset = (chunks_start + row * chunk_size) as u64;
+
+        writer.write_all(&offset.to_le_bytes())?;
+    }
+
+    for (row, line) in rgb_list.chunks(width as usize).enumerate() {
+        writer.write_all(&(row as i32).to_le_bytes())?;
+        writer.write_all(&(line_size as i32).to_le_bytes())?;
+
+        for (_, channel) in channels.iter() {
+            for color in line {
+                writer.write_all(&channel(color).to_le_bytes())?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Uncompressed Radiance RGBE image.
+fn write_radiance_hdr<W: Write>(
+    writer: &mut W,
+    rgb_list: &[RgbColor],
+    width: u32,
+    height: u32,
+) -> io::Result<()> {
+    write!(
+        writer,
+        "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n",
+        height, width
+    )?;
+
+    for color in rgb_list {
+        writer.write_all(&to_rgbe(color))?;
+    }
+
+    Ok(())
+}
+
+/// Shares the exponent of the largest component between the three of them.
+fn to_rgbe(color: &RgbColor) -> [u8; 4] {
+    let red = f32::max(color.red, 0.0);
+    let green = f32::max(color.green, 0.0);
+    let blue = f32::max(color.blue, 0.0);
+
+    let max_component = red.max(green).max(blue);
+
+    if !max_component.is_finite() || max_component < 1e-32 {
+        return [0, 0, 0, 0];
+    }
+
+    // `max_component` is `mantissa * 2^exponent`, with the mantissa in
+    // [0.5, 1).
+    let exponent = i32::min(f32::floor(f32::log2(max_component)) as i32 + 1, 127);
+    let scale = 256.0 / f32::powi(2.0, exponent);
+
+    let quantize = |component: f32| f32::min(component * scale, 255.0) as u8;
+
+    [
+        quantize(red),
+        quantize(green),
+        quantize(blue),
+        (exponent + 128) as u8,
+    ]
+}
+
+/// Little endian portable float map, stored from the bottom row up.
+fn write_pfm<W: Write>(
+    writer: &mut W,
+    rgb_list: &[RgbColor],
+    width: u32,
+    height: u32,
+) -> io::Result<()> {
+    write!(writer, "PF\n{} {}\n-1.0\n", width, height)?;
+
+    for line in rgb_list.chunks(width as usize).rev() {
+        for color in line {
+            writer.write_all(&color.red.to_le_bytes())?;
+            writer.write_all(&color.green.to_le_bytes())?;
+            writer.write_all(&color.blue.to_le_bytes())?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryInto;
+
+    use super::*;
+
+    use crate::helpers::tests::TemporaryDirectory;
+
+    /// Two rows of three pixels, all different.
+    fn test_image() -> Vec<RgbColor> {
+        (0..6)
+            .map(|i| RgbColor::new(i as f32, 0.5 * i as f32, -(i as f32)))
+            .collect()
+    }
 
-    image::save_buffer(ppm_path, &buffer[..], width, height, image::ColorType::Rgb8)
+    fn read_f32(bytes: &[u8], offset: usize) -> f32 {
+        f32::from_le_bytes([
+            bytes[offset],
+            bytes[offset + 1],
+            bytes[offset + 2],
+            bytes[offset + 3],
+        ])
+    }
+
+    fn read_i32(bytes: &[u8], offset: usize) -> i32 {
+        i32::from_le_bytes([
+            bytes[offset],
+            bytes[offset + 1],
+            bytes[offset + 2],
+            bytes[offset + 3],
+        ])
+    }
+
+    fn read_null_terminated(bytes: &[u8], offset: usize) -> &str {
+        let len = bytes[offset..].iter().position(|&byte| byte == 0).unwrap();
+
+        std::str::from_utf8(&bytes[offset..offset + len]).unwrap()
+    }
+
+    #[test]
+    fn writes_exr_headers_offsets_and_scanlines() {
+        let image = test_image();
+        let mut bytes = Vec::new();
+
+        write_exr(&mut bytes, &image, 3, 2).unwrap();
+
+        assert_eq!(bytes[..4], EXR_MAGIC_NUMBER);
+        assert_eq!(read_i32(&bytes, 4), 2);
+
+        let mut attributes = Vec::new();
+        let mut offset = 8;
+
+        while bytes[offset] != 0 {
+            let name = read_null_terminated(&bytes, offset);
+            offset += name.len() + 1;
+
+            let kind = read_null_terminated(&bytes, offset);
+            offset += kind.len() + 1;
+
+            let size = read_i32(&bytes, offset) as usize;
+            offset += 4;
+
+            attributes.push((name, kind, &bytes[offset..offset + size]));
+            offset += size;
+        }
+
+        let header_size = offset + 1;
+
+        let names: Vec<_> = attributes
+            .iter()
+            .map(|(name, kind, _)| (*name, *kind))
+            .collect();
+
+        assert_eq!(
+            names,
+            vec![
+                ("channels", "chlist"),
+                ("compression", "compression"),
+                ("dataWindow", "box2i"),
+                ("displayWindow", "box2i"),
+                ("lineOrder", "lineOrder"),
+                ("pixelAspectRatio", "float"),
+                ("screenWindowCenter", "v2f"),
+                ("screenWindowWidth", "float"),
+            ]
+        );
+
+        let channels = attributes[0].2;
+
+        assert_eq!(channels.len(), 3 * 18 + 1);
+
+        for (index, name) in ["B", "G", "R"].iter().enumerate() {
+            let channel = &channels[index * 18..(index + 1) * 18];
+
+            assert_eq!(read_null_terminated(channel, 0), *name);
+            assert_eq!(read_i32(channel, 2), EXR_FLOAT_PIXEL_TYPE);
+            assert_eq!((read_i32(channel, 10), read_i32(channel, 14)), (1, 1));
+        }
+
+        assert_eq!(attributes[1].2, [EXR_NO_COMPRESSION]);
+
+        let window: Vec<_> = (0..4).map(|i| read_i32(attributes[2].2, 4 * i)).collect();
+
+        assert_eq!(window, vec![0, 0, 2, 1]);
+        assert_eq!(attributes[3].2, attributes[2].2);
+
+        // The offset table points at one chunk per scanline, in order.
+        let line_size = 3 * 3 * 4;
+        let chunks_start = header_size + 2 * 8;
+
+        for row in 0..2 {
+            let entry = header_size + row * 8;
+            let chunk_offset = u64::from_le_bytes(bytes[entry..entry + 8].try_into().unwrap());
+
+            assert_eq!(chunk_offset as usize, chunks_start + row * (8 + line_size));
+
+            let chunk = &bytes[chunk_offset as usize..];
+
+            assert_eq!(read_i32(chunk, 0), row as i32);
+            assert_eq!(read_i32(chunk, 4), line_size as i32);
+
+            for column in 0..3 {
+                let color = image[row * 3 + column];
+
+                assert_eq!(read_f32(chunk, 8 + 4 * column), color.blue);
+                assert_eq!(read_f32(chunk, 8 + 12 + 4 * column), color.green);
+                assert_eq!(read_f32(chunk, 8 + 24 + 4 * column), color.red);
+            }
+        }
+
+        assert_eq!(bytes.len(), chunks_start + 2 * (8 + line_size));
+    }
+
+    fn from_rgbe([red, green, blue, exponent]: [u8; 4]) -> RgbColor {
+        if exponent == 0 {
+            return RgbColor::new(0.0, 0.0, 0.0);
+        }
+
+        let scale = f32::powi(2.0, exponent as i32 - 128 - 8);
+
+        RgbColor::new(
+            red as f32 * scale,
+            green as f32 * scale,
+            blue as f32 * scale,
+        )
+    }
+
+    #[test]
+    fn round_trips_rgbe_within_quantization() {
+        let values = [
+            1e-20, 0.001, 0.1, 0.5, 0.999, 1.0, 1.5, 3.0, 255.0, 1e6, 1e30,
+        ];
+
+        for &red in values.iter() {
+            for &green in values.iter() {
+                for &blue in [0.0, 0.3, 7.0].iter() {
+                    let color = RgbColor::new(red, green, blue);
+                    let decoded = from_rgbe(to_rgbe(&color));
+
+                    // Components are truncated to 8 bits of the largest one.
+                    let step = red.max(green).max(blue) / 128.0;
+
+                    for &(original, decoded) in [
+                        (color.red, decoded.red),
+                        (color.green, decoded.green),
+                        (color.blue, decoded.blue),
+                    ]
+                    .iter()
+                    {
+                        assert!(
+                            decoded <= original && original - decoded <= step,
+                            "{:?} decoded as {:?}",
+                            color,
+                            decoded
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn encodes_black_negative_and_non_finite_colors_as_zero() {
+        for color in [
+            RgbColor::new(0.0, 0.0, 0.0),
+            RgbColor::new(-1.0, -0.5, -2.0),
+            RgbColor::new(1e-35, 0.0, 0.0),
+            RgbColor::new(f32::INFINITY, 1.0, 1.0),
+            RgbColor::new(f32::NAN, f32::NAN, f32::NAN),
+        ]
+        .iter()
+        {
+            assert_eq!(to_rgbe(color), [0, 0, 0, 0], "{:?}", color);
+        }
+
+        // Negative components are clamped while the others are kept.
+        assert_eq!(
+            from_rgbe(to_rgbe(&RgbColor::new(-1.0, 0.5, 0.25))),
+            RgbColor::new(0.0, 0.5, 0.25)
+        );
+    }
+
+    #[test]
+    fn writes_radiance_headers() {
+        let mut bytes = Vec::new();
+
+        write_radiance_hdr(&mut bytes, &test_image(), 3, 2).unwrap();
+
+        let header = "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 2 +X 3\n";
+
+        assert_eq!(&bytes[..header.len()], header.as_bytes());
+        assert_eq!(bytes.len(), header.len() + 6 * 4);
+        assert_eq!(
+            bytes[header.len() + 4..header.len() + 8],
+            to_rgbe(&test_image()[1])
+        );
+    }
+
+    #[test]
+    fn writes_pfm_rows_from_the_bottom_up() {
+        let image = test_image();
+        let mut bytes = Vec::new();
+
+        write_pfm(&mut bytes, &image, 3, 2).unwrap();
+
+        let header = "PF\n3 2\n-1.0\n";
+
+        assert_eq!(&bytes[..header.len()], header.as_bytes());
+        assert_eq!(bytes.len(), header.len() + 6 * 12);
+
+        let pixels = &bytes[header.len()..];
+
+        for (index, row) in [1, 0].iter().enumerate() {
+            for column in 0..3 {
+                let color = image[row * 3 + column];
+                let offset = (index * 3 + column) * 12;
+
+                assert_eq!(read_f32(pixels, offset), color.red);
+                assert_eq!(read_f32(pixels, offset + 4), color.green);
+                assert_eq!(read_f32(pixels, offset + 8), color.blue);
+            }
+        }
+    }
+
+    /// Names of the files in the directory, sorted.
+    fn file_names(directory: &Path) -> Vec<String> {
+        let mut names: Vec<_> = fs::read_dir(directory)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        names.sort();
+
+        names
+    }
+
+    #[test]
+    fn replaces_images_without_leaving_temporary_files() {
+        let directory = TemporaryDirectory::new("output-replace");
+        let path = directory.path().join("image.pfm");
+        let path_str = path.to_str().unwrap();
+
+        fs::write(&path, b"previous snapshot").unwrap();
+        save_hdr_image(path_str, HdrFormat::Pfm, &test_image(), 3, 2).unwrap();
+
+        let mut expected = Vec::new();
+        write_pfm(&mut expected, &test_image(), 3, 2).unwrap();
+
+        assert_eq!(fs::read(&path).unwrap(), expected);
+        assert_eq!(file_names(directory.path()), vec!["image.pfm"]);
+
+        let raw: Vec<_> = test_image().iter().map(|color| color.to_raw()).collect();
+        let path = directory.path().join("image.png");
+
+        save_image(path.to_str().unwrap(), &raw, 3, 2).unwrap();
+        save_image(path.to_str().unwrap(), &raw, 3, 2).unwrap();
+
+        assert_eq!(
+            file_names(directory.path()),
+            vec!["image.pfm", "image.png", "image.ppm"]
+        );
+        assert_eq!(
+            image::open(&path).unwrap().to_rgb8().into_raw(),
+            raw.concat()
+        );
+    }
+
+    #[test]
+    fn keeps_the_previous_image_when_saving_fails() {
+        let directory = TemporaryDirectory::new("output-failure");
+        let path = directory.path().join("image.hdr");
+
+        fs::write(&path, b"previous snapshot").unwrap();
+
+        let result: io::Result<()> = write_atomically(&path, |temporary_path| {
+            fs::write(temporary_path, b"partial")?;
+
+            Err(io::Error::other("disk full"))
+        });
+
+        assert!(result.is_err());
+        assert_eq!(fs::read(&path).unwrap(), b"previous snapshot");
+        assert_eq!(file_names(directory.path()), vec!["image.hdr"]);
+    }
 }
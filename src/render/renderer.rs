@@ -1,12 +1,20 @@
-use std::sync::{Arc, Mutex};
+use std::ops::Range;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
 
+use rand::Rng as _;
 use rayon::prelude::*;
 
 use crate::{
     camera::Camera,
-    helpers::random,
-    render::output::save_image,
-    rgb_color::{RawRgbColor, RgbColor, BLACK},
+    render::{
+        output::{save_hdr_image, save_image, HdrFormat},
+        RenderSettings, Tile, TILE_SIZE,
+    },
+    rgb_color::{RgbColor, BLACK},
+    rng::{hash, Rng},
+    samplers::Sampler,
     scene::Scene,
 };
 
@@ -21,72 +29,175 @@ impl Renderer {
         Self { camera, scene }
     }
 
-    pub fn render(
+    pub fn render(&self, path: &str, settings: &RenderSettings) -> image::ImageResult<()> {
+        let image_height = settings.image_height;
+        let image_width = f32::round(image_height as f32 * self.camera.aspect_ratio) as u32;
+
+        let samples_per_pixel = settings.samples_per_pixel;
+        let samples_per_pass = settings
+            .progressive
+            .map_or(samples_per_pixel, |progressive| {
+                u32::min(progressive.samples_per_pass, samples_per_pixel)
+            });
+
+        let mut sums = vec![BLACK; (image_width * image_height) as usize];
+        let mut rendered_samples = 0;
+
+        let mut passes_since_snapshot = 0;
+        let mut last_snapshot = Instant::now();
+
+        while rendered_samples < samples_per_pixel {
+            let pass_samples =
+                rendered_samples..u32::min(rendered_samples + samples_per_pass, samples_per_pixel);
+
+            self.render_pass(
+                &mut sums[..],
+                image_height,
+                image_width,
+                pass_samples.clone(),
+                settings,
+            );
+
+            rendered_samples = pass_samples.end;
+            passes_since_snapshot += 1;
+
+            if rendered_samples == samples_per_pixel {
+                break;
+            }
+
+            if let Some(progressive) = settings.progressive {
+                if progressive.is_snapshot_due(passes_since_snapshot, last_snapshot.elapsed()) {
+                    self.save(path, &sums[..], rendered_samples, image_width, settings)?;
+
+                    passes_since_snapshot = 0;
+                    last_snapshot = Instant::now();
+                }
+            }
+        }
+
+        self.save(path, &sums[..], rendered_samples, image_width, settings)
+    }
+
+    fn save(
         &self,
         path: &str,
-        image_height: u32,
-        samples_per_pixel: u32,
-        max_ray_bounces: u32,
-        gamma: f32,
+        sums: &[RgbColor],
+        rendered_samples: u32,
+        image_width: u32,
+        settings: &RenderSettings,
     ) -> image::ImageResult<()> {
-        let image_width = f32::round(image_height as f32 * self.camera.aspect_ratio) as u32;
-
-        let raw_rgb_list = self.render_in_parallel(
-            image_height,
-            image_width,
-            samples_per_pixel,
-            max_ray_bounces,
-            gamma,
-        );
+        let scale = settings.exposure_scale() / rendered_samples as f32;
+
+        // High dynamic range formats get the linear radiance, without tone
+        // mapping, encoding or clamping.
+        if let Some(format) = HdrFormat::from_path(path) {
+            let rgb_list: Vec<_> = sums.iter().map(|&sum| sum * scale).collect();
+
+            return Ok(save_hdr_image(
+                path,
+                format,
+                &rgb_list[..],
+                image_width,
+                settings.image_height,
+            )?);
+        }
+
+        let raw_rgb_list: Vec<_> = sums
+            .iter()
+            .enumerate()
+            .map(|(pixel_index, &sum)| {
+                let color = settings
+                    .tone_mapper
+                    .map_or(sum * scale, |tone_mapper| tone_mapper.apply(sum * scale));
+                let color = settings.transfer_function.encode_color(color);
+
+                if settings.dither {
+                    color.to_dithered_raw(dither_offsets(settings.seed, pixel_index))
+                } else {
+                    color.to_raw()
+                }
+            })
+            .collect();
+
+        save_image(path, &raw_rgb_list[..], image_width, settings.image_height)
+    }
 
-        save_image(path, &raw_rgb_list[..], image_width, image_height)
+    /// Adds the given range of samples of every pixel to `sums`.
+    fn render_pass(
+        &self,
+        sums: &mut [RgbColor],
+        image_height: u32,
+        image_width: u32,
+        samples: Range<u32>,
+        settings: &RenderSettings,
+    ) {
+        let tiles = Tile::split_image(image_width, image_height, TILE_SIZE);
+        let tile_count = tiles.len();
+        let finished_tiles = AtomicUsize::new(0);
+
+        let samples_per_pixel = settings.samples_per_pixel as usize;
+        let samples_before_pass = samples.start as usize;
+        let pass_sample_count = samples.len();
+
+        let rendered_tiles: Vec<_> = tiles
+            .into_par_iter()
+            .map(|tile| {
+                let colors =
+                    self.render_tile(&tile, image_height, image_width, samples.clone(), settings);
+
+                let finished = finished_tiles.fetch_add(1, Ordering::Relaxed) + 1;
+                let progress = (samples_before_pass * tile_count + finished * pass_sample_count)
+                    * 100
+                    / (samples_per_pixel * tile_count);
+                eprint!("\rRendering... {}%", progress);
+
+                (tile, colors)
+            })
+            .collect();
+
+        for (tile, colors) in rendered_tiles.iter() {
+            for ((x, y), &color) in tile.pixels().zip(colors.iter()) {
+                let sum = &mut sums[(y * image_width + x) as usize];
+
+                *sum += color;
+            }
+        }
     }
 
-    fn render_in_parallel(
+    fn render_tile(
         &self,
+        tile: &Tile,
         image_height: u32,
         image_width: u32,
-        samples_per_pixel: u32,
-        max_ray_bounces: u32,
-        gamma: f32,
-    ) -> Vec<RawRgbColor> {
-        let pixel_count = image_height * image_width;
-
-        let render_data = Arc::new(Mutex::new((vec![BLACK; pixel_count as usize], 0)));
-
-        (0..samples_per_pixel).into_par_iter().for_each(|_| {
-            let image_layer: Vec<_> = (0..image_height)
-                .rev()
-                .flat_map(|y| {
-                    (0..image_width)
-                        .map(|x| {
-                            self.render_sample(image_height, image_width, x, y, max_ray_bounces)
-                        })
-                        .collect::<Vec<_>>()
+        samples: Range<u32>,
+        settings: &RenderSettings,
+    ) -> Vec<RgbColor> {
+        let mut sampler = settings
+            .sampler
+            .build(settings.seed, settings.samples_per_pixel);
+
+        tile.pixels()
+            .map(|(x, row)| {
+                // Rows are counted from the top, while the camera expects
+                // coordinates growing upwards.
+                let y = image_height - 1 - row;
+
+                samples.clone().fold(BLACK, |sum, sample| {
+                    // Samplers are seeded from the pixel and sample index
+                    // instead of carrying state over, which keeps the image
+                    // independent of the order in which tiles are rendered.
+                    sampler.start_pixel_sample(x, y, sample);
+
+                    sum + self.render_sample(
+                        image_height,
+                        image_width,
+                        x,
+                        y,
+                        settings,
+                        sampler.as_mut(),
+                    )
                 })
-                .collect();
-
-            let render_data_clone = Arc::clone(&render_data);
-            let mut data = render_data_clone.lock().unwrap();
-
-            (*data).0 = (*data)
-                .0
-                .iter()
-                .zip(image_layer.iter())
-                .map(|(&color1, &color2)| color1 + color2 / samples_per_pixel as f32)
-                .collect();
-            (*data).1 += 1;
-
-            eprint!("\rRendering... {}%", (*data).1 * 100 / samples_per_pixel);
-        });
-
-        let render_data_clone = Arc::clone(&render_data);
-        let data = render_data_clone.lock().unwrap();
-
-        (*data)
-            .0
-            .iter()
-            .map(|color| color.gamma_correct(gamma).to_raw())
+            })
             .collect()
     }
 
@@ -96,13 +207,30 @@ impl Renderer {
         image_width: u32,
         x: u32,
         y: u32,
-        max_ray_bounces: u32,
+        settings: &RenderSettings,
+        sampler: &mut dyn Sampler,
     ) -> RgbColor {
-        let s = (x as f32 + random(0.0..1.0)) / (image_width as f32 - 1.0);
-        let t = (y as f32 + random(0.0..1.0)) / (image_height as f32 - 1.0);
+        let (jitter_x, jitter_y) = sampler.next_2d();
+
+        let s = (x as f32 + jitter_x) / (image_width as f32 - 1.0);
+        let t = (y as f32 + jitter_y) / (image_height as f32 - 1.0);
 
-        let ray = self.camera.cast_ray(s, t);
+        let ray = self.camera.cast_ray(s, t, sampler);
 
-        ray.trace(&self.scene, max_ray_bounces)
+        ray.trace(
+            &self.scene,
+            settings.max_ray_bounces,
+            settings.russian_roulette_depth,
+            sampler,
+        )
     }
 }
+
+/// Triangularly distributed offsets in (-1, 1), the same for a pixel in
+/// every snapshot.
+fn dither_offsets(seed: u64, pixel_index: usize) -> [f32; 3] {
+    let mut rng = Rng::from_seed(hash(&[seed, pixel_index as u64]));
+    let mut offset = || rng.gen::<f32>() - rng.gen::<f32>();
+
+    [offset(), offset(), offset()]
+}
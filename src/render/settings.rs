@@ -0,0 +1,83 @@
+use std::time::Duration;
+
+use crate::{color_space::TransferFunction, render::ToneMapper, samplers::SamplerKind};
+
+/// Renders the samples of every pixel in passes, saving the image rendered
+/// so far every given number of passes or amount of time.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct ProgressiveSettings {
+    pub samples_per_pass: u32,
+    pub snapshot_passes: Option<u32>,
+    pub snapshot_interval: Option<Duration>,
+}
+
+impl ProgressiveSettings {
+    pub const fn new(samples_per_pass: u32) -> Self {
+        Self {
+            samples_per_pass,
+            snapshot_passes: None,
+            snapshot_interval: None,
+        }
+    }
+
+    pub fn is_snapshot_due(
+        &self,
+        passes_since_snapshot: u32,
+        time_since_snapshot: Duration,
+    ) -> bool {
+        self.snapshot_passes
+            .is_some_and(|passes| passes_since_snapshot >= passes)
+            || self
+                .snapshot_interval
+                .is_some_and(|interval| time_since_snapshot >= interval)
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct RenderSettings {
+    pub image_height: u32,
+    pub samples_per_pixel: u32,
+    pub max_ray_bounces: u32,
+    /// Bounces after which paths may be terminated by Russian roulette.
+    pub russian_roulette_depth: u32,
+    /// Exposure compensation in stops (EV), applied before tone mapping.
+    pub exposure: f32,
+    /// Without a tone mapper colors are clamped.
+    pub tone_mapper: Option<ToneMapper>,
+    pub transfer_function: TransferFunction,
+    /// Adds triangular noise of one quantization step before rounding to
+    /// 8 bits, which breaks up banding in smooth gradients.
+    pub dither: bool,
+    /// Seed of the random numbers, the same seed renders the same image.
+    pub seed: u64,
+    pub sampler: SamplerKind,
+    pub progressive: Option<ProgressiveSettings>,
+}
+
+impl RenderSettings {
+    pub const fn new(image_height: u32, samples_per_pixel: u32, max_ray_bounces: u32) -> Self {
+        Self {
+            image_height,
+            samples_per_pixel,
+            max_ray_bounces,
+            russian_roulette_depth: 3,
+            exposure: 0.0,
+            tone_mapper: None,
+            transfer_function: TransferFunction::Srgb,
+            dither: false,
+            seed: 0,
+            sampler: SamplerKind::Sobol,
+            progressive: None,
+        }
+    }
+
+    pub fn exposure_scale(&self) -> f32 {
+        f32::powf(2.0, self.exposure)
+    }
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        Self::new(400, 10, 10)
+    }
+}
@@ -0,0 +1,50 @@
+pub const TILE_SIZE: u32 = 16;
+
+/// A rectangle of the image rendered as a unit of work. Rows are counted
+/// from the top of the image.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Tile {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Tile {
+    pub const fn new(x: u32, y: u32, width: u32, height: u32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    pub fn split_image(image_width: u32, image_height: u32, tile_size: u32) -> Vec<Self> {
+        (0..image_height)
+            .step_by(tile_size as usize)
+            .flat_map(|y| {
+                (0..image_width).step_by(tile_size as usize).map(move |x| {
+                    Self::new(
+                        x,
+                        y,
+                        u32::min(tile_size, image_width - x),
+                        u32::min(tile_size, image_height - y),
+                    )
+                })
+            })
+            .collect()
+    }
+
+    pub fn pixel_count(&self) -> usize {
+        (self.width * self.height) as usize
+    }
+
+    /// Image coordinates of the pixels of the tile, row by row.
+    pub fn pixels(&self) -> impl Iterator<Item = (u32, u32)> {
+        let tile = *self;
+
+        (tile.y..(tile.y + tile.height))
+            .flat_map(move |y| (tile.x..(tile.x + tile.width)).map(move |x| (x, y)))
+    }
+}
@@ -0,0 +1,177 @@
+use crate::rgb_color::RgbColor;
+
+pub const DEFAULT_WHITE_POINT: f32 = 4.0;
+
+/// Compresses linear radiance into the displayable range before it's
+/// encoded and quantized.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum ToneMapper {
+    Reinhard,
+    /// Reinhard with the given luminance mapped to white instead of
+    /// infinity.
+    ExtendedReinhard {
+        white_point: f32,
+    },
+    /// Narkowicz's fit of the ACES filmic curve.
+    Aces,
+    /// Hable's filmic curve from Uncharted 2.
+    Hable,
+}
+
+impl ToneMapper {
+    pub const NAMES: [&'static str; 4] = ["reinhard", "extended-reinhard", "aces", "hable"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "reinhard" => Some(Self::Reinhard),
+            "extended-reinhard" => Some(Self::ExtendedReinhard {
+                white_point: DEFAULT_WHITE_POINT,
+            }),
+            "aces" => Some(Self::Aces),
+            "hable" => Some(Self::Hable),
+            _ => None,
+        }
+    }
+
+    pub fn apply(&self, color: RgbColor) -> RgbColor {
+        match *self {
+            // Reinhard curves scale the luminance to keep hues from
+            // shifting.
+            Self::Reinhard => scale_luminance(color, |luminance| luminance / (1.0 + luminance)),
+            Self::ExtendedReinhard { white_point } => scale_luminance(color, |luminance| {
+                luminance * (1.0 + luminance / white_point.powi(2)) / (1.0 + luminance)
+            }),
+            Self::Aces => map_channels(color, aces),
+            Self::Hable => map_channels(color, |value| {
+                hable_partial(value * HABLE_EXPOSURE_BIAS) / hable_partial(HABLE_WHITE_POINT)
+            }),
+        }
+    }
+}
+
+const HABLE_EXPOSURE_BIAS: f32 = 2.0;
+const HABLE_WHITE_POINT: f32 = 11.2;
+
+fn scale_luminance<F: Fn(f32) -> f32>(color: RgbColor, curve: F) -> RgbColor {
+    let luminance = color.luminance();
+
+    if luminance <= 0.0 {
+        return color;
+    }
+
+    color * (curve(luminance) / luminance)
+}
+
+fn map_channels<F: Fn(f32) -> f32>(color: RgbColor, curve: F) -> RgbColor {
+    RgbColor::new(
+        curve(f32::max(color.red, 0.0)),
+        curve(f32::max(color.green, 0.0)),
+        curve(f32::max(color.blue, 0.0)),
+    )
+}
+
+fn aces(value: f32) -> f32 {
+    let value = value * 0.6;
+
+    (value * (2.51 * value + 0.03) / (value * (2.43 * value + 0.59) + 0.14)).clamp(0.0, 1.0)
+}
+
+fn hable_partial(value: f32) -> f32 {
+    const A: f32 = 0.15;
+    const B: f32 = 0.50;
+    const C: f32 = 0.10;
+    const D: f32 = 0.20;
+    const E: f32 = 0.02;
+    const F: f32 = 0.30;
+
+    (value * (A * value + C * B) + D * E) / (value * (A * value + B) + D * F) - E / F
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::rgb_color::BLACK;
+
+    fn gray(value: f32) -> RgbColor {
+        RgbColor::new(value, value, value)
+    }
+
+    fn assert_gray(color: RgbColor, expected: f32) {
+        for &channel in [color.red, color.green, color.blue].iter() {
+            assert!((channel - expected).abs() < 1e-4, "{:?}", color);
+        }
+    }
+
+    #[test]
+    fn finds_every_tone_mapper_by_name() {
+        for name in ToneMapper::NAMES.iter() {
+            assert!(ToneMapper::from_name(name).is_some(), "{}", name);
+        }
+
+        assert_eq!(ToneMapper::from_name("filmic"), None);
+    }
+
+    #[test]
+    fn maps_known_values_with_reinhard() {
+        assert_eq!(ToneMapper::Reinhard.apply(BLACK), BLACK);
+        assert_gray(ToneMapper::Reinhard.apply(gray(1.0)), 0.5);
+
+        let bright = ToneMapper::Reinhard.apply(gray(1e4));
+        assert!(bright.red < 1.0 && bright.red > 0.999);
+
+        // The luminance is compressed, not the channels.
+        let color = ToneMapper::Reinhard.apply(RgbColor::new(2.0, 1.0, 0.5));
+        assert!((color.red - 2.0 * color.green).abs() < 1e-5);
+        assert!((color.green - 2.0 * color.blue).abs() < 1e-5);
+    }
+
+    #[test]
+    fn maps_the_white_point_to_white_with_extended_reinhard() {
+        let tone_mapper = ToneMapper::ExtendedReinhard { white_point: 4.0 };
+
+        assert_eq!(tone_mapper.apply(BLACK), BLACK);
+        assert_gray(tone_mapper.apply(gray(4.0)), 1.0);
+        assert_gray(tone_mapper.apply(gray(1.0)), 1.0625 / 2.0);
+    }
+
+    #[test]
+    fn maps_known_values_with_aces() {
+        assert_eq!(ToneMapper::Aces.apply(BLACK), BLACK);
+        assert_gray(ToneMapper::Aces.apply(gray(1.0)), 0.9216 / 1.3688);
+        assert_gray(ToneMapper::Aces.apply(gray(1e4)), 1.0);
+        assert_eq!(ToneMapper::Aces.apply(gray(-1.0)), BLACK);
+    }
+
+    #[test]
+    fn maps_the_white_point_to_white_with_hable() {
+        assert_gray(ToneMapper::Hable.apply(BLACK), 0.0);
+        assert_gray(
+            ToneMapper::Hable.apply(gray(HABLE_WHITE_POINT / HABLE_EXPOSURE_BIAS)),
+            1.0,
+        );
+    }
+
+    /// Up to the default white point, past which extended Reinhard goes
+    /// over white.
+    #[test]
+    fn keeps_curves_monotone() {
+        for name in ToneMapper::NAMES.iter() {
+            let tone_mapper = ToneMapper::from_name(name).unwrap();
+            let mut previous = -1.0;
+
+            for step in 0..80 {
+                let value = tone_mapper.apply(gray(step as f32 * 0.05)).green;
+
+                assert!(
+                    value > previous && value <= 1.0 + 1e-5,
+                    "{}: {}",
+                    name,
+                    step
+                );
+
+                previous = value;
+            }
+        }
+    }
+}
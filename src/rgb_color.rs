@@ -15,19 +15,37 @@ impl RgbColor {
         Self { red, green, blue }
     }
 
-    pub fn gamma_correct(&self, value: f32) -> Self {
-        Self::new(
-            f32::powf(self.red, 1.0 / value),
-            f32::powf(self.green, 1.0 / value),
-            f32::powf(self.blue, 1.0 / value),
-        )
+    /// Relative luminance of linear Rec. 709 primaries.
+    pub fn luminance(&self) -> f32 {
+        0.2126 * self.red + 0.7152 * self.green + 0.0722 * self.blue
+    }
+
+    pub fn max_component(&self) -> f32 {
+        self.red.max(self.green).max(self.blue)
     }
 
     pub fn to_raw(&self) -> RawRgbColor {
+        self.to_dithered_raw([0.0; 3])
+    }
+
+    /// Quantizes to 8 bits after adding the given offsets, measured in
+    /// quantization steps, to the channels. Channels out of the range
+    /// aren't offset, so that black and white stay exact.
+    pub fn to_dithered_raw(&self, offsets: [f32; 3]) -> RawRgbColor {
+        let quantize = |value: f32, offset: f32| {
+            if value <= 0.0 {
+                0
+            } else if value >= 1.0 {
+                255
+            } else {
+                f32::round(value * 255.0 + offset).clamp(0.0, 255.0) as u8
+            }
+        };
+
         [
-            f32::round(self.red.clamp(0.0, 0.999) * 256.0) as u8,
-            f32::round(self.green.clamp(0.0, 0.999) * 256.0) as u8,
-            f32::round(self.blue.clamp(0.0, 0.999) * 256.0) as u8,
+            quantize(self.red, offsets[0]),
+            quantize(self.green, offsets[1]),
+            quantize(self.blue, offsets[2]),
         ]
     }
 }
@@ -44,6 +62,12 @@ impl ops::Add for RgbColor {
     }
 }
 
+impl ops::AddAssign for RgbColor {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
 impl ops::Mul for RgbColor {
     type Output = Self;
 
@@ -56,6 +80,12 @@ impl ops::Mul for RgbColor {
     }
 }
 
+impl ops::MulAssign for RgbColor {
+    fn mul_assign(&mut self, rhs: Self) {
+        *self = *self * rhs;
+    }
+}
+
 impl ops::Mul<f32> for RgbColor {
     type Output = Self;
 
@@ -89,3 +119,52 @@ impl ops::Div<RgbColor> for f32 {
 }
 
 pub type RawRgbColor = [u8; 3];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rounds_to_the_nearest_step() {
+        assert_eq!(BLACK.to_raw(), [0, 0, 0]);
+        assert_eq!(WHITE.to_raw(), [255, 255, 255]);
+        assert_eq!(
+            RgbColor::new(0.5, 1.4 / 255.0, 1.6 / 255.0).to_raw(),
+            [128, 1, 2]
+        );
+        assert_eq!(RgbColor::new(-1.0, 2.0, f32::NAN).to_raw(), [0, 255, 0]);
+    }
+
+    #[test]
+    fn keeps_black_and_white_exact_when_dithering() {
+        for &offset in [-0.99, -0.5, 0.0, 0.5, 0.99].iter() {
+            let offsets = [offset; 3];
+
+            assert_eq!(BLACK.to_dithered_raw(offsets), [0, 0, 0]);
+            assert_eq!(WHITE.to_dithered_raw(offsets), [255, 255, 255]);
+            assert_eq!(
+                RgbColor::new(-0.1, 1.5, 0.0).to_dithered_raw(offsets),
+                [0, 255, 0]
+            );
+        }
+    }
+
+    #[test]
+    fn moves_by_at_most_one_step_when_dithering() {
+        let color = RgbColor::new(0.25, 0.5, 0.75);
+        let raw = color.to_raw();
+
+        for &offset in [-0.99, -0.5, 0.5, 0.99].iter() {
+            let dithered = color.to_dithered_raw([offset; 3]);
+
+            for channel in 0..3 {
+                assert!((dithered[channel] as i32 - raw[channel] as i32).abs() <= 1);
+            }
+        }
+
+        assert_eq!(
+            RgbColor::new(0.5, 0.5, 0.5).to_dithered_raw([-0.6, 0.6, 0.0]),
+            [127, 128, 128]
+        );
+    }
+}
@@ -0,0 +1,83 @@
+use rand::{Error, RngCore};
+
+const MULTIPLIER: u64 = 6_364_136_223_846_793_005;
+const DEFAULT_STREAM: u64 = 1_442_695_040_888_963_407;
+
+/// Small and fast PCG32 generator. Every sample of every pixel gets its own
+/// generator, seeded from its coordinates, so that renders are reproducible
+/// regardless of how the work is split between threads.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Rng {
+    state: u64,
+    increment: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64, stream: u64) -> Self {
+        let mut rng = Self {
+            state: 0,
+            increment: (stream << 1) | 1,
+        };
+
+        rng.next_u32();
+        rng.state = rng.state.wrapping_add(seed);
+        rng.next_u32();
+
+        rng
+    }
+
+    pub fn from_seed(seed: u64) -> Self {
+        Self::new(seed, DEFAULT_STREAM)
+    }
+
+    pub fn for_sample(seed: u64, x: u32, y: u32, sample: u32) -> Self {
+        let pixel = ((x as u64) << 32) | y as u64;
+
+        Self::new(mix(seed ^ mix(pixel)), mix(sample as u64))
+    }
+}
+
+impl RngCore for Rng {
+    fn next_u32(&mut self) -> u32 {
+        let state = self.state;
+
+        self.state = state.wrapping_mul(MULTIPLIER).wrapping_add(self.increment);
+
+        let xor_shifted = (((state >> 18) ^ state) >> 27) as u32;
+        let rotation = (state >> 59) as u32;
+
+        xor_shifted.rotate_right(rotation)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        ((self.next_u32() as u64) << 32) | self.next_u32() as u64
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(4) {
+            let bytes = self.next_u32().to_le_bytes();
+
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+
+        Ok(())
+    }
+}
+
+/// SplitMix64 finalizer, used to decorrelate nearby seeds.
+pub fn mix(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+
+    z ^ (z >> 31)
+}
+
+/// Hashes a few values into a seed.
+pub fn hash(values: &[u64]) -> u64 {
+    values.iter().fold(0, |hash, &value| mix(hash ^ value))
+}
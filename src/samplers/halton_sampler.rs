@@ -0,0 +1,127 @@
+use rand::Rng as _;
+
+use crate::{
+    rng::{hash, mix, Rng},
+    samplers::{permutation_element, Sampler, ONE_MINUS_EPSILON},
+};
+
+const PRIMES: [u32; 64] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+    101, 103, 107, 109, 113, 127, 131, 137, 139, 149, 151, 157, 163, 167, 173, 179, 181, 191, 193,
+    197, 199, 211, 223, 227, 229, 233, 239, 241, 251, 257, 263, 269, 271, 277, 281, 283, 293, 307,
+    311,
+];
+
+/// Halton sequence with one prime base per dimension, Owen scrambled per
+/// pixel. Dimensions past the last base fall back to uniform random numbers.
+#[derive(Clone, Debug)]
+pub struct HaltonSampler {
+    pub seed: u64,
+    pixel_seed: u64,
+    sample_index: u32,
+    dimension: usize,
+    rng: Rng,
+}
+
+impl HaltonSampler {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            pixel_seed: seed,
+            sample_index: 0,
+            dimension: 0,
+            rng: Rng::from_seed(seed),
+        }
+    }
+}
+
+impl Sampler for HaltonSampler {
+    fn start_pixel_sample(&mut self, x: u32, y: u32, sample_index: u32) {
+        self.pixel_seed = hash(&[self.seed, x as u64, y as u64]);
+        self.sample_index = sample_index;
+        self.dimension = 0;
+        self.rng = Rng::for_sample(self.seed, x, y, sample_index);
+    }
+
+    fn next_1d(&mut self) -> f32 {
+        let dimension = self.dimension;
+        self.dimension += 1;
+
+        match PRIMES.get(dimension) {
+            Some(&base) => scrambled_radical_inverse(
+                self.sample_index,
+                base,
+                hash(&[self.pixel_seed, dimension as u64]),
+            ),
+            None => self.rng.gen(),
+        }
+    }
+
+    fn next_2d(&mut self) -> (f32, f32) {
+        (self.next_1d(), self.next_1d())
+    }
+}
+
+/// Mirrors the digits of `index` in `base` around the radix point, permuting
+/// every digit depending on the ones before it.
+fn scrambled_radical_inverse(mut index: u32, base: u32, seed: u64) -> f32 {
+    let inverse_base = 1.0 / base as f32;
+    let mut digit_scale = 1.0;
+    let mut reversed_digits: u64 = 0;
+
+    // Leading zero digits are permuted as well, until they no longer change
+    // the result.
+    while 1.0 - digit_scale < 1.0 {
+        let digit = index % base;
+        index /= base;
+
+        let digit_seed = mix(seed ^ reversed_digits) as u32;
+        let permuted_digit = permutation_element(digit, base, digit_seed);
+
+        reversed_digits = reversed_digits * base as u64 + permuted_digit as u64;
+        digit_scale *= inverse_base;
+    }
+
+    f32::min(reversed_digits as f32 * digit_scale, ONE_MINUS_EPSILON)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pixel_samples(seed: u64) -> Vec<f32> {
+        let mut sampler = HaltonSampler::new(seed);
+        let mut samples = Vec::new();
+
+        for sample_index in 0..16 {
+            sampler.start_pixel_sample(5, 7, sample_index);
+            samples.extend((0..4).map(|_| sampler.next_1d()));
+        }
+
+        samples
+    }
+
+    #[test]
+    fn scrambles_deterministically() {
+        assert_eq!(pixel_samples(11), pixel_samples(11));
+        assert_ne!(pixel_samples(11), pixel_samples(12));
+    }
+
+    #[test]
+    fn keeps_the_stratification_of_the_sequence() {
+        // The first power of each base fills every interval of that width
+        // exactly once, whatever the scrambling.
+        for &(dimension, base) in [(0, 2), (1, 3)].iter() {
+            let count = base * base * base;
+            let mut intervals = vec![0; count as usize];
+
+            for index in 0..count {
+                let u = scrambled_radical_inverse(index, base, hash(&[42, dimension]));
+
+                intervals[(u * count as f32) as usize] += 1;
+            }
+
+            assert!(intervals.iter().all(|&hits| hits == 1), "{:?}", intervals);
+        }
+    }
+}
@@ -0,0 +1,52 @@
+use rand::Rng as _;
+
+use crate::{rng::Rng, samplers::Sampler};
+
+/// Uniform random numbers, without any stratification.
+#[derive(Clone, Debug)]
+pub struct IndependentSampler {
+    pub seed: u64,
+    rng: Rng,
+}
+
+impl IndependentSampler {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: Rng::from_seed(seed),
+        }
+    }
+}
+
+impl Sampler for IndependentSampler {
+    fn start_pixel_sample(&mut self, x: u32, y: u32, sample_index: u32) {
+        self.rng = Rng::for_sample(self.seed, x, y, sample_index);
+    }
+
+    fn next_1d(&mut self) -> f32 {
+        self.rng.gen()
+    }
+
+    fn next_2d(&mut self) -> (f32, f32) {
+        (self.rng.gen(), self.rng.gen())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn depends_only_on_the_pixel_sample() {
+        let mut sampler = IndependentSampler::new(9);
+
+        sampler.start_pixel_sample(1, 2, 3);
+        let first = (sampler.next_1d(), sampler.next_2d());
+
+        sampler.start_pixel_sample(4, 5, 6);
+        sampler.next_1d();
+
+        sampler.start_pixel_sample(1, 2, 3);
+        assert_eq!((sampler.next_1d(), sampler.next_2d()), first);
+    }
+}
@@ -0,0 +1,9 @@
+mod halton_sampler;
+mod independent_sampler;
+mod sampler;
+mod sobol_sampler;
+mod stratified_sampler;
+
+pub use self::{
+    halton_sampler::*, independent_sampler::*, sampler::*, sobol_sampler::*, stratified_sampler::*,
+};
@@ -0,0 +1,137 @@
+use crate::samplers::{HaltonSampler, IndependentSampler, SobolSampler, StratifiedSampler};
+
+/// Largest `f32` below one.
+pub const ONE_MINUS_EPSILON: f32 = 1.0 - f32::EPSILON / 2.0;
+
+/// Supplies the random numbers of pixel samples. Every call moves on to the
+/// next dimension of the sample, so a call site gets the same dimension of
+/// every sample of a pixel as long as the paths take the same branches.
+pub trait Sampler {
+    fn start_pixel_sample(&mut self, x: u32, y: u32, sample_index: u32);
+
+    fn next_1d(&mut self) -> f32;
+    fn next_2d(&mut self) -> (f32, f32);
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum SamplerKind {
+    Independent,
+    Stratified,
+    Halton,
+    Sobol,
+}
+
+impl SamplerKind {
+    pub const NAMES: [&'static str; 4] = ["independent", "stratified", "halton", "sobol"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "independent" => Some(Self::Independent),
+            "stratified" => Some(Self::Stratified),
+            "halton" => Some(Self::Halton),
+            "sobol" => Some(Self::Sobol),
+            _ => None,
+        }
+    }
+
+    pub fn build(self, seed: u64, samples_per_pixel: u32) -> Box<dyn Sampler> {
+        match self {
+            Self::Independent => Box::new(IndependentSampler::new(seed)),
+            Self::Stratified => Box::new(StratifiedSampler::new(seed, samples_per_pixel)),
+            Self::Halton => Box::new(HaltonSampler::new(seed)),
+            Self::Sobol => Box::new(SobolSampler::new(seed)),
+        }
+    }
+}
+
+/// Picks one of `count` items with a uniform sample, and returns it along
+/// with the sample remapped to `[0, 1)` so that it can be used again.
+pub fn sample_discrete(u: f32, count: usize) -> (usize, f32) {
+    let scaled = u * count as f32;
+    let index = usize::min(scaled as usize, count - 1);
+
+    (index, f32::min(scaled - index as f32, ONE_MINUS_EPSILON))
+}
+
+/// Maps the high bits of a random integer to `[0, 1)`.
+pub(crate) fn to_unit_float(bits: u32) -> f32 {
+    (bits >> 8) as f32 / (1 << 24) as f32
+}
+
+/// Element `index` of a pseudo-random permutation of `0..length`, see
+/// Kensler, "Correlated Multi-Jittered Sampling".
+pub(crate) fn permutation_element(index: u32, length: u32, seed: u32) -> u32 {
+    let mut mask = length - 1;
+    mask |= mask >> 1;
+    mask |= mask >> 2;
+    mask |= mask >> 4;
+    mask |= mask >> 8;
+    mask |= mask >> 16;
+
+    let mut i = index;
+
+    loop {
+        i ^= seed;
+        i = i.wrapping_mul(0xe170_893d);
+        i ^= seed >> 16;
+        i ^= (i & mask) >> 4;
+        i ^= seed >> 8;
+        i = i.wrapping_mul(0x0929_eb3f);
+        i ^= seed >> 23;
+        i ^= (i & mask) >> 1;
+        i = i.wrapping_mul(1 | seed >> 27);
+        i = i.wrapping_mul(0x6935_fa69);
+        i ^= (i & mask) >> 11;
+        i = i.wrapping_mul(0x74dc_b303);
+        i ^= (i & mask) >> 2;
+        i = i.wrapping_mul(0x9e50_1cc3);
+        i ^= (i & mask) >> 2;
+        i = i.wrapping_mul(0xc860_a3df);
+        i &= mask;
+        i ^= i >> 5;
+
+        // Values outside of the range are permuted again until they land
+        // inside it.
+        if i < length {
+            break;
+        }
+    }
+
+    (i + seed) % length
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn samples_lie_in_the_unit_interval() {
+        for name in SamplerKind::NAMES.iter() {
+            let mut sampler = SamplerKind::from_name(name).unwrap().build(7, 10);
+
+            for &(x, y) in [(0, 0), (3, 5), (1920, 1080)].iter() {
+                for sample_index in 0..64 {
+                    sampler.start_pixel_sample(x, y, sample_index);
+
+                    // Goes past the dimensions covered by the Halton bases.
+                    for _ in 0..40 {
+                        let (u1, u2) = sampler.next_2d();
+                        let u3 = sampler.next_1d();
+
+                        for &u in [u1, u2, u3].iter() {
+                            assert!((0.0..1.0).contains(&u), "{}: {}", name, u);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn picks_discrete_items_uniformly() {
+        assert_eq!(sample_discrete(0.0, 4), (0, 0.0));
+        assert_eq!(sample_discrete(0.625, 4), (2, 0.5));
+        assert_eq!(sample_discrete(ONE_MINUS_EPSILON, 4).0, 3);
+        assert!(sample_discrete(ONE_MINUS_EPSILON, 4).1 < 1.0);
+    }
+}
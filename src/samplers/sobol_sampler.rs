@@ -0,0 +1,132 @@
+use crate::{
+    rng::hash,
+    samplers::{to_unit_float, Sampler},
+};
+
+/// First two dimensions of the Sobol sequence, Owen scrambled and shuffled
+/// differently for every pixel and pair of dimensions, see Burley,
+/// "Practical Hash-based Owen Scrambling".
+#[derive(Clone, Debug)]
+pub struct SobolSampler {
+    pub seed: u64,
+    pixel_seed: u64,
+    sample_index: u32,
+    dimension: u64,
+}
+
+impl SobolSampler {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            pixel_seed: seed,
+            sample_index: 0,
+            dimension: 0,
+        }
+    }
+
+    fn dimension_seeds(&mut self) -> [u32; 3] {
+        let dimension_seed = hash(&[self.pixel_seed, self.dimension]);
+        self.dimension += 1;
+
+        [
+            dimension_seed as u32,
+            (dimension_seed >> 32) as u32,
+            hash(&[dimension_seed]) as u32,
+        ]
+    }
+}
+
+impl Sampler for SobolSampler {
+    fn start_pixel_sample(&mut self, x: u32, y: u32, sample_index: u32) {
+        self.pixel_seed = hash(&[self.seed, x as u64, y as u64]);
+        self.sample_index = sample_index;
+        self.dimension = 0;
+    }
+
+    fn next_1d(&mut self) -> f32 {
+        let [index_seed, x_seed, _] = self.dimension_seeds();
+        let index = nested_uniform_scramble(self.sample_index, index_seed);
+
+        to_unit_float(nested_uniform_scramble(index.reverse_bits(), x_seed))
+    }
+
+    fn next_2d(&mut self) -> (f32, f32) {
+        let [index_seed, x_seed, y_seed] = self.dimension_seeds();
+        let index = nested_uniform_scramble(self.sample_index, index_seed);
+
+        (
+            to_unit_float(nested_uniform_scramble(index.reverse_bits(), x_seed)),
+            to_unit_float(nested_uniform_scramble(
+                sobol_second_dimension(index),
+                y_seed,
+            )),
+        )
+    }
+}
+
+fn sobol_second_dimension(mut index: u32) -> u32 {
+    let mut result = 0;
+    let mut direction = 1 << 31;
+
+    while index != 0 {
+        if index & 1 != 0 {
+            result ^= direction;
+        }
+
+        index >>= 1;
+        direction ^= direction >> 1;
+    }
+
+    result
+}
+
+/// Owen scrambling in base 2. Applied to sample indices it shuffles them
+/// without breaking the stratification of power of two prefixes.
+fn nested_uniform_scramble(value: u32, seed: u32) -> u32 {
+    laine_karras_permutation(value.reverse_bits(), seed).reverse_bits()
+}
+
+fn laine_karras_permutation(mut x: u32, seed: u32) -> u32 {
+    x ^= x.wrapping_mul(0x3d20_adea);
+    x = x.wrapping_add(seed);
+    x = x.wrapping_mul((seed >> 16) | 1);
+    x ^= x.wrapping_mul(0x0552_6c56);
+    x ^= x.wrapping_mul(0x53a2_2864);
+
+    x
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pixel_samples(seed: u64) -> Vec<(f32, f32)> {
+        let mut sampler = SobolSampler::new(seed);
+
+        (0..16)
+            .map(|sample_index| {
+                sampler.start_pixel_sample(5, 7, sample_index);
+                sampler.next_1d();
+
+                sampler.next_2d()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn scrambles_deterministically() {
+        assert_eq!(pixel_samples(11), pixel_samples(11));
+        assert_ne!(pixel_samples(11), pixel_samples(12));
+    }
+
+    #[test]
+    fn fills_every_cell_of_a_power_of_two_grid() {
+        let mut cells = [0; 16];
+
+        for (u1, u2) in pixel_samples(11) {
+            cells[(u2 * 4.0) as usize * 4 + (u1 * 4.0) as usize] += 1;
+        }
+
+        assert!(cells.iter().all(|&hits| hits == 1), "{:?}", cells);
+    }
+}
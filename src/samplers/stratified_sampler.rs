@@ -0,0 +1,125 @@
+use rand::Rng as _;
+
+use crate::{
+    rng::{hash, Rng},
+    samplers::{permutation_element, Sampler, ONE_MINUS_EPSILON},
+};
+
+/// Splits every dimension in as many strata as samples per pixel, and
+/// jitters each sample inside its own stratum. Two-dimensional samples use
+/// a grid of strata as square as possible.
+#[derive(Clone, Debug)]
+pub struct StratifiedSampler {
+    pub seed: u64,
+    pub samples_per_pixel: u32,
+    pixel_seed: u64,
+    sample_index: u32,
+    dimension: u64,
+    rng: Rng,
+}
+
+impl StratifiedSampler {
+    pub fn new(seed: u64, samples_per_pixel: u32) -> Self {
+        Self {
+            seed,
+            samples_per_pixel: u32::max(samples_per_pixel, 1),
+            pixel_seed: seed,
+            sample_index: 0,
+            dimension: 0,
+            rng: Rng::from_seed(seed),
+        }
+    }
+
+    /// Stratum of the current sample among `strata_count` strata. Every
+    /// dimension shuffles the strata differently so that they aren't
+    /// correlated with each other.
+    fn stratum(&mut self, strata_count: u32) -> u32 {
+        let dimension_seed = hash(&[self.pixel_seed, self.dimension]) as u32;
+        self.dimension += 1;
+
+        permutation_element(
+            self.sample_index % strata_count,
+            strata_count,
+            dimension_seed,
+        )
+    }
+
+    fn jitter(&mut self, stratum: u32, strata_count: u32) -> f32 {
+        f32::min(
+            (stratum as f32 + self.rng.gen::<f32>()) / strata_count as f32,
+            ONE_MINUS_EPSILON,
+        )
+    }
+}
+
+impl Sampler for StratifiedSampler {
+    fn start_pixel_sample(&mut self, x: u32, y: u32, sample_index: u32) {
+        self.pixel_seed = hash(&[self.seed, x as u64, y as u64]);
+        self.sample_index = sample_index;
+        self.dimension = 0;
+        self.rng = Rng::for_sample(self.seed, x, y, sample_index);
+    }
+
+    fn next_1d(&mut self) -> f32 {
+        let stratum = self.stratum(self.samples_per_pixel);
+
+        self.jitter(stratum, self.samples_per_pixel)
+    }
+
+    fn next_2d(&mut self) -> (f32, f32) {
+        // When the samples don't fill a square grid some strata stay empty,
+        // but no stratum is ever used twice.
+        let columns = f32::ceil(f32::sqrt(self.samples_per_pixel as f32)) as u32;
+        let rows = self.samples_per_pixel.div_ceil(columns);
+
+        let stratum = self.stratum(columns * rows);
+
+        (
+            self.jitter(stratum % columns, columns),
+            self.jitter(stratum / columns, rows),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Strata hit by the first 1D and 2D samples of every sample of a pixel.
+    fn strata(samples_per_pixel: u32, columns: u32, rows: u32) -> (Vec<u32>, Vec<u32>) {
+        let mut sampler = StratifiedSampler::new(3, samples_per_pixel);
+        let mut strata_1d = vec![0; samples_per_pixel as usize];
+        let mut strata_2d = vec![0; (columns * rows) as usize];
+
+        for sample_index in 0..samples_per_pixel {
+            sampler.start_pixel_sample(12, 34, sample_index);
+
+            let u = sampler.next_1d();
+            let (u1, u2) = sampler.next_2d();
+
+            strata_1d[(u * samples_per_pixel as f32) as usize] += 1;
+            strata_2d[(u2 * rows as f32) as usize * columns as usize
+                + (u1 * columns as f32) as usize] += 1;
+        }
+
+        (strata_1d, strata_2d)
+    }
+
+    #[test]
+    fn hits_every_stratum_once_per_pixel() {
+        let (strata_1d, strata_2d) = strata(16, 4, 4);
+
+        assert!(strata_1d.iter().all(|&count| count == 1));
+        assert!(strata_2d.iter().all(|&count| count == 1));
+    }
+
+    #[test]
+    fn never_hits_a_stratum_twice_in_an_incomplete_grid() {
+        // Ten samples use a grid of four columns and three rows.
+        let (strata_1d, strata_2d) = strata(10, 4, 3);
+
+        assert!(strata_1d.iter().all(|&count| count == 1));
+        assert!(strata_2d.iter().all(|&count| count <= 1));
+        assert_eq!(strata_2d.iter().sum::<u32>(), 10);
+    }
+}
@@ -1,28 +1,72 @@
 use std::sync::Arc;
 
-use crate::{objects::Object, ray::Ray, rgb_color::RgbColor};
+use crate::{
+    backgrounds::Background,
+    lights::DeltaLight,
+    objects::{Object, ObjectList},
+    pdfs::{BackgroundPdf, EmissivePdf, MixturePdf, ScatteringPdf},
+    ray::Ray,
+    rgb_color::RgbColor,
+    vec3::Point3,
+};
 
 #[derive(Clone)]
 pub struct Scene {
-    background: fn(&Ray) -> RgbColor,
+    pub background: Arc<dyn Background>,
     pub objects: Arc<dyn Object>,
-    pub lights: Arc<dyn Object>,
+    pub lights: Arc<ObjectList>,
+    /// Point, spot and directional lights, which aren't objects.
+    pub delta_lights: Vec<Arc<dyn DeltaLight>>,
 }
 
 impl Scene {
     pub fn new(
         objects: Arc<dyn Object>,
-        lights: Arc<dyn Object>,
-        background: fn(&Ray) -> RgbColor,
+        lights: Arc<ObjectList>,
+        background: Arc<dyn Background>,
     ) -> Self {
         Self {
             objects,
             lights,
+            delta_lights: Vec::new(),
             background,
         }
     }
 
+    pub fn with_delta_lights(self, delta_lights: Vec<Arc<dyn DeltaLight>>) -> Self {
+        Self {
+            delta_lights,
+            ..self
+        }
+    }
+
     pub fn background(&self, ray: &Ray) -> RgbColor {
-        (self.background)(ray)
+        self.background.radiance(ray)
+    }
+
+    /// Distribution of directions towards the lights of the scene, seen from
+    /// the given point, if there is anything to sample.
+    pub fn light_pdf(&self, origin: Point3, time: f32) -> Option<Arc<dyn ScatteringPdf>> {
+        let objects_pdf = if self.lights.objects.is_empty() {
+            None
+        } else {
+            let lights: Arc<dyn Object> = self.lights.clone();
+
+            Some(Arc::new(EmissivePdf::new(lights, origin, time)) as Arc<dyn ScatteringPdf>)
+        };
+
+        let background_pdf = if self.background.is_sampled() {
+            Some(Arc::new(BackgroundPdf::new(Arc::clone(&self.background)))
+                as Arc<dyn ScatteringPdf>)
+        } else {
+            None
+        };
+
+        match (objects_pdf, background_pdf) {
+            (Some(objects_pdf), Some(background_pdf)) => {
+                Some(Arc::new(MixturePdf::new(0.5, objects_pdf, background_pdf)))
+            }
+            (objects_pdf, background_pdf) => objects_pdf.or(background_pdf),
+        }
     }
 }
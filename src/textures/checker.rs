@@ -0,0 +1,62 @@
+use std::sync::Arc;
+
+use crate::{rgb_color::RgbColor, textures::Texture, vec3::Point3};
+
+/// Alternates between two textures in squares of the texture coordinates.
+pub struct UvChecker {
+    pub even: Arc<dyn Texture>,
+    pub odd: Arc<dyn Texture>,
+    /// Squares along each texture coordinate.
+    pub frequency: f32,
+}
+
+impl UvChecker {
+    pub fn new(even: Arc<dyn Texture>, odd: Arc<dyn Texture>, frequency: f32) -> Self {
+        Self {
+            even,
+            odd,
+            frequency,
+        }
+    }
+}
+
+impl Texture for UvChecker {
+    fn value(&self, u: f32, v: f32, point: Point3) -> RgbColor {
+        let cell = f32::floor(u * self.frequency) + f32::floor(v * self.frequency);
+
+        if cell.rem_euclid(2.0) == 0.0 {
+            self.even.value(u, v, point)
+        } else {
+            self.odd.value(u, v, point)
+        }
+    }
+}
+
+/// Alternates between two textures in cubes of space, so it doesn't depend
+/// on how surfaces are parameterized.
+pub struct SolidChecker {
+    pub even: Arc<dyn Texture>,
+    pub odd: Arc<dyn Texture>,
+    /// Length of the side of the cubes.
+    pub size: f32,
+}
+
+impl SolidChecker {
+    pub fn new(even: Arc<dyn Texture>, odd: Arc<dyn Texture>, size: f32) -> Self {
+        Self { even, odd, size }
+    }
+}
+
+impl Texture for SolidChecker {
+    fn value(&self, u: f32, v: f32, point: Point3) -> RgbColor {
+        let cell = f32::floor(point.x / self.size)
+            + f32::floor(point.y / self.size)
+            + f32::floor(point.z / self.size);
+
+        if cell.rem_euclid(2.0) == 0.0 {
+            self.even.value(u, v, point)
+        } else {
+            self.odd.value(u, v, point)
+        }
+    }
+}
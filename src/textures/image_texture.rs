@@ -0,0 +1,253 @@
+use std::path::Path;
+
+use crate::{
+    color_space::TransferFunction,
+    rgb_color::{RgbColor, BLACK},
+    textures::Texture,
+    vec3::Point3,
+};
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum TextureFilter {
+    Nearest,
+    Bilinear,
+}
+
+impl TextureFilter {
+    pub const NAMES: [&'static str; 2] = ["nearest", "bilinear"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "nearest" => Some(Self::Nearest),
+            "bilinear" => Some(Self::Bilinear),
+            _ => None,
+        }
+    }
+}
+
+/// How texture coordinates outside of `[0, 1]` are mapped to the image.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum WrapMode {
+    Repeat,
+    Clamp,
+    Mirror,
+}
+
+impl WrapMode {
+    pub const NAMES: [&'static str; 3] = ["repeat", "clamp", "mirror"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "repeat" => Some(Self::Repeat),
+            "clamp" => Some(Self::Clamp),
+            "mirror" => Some(Self::Mirror),
+            _ => None,
+        }
+    }
+
+    fn wrap(&self, index: i64, size: u32) -> u32 {
+        let size = size as i64;
+
+        let wrapped = match self {
+            Self::Repeat => index.rem_euclid(size),
+            Self::Clamp => index.clamp(0, size - 1),
+            Self::Mirror => {
+                let index = index.rem_euclid(2 * size);
+
+                if index < size {
+                    index
+                } else {
+                    2 * size - 1 - index
+                }
+            }
+        };
+
+        wrapped as u32
+    }
+}
+
+/// Texture backed by an image with linear colors, stored from the top row
+/// down.
+pub struct ImageTexture {
+    pixels: Vec<RgbColor>,
+    width: u32,
+    height: u32,
+    pub filter: TextureFilter,
+    pub wrap_mode: WrapMode,
+}
+
+impl ImageTexture {
+    pub fn new(pixels: Vec<RgbColor>, width: u32, height: u32) -> Self {
+        Self {
+            pixels,
+            width,
+            height,
+            filter: TextureFilter::Bilinear,
+            wrap_mode: WrapMode::Repeat,
+        }
+    }
+
+    /// Opens an image with sRGB encoded colors, like most photographs and
+    /// painted color maps.
+    pub fn open<P: AsRef<Path>>(path: P) -> image::ImageResult<Self> {
+        Self::open_with_transfer_function(path, TransferFunction::Srgb)
+    }
+
+    pub fn open_with_transfer_function<P: AsRef<Path>>(
+        path: P,
+        transfer_function: TransferFunction,
+    ) -> image::ImageResult<Self> {
+        let image = image::open(path)?.to_rgb16();
+
+        let pixels = image
+            .pixels()
+            .map(|pixel| {
+                transfer_function.decode_color(RgbColor::new(
+                    pixel[0] as f32 / u16::MAX as f32,
+                    pixel[1] as f32 / u16::MAX as f32,
+                    pixel[2] as f32 / u16::MAX as f32,
+                ))
+            })
+            .collect();
+
+        Ok(Self::new(pixels, image.width(), image.height()))
+    }
+
+    pub fn with_filter(self, filter: TextureFilter) -> Self {
+        Self { filter, ..self }
+    }
+
+    pub fn with_wrap_mode(self, wrap_mode: WrapMode) -> Self {
+        Self { wrap_mode, ..self }
+    }
+
+    fn texel(&self, x: i64, y: i64) -> RgbColor {
+        let x = self.wrap_mode.wrap(x, self.width);
+        let y = self.wrap_mode.wrap(y, self.height);
+
+        self.pixels[(y * self.width + x) as usize]
+    }
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, u: f32, v: f32, _point: Point3) -> RgbColor {
+        if self.pixels.is_empty() {
+            return BLACK;
+        }
+
+        // Images are stored top to bottom, while v grows upwards.
+        let x = u * self.width as f32;
+        let y = (1.0 - v) * self.height as f32;
+
+        match self.filter {
+            TextureFilter::Nearest => self.texel(x.floor() as i64, y.floor() as i64),
+            TextureFilter::Bilinear => {
+                // Texel centers are half a texel away from their corners.
+                let x = x - 0.5;
+                let y = y - 0.5;
+
+                let x0 = x.floor();
+                let y0 = y.floor();
+                let dx = x - x0;
+                let dy = y - y0;
+
+                let (x0, y0) = (x0 as i64, y0 as i64);
+
+                (1.0 - dx) * (1.0 - dy) * self.texel(x0, y0)
+                    + dx * (1.0 - dy) * self.texel(x0 + 1, y0)
+                    + (1.0 - dx) * dy * self.texel(x0, y0 + 1)
+                    + dx * dy * self.texel(x0 + 1, y0 + 1)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gray(value: f32) -> RgbColor {
+        RgbColor::new(value, value, value)
+    }
+
+    /// Row of four texels, from 0 to 3.
+    fn row_texture(filter: TextureFilter, wrap_mode: WrapMode) -> ImageTexture {
+        ImageTexture::new((0..4).map(|index| gray(index as f32)).collect(), 4, 1)
+            .with_filter(filter)
+            .with_wrap_mode(wrap_mode)
+    }
+
+    fn value_at(texture: &ImageTexture, u: f32) -> f32 {
+        texture.value(u, 0.5, Point3::zero()).green
+    }
+
+    #[test]
+    fn wraps_coordinates_outside_of_the_image() {
+        let cases = [
+            (WrapMode::Repeat, [3.0, 2.0, 0.0, 1.0]),
+            (WrapMode::Clamp, [0.0, 0.0, 3.0, 3.0]),
+            (WrapMode::Mirror, [0.0, 1.0, 3.0, 2.0]),
+        ];
+
+        for &(wrap_mode, expected) in cases.iter() {
+            let texture = row_texture(TextureFilter::Nearest, wrap_mode);
+
+            for (&u, &expected) in [-0.1, -0.3, 1.1, 1.3].iter().zip(expected.iter()) {
+                assert_eq!(value_at(&texture, u), expected, "{:?}: {}", wrap_mode, u);
+            }
+
+            // Inside of the image, every mode agrees.
+            assert_eq!(value_at(&texture, 0.3), 1.0);
+        }
+    }
+
+    #[test]
+    fn mirrors_every_other_repetition() {
+        let wrapped: Vec<_> = (-8..8)
+            .map(|index| WrapMode::Mirror.wrap(index, 4))
+            .collect();
+
+        assert_eq!(
+            wrapped,
+            vec![0, 1, 2, 3, 3, 2, 1, 0, 0, 1, 2, 3, 3, 2, 1, 0]
+        );
+    }
+
+    #[test]
+    fn interpolates_between_texel_centers() {
+        let texture = row_texture(TextureFilter::Bilinear, WrapMode::Clamp);
+
+        for index in 0..4 {
+            let u = (index as f32 + 0.5) / 4.0;
+
+            assert!((value_at(&texture, u) - index as f32).abs() < 1e-5);
+        }
+
+        assert!((value_at(&texture, 0.5) - 1.5).abs() < 1e-5);
+        assert!((value_at(&texture, 0.3125) - 0.75).abs() < 1e-5);
+
+        // Clamped edges keep the color of the last texel.
+        assert!((value_at(&texture, 0.0) - 0.0).abs() < 1e-5);
+        assert!((value_at(&texture, 1.0) - 3.0).abs() < 1e-5);
+
+        // Repeated edges blend with the other side of the image.
+        let texture = row_texture(TextureFilter::Bilinear, WrapMode::Repeat);
+        assert!((value_at(&texture, 0.0) - 1.5).abs() < 1e-5);
+    }
+
+    #[test]
+    fn stores_rows_from_the_top_down() {
+        let texture =
+            ImageTexture::new(vec![gray(1.0), gray(0.0)], 1, 2).with_filter(TextureFilter::Nearest);
+
+        assert_eq!(texture.value(0.5, 0.9, Point3::zero()), gray(1.0));
+        assert_eq!(texture.value(0.5, 0.1, Point3::zero()), gray(0.0));
+    }
+
+    #[test]
+    fn is_black_without_pixels() {
+        let texture = ImageTexture::new(Vec::new(), 0, 0);
+
+        assert_eq!(texture.value(0.5, 0.5, Point3::zero()), BLACK);
+    }
+}
@@ -1,4 +1,8 @@
+mod checker;
+mod image_texture;
+mod noise;
+mod perlin;
 mod solid_color;
 mod texture;
 
-pub use self::{solid_color::*, texture::*};
+pub use self::{checker::*, image_texture::*, noise::*, perlin::*, solid_color::*, texture::*};
@@ -0,0 +1,163 @@
+use crate::{
+    rgb_color::RgbColor,
+    textures::{Perlin, Texture},
+    vec3::Point3,
+};
+
+pub const DEFAULT_TURBULENCE_DEPTH: u32 = 7;
+
+/// Turbulent Perlin noise, scaling a color.
+pub struct Noise {
+    pub perlin: Perlin,
+    pub color: RgbColor,
+    /// Frequency of the noise in space.
+    pub scale: f32,
+    pub turbulence_depth: u32,
+}
+
+impl Noise {
+    pub fn new(seed: u64, color: RgbColor, scale: f32) -> Self {
+        Self {
+            perlin: Perlin::new(seed),
+            color,
+            scale,
+            turbulence_depth: DEFAULT_TURBULENCE_DEPTH,
+        }
+    }
+}
+
+impl Texture for Noise {
+    fn value(&self, _u: f32, _v: f32, point: Point3) -> RgbColor {
+        self.color
+            * self
+                .perlin
+                .turbulence(point * self.scale, self.turbulence_depth)
+                .min(1.0)
+    }
+}
+
+/// Veins of one color through another, bent by turbulence.
+pub struct Marble {
+    pub perlin: Perlin,
+    pub base: RgbColor,
+    pub vein: RgbColor,
+    /// Frequency of the veins along the z axis.
+    pub scale: f32,
+    pub turbulence_depth: u32,
+    /// How much the turbulence bends the veins.
+    pub distortion: f32,
+}
+
+impl Marble {
+    pub fn new(seed: u64, base: RgbColor, vein: RgbColor, scale: f32) -> Self {
+        Self {
+            perlin: Perlin::new(seed),
+            base,
+            vein,
+            scale,
+            turbulence_depth: DEFAULT_TURBULENCE_DEPTH,
+            distortion: 10.0,
+        }
+    }
+}
+
+impl Texture for Marble {
+    fn value(&self, _u: f32, _v: f32, point: Point3) -> RgbColor {
+        let point = point * self.scale;
+        let phase =
+            point.z + self.distortion * self.perlin.turbulence(point, self.turbulence_depth);
+
+        // The veins are the troughs of the sine.
+        let t = 0.5 * (1.0 + f32::sin(phase));
+
+        t * self.base + (1.0 - t) * self.vein
+    }
+}
+
+/// Rings of two colors around the y axis, made irregular by turbulence.
+pub struct Wood {
+    pub perlin: Perlin,
+    pub light: RgbColor,
+    pub dark: RgbColor,
+    /// Rings per unit of distance from the axis.
+    pub ring_frequency: f32,
+    pub turbulence_depth: u32,
+    pub distortion: f32,
+}
+
+impl Wood {
+    pub fn new(seed: u64, light: RgbColor, dark: RgbColor, ring_frequency: f32) -> Self {
+        Self {
+            perlin: Perlin::new(seed),
+            light,
+            dark,
+            ring_frequency,
+            turbulence_depth: DEFAULT_TURBULENCE_DEPTH,
+            distortion: 0.05,
+        }
+    }
+}
+
+impl Texture for Wood {
+    fn value(&self, _u: f32, _v: f32, point: Point3) -> RgbColor {
+        let radius = f32::sqrt(point.x.powi(2) + point.z.powi(2));
+        let rings = (radius
+            + self.distortion * self.perlin.turbulence(point, self.turbulence_depth))
+            * self.ring_frequency;
+
+        // Sharper dark edges at the end of every ring.
+        let t = (rings - rings.floor()).powi(3);
+
+        (1.0 - t) * self.light + t * self.dark
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::rgb_color::BLACK;
+
+    fn points() -> impl Iterator<Item = Point3> {
+        (0..1000).map(|index| {
+            let index = index as f32;
+
+            Point3::new(index * 0.37, index * -0.11, index * 0.73)
+        })
+    }
+
+    /// Whether `color` lies on the segment between `from` and `to`.
+    fn is_between(color: RgbColor, from: RgbColor, to: RgbColor) -> bool {
+        let t = (color.red - from.red) / (to.red - from.red);
+        let expected = (1.0 - t) * from + t * to;
+
+        (-1e-5..=1.0 + 1e-5).contains(&t)
+            && (color.green - expected.green).abs() < 1e-5
+            && (color.blue - expected.blue).abs() < 1e-5
+    }
+
+    #[test]
+    fn scales_the_color_of_noise() {
+        let color = RgbColor::new(0.8, 0.4, 0.2);
+        let noise = Noise::new(1, color, 4.0);
+
+        for point in points() {
+            let value = noise.value(0.0, 0.0, point);
+
+            assert!(is_between(value, BLACK, color), "{:?}", value);
+            assert_eq!(value, Noise::new(1, color, 4.0).value(0.5, 0.5, point));
+        }
+    }
+
+    #[test]
+    fn blends_the_colors_of_marble_and_wood() {
+        let (light, dark) = (RgbColor::new(0.9, 0.8, 0.7), RgbColor::new(0.3, 0.1, 0.0));
+        let marble = Marble::new(2, light, dark, 3.0);
+        let wood = Wood::new(3, light, dark, 8.0);
+
+        for point in points() {
+            assert!(is_between(marble.value(0.0, 0.0, point), light, dark));
+            assert!(is_between(wood.value(0.0, 0.0, point), light, dark));
+        }
+    }
+}
@@ -0,0 +1,200 @@
+use rand::seq::SliceRandom;
+
+use crate::{rng::Rng, vec3::Point3};
+
+const PERMUTATION_SIZE: usize = 256;
+
+/// Perlin's improved gradient noise, with the lattice shuffled from a seed.
+#[derive(Clone, Debug)]
+pub struct Perlin {
+    permutation: Vec<u8>,
+}
+
+impl Perlin {
+    pub fn new(seed: u64) -> Self {
+        let mut permutation: Vec<_> = (0..PERMUTATION_SIZE).map(|index| index as u8).collect();
+        permutation.shuffle(&mut Rng::from_seed(seed));
+
+        // Repeating the permutation saves wrapping the indices of the
+        // corners of the last cells.
+        let repeated = permutation.clone();
+        permutation.extend(repeated);
+
+        Self { permutation }
+    }
+
+    /// Smooth noise roughly in `[-1, 1]`.
+    pub fn noise(&self, point: Point3) -> f32 {
+        let cell_x = point.x.floor();
+        let cell_y = point.y.floor();
+        let cell_z = point.z.floor();
+
+        let x = point.x - cell_x;
+        let y = point.y - cell_y;
+        let z = point.z - cell_z;
+
+        let wrap =
+            |coordinate: f32| (coordinate as i64).rem_euclid(PERMUTATION_SIZE as i64) as usize;
+        let (cell_x, cell_y, cell_z) = (wrap(cell_x), wrap(cell_y), wrap(cell_z));
+
+        let hash = |dx: usize, dy: usize, dz: usize| {
+            let p = &self.permutation;
+
+            p[p[p[cell_x + dx] as usize + cell_y + dy] as usize + cell_z + dz]
+        };
+
+        let u = fade(x);
+        let v = fade(y);
+        let w = fade(z);
+
+        lerp(
+            w,
+            lerp(
+                v,
+                lerp(
+                    u,
+                    gradient(hash(0, 0, 0), x, y, z),
+                    gradient(hash(1, 0, 0), x - 1.0, y, z),
+                ),
+                lerp(
+                    u,
+                    gradient(hash(0, 1, 0), x, y - 1.0, z),
+                    gradient(hash(1, 1, 0), x - 1.0, y - 1.0, z),
+                ),
+            ),
+            lerp(
+                v,
+                lerp(
+                    u,
+                    gradient(hash(0, 0, 1), x, y, z - 1.0),
+                    gradient(hash(1, 0, 1), x - 1.0, y, z - 1.0),
+                ),
+                lerp(
+                    u,
+                    gradient(hash(0, 1, 1), x, y - 1.0, z - 1.0),
+                    gradient(hash(1, 1, 1), x - 1.0, y - 1.0, z - 1.0),
+                ),
+            ),
+        )
+    }
+
+    /// Sum of the absolute noise of `depth` octaves, each with double the
+    /// frequency and half the amplitude of the previous one.
+    pub fn turbulence(&self, mut point: Point3, depth: u32) -> f32 {
+        let mut sum = 0.0;
+        let mut weight = 1.0;
+
+        for _ in 0..depth {
+            sum += weight * self.noise(point).abs();
+
+            point *= 2.0;
+            weight *= 0.5;
+        }
+
+        sum
+    }
+}
+
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(t: f32, a: f32, b: f32) -> f32 {
+    a + t * (b - a)
+}
+
+/// Dot product with one of twelve gradients pointing to the edges of the
+/// cell, picked by the hash.
+fn gradient(hash: u8, x: f32, y: f32, z: f32) -> f32 {
+    let hash = hash & 15;
+
+    let u = if hash < 8 { x } else { y };
+    let v = match hash {
+        0..=3 => y,
+        12 | 14 => x,
+        _ => z,
+    };
+
+    (if hash & 1 == 0 { u } else { -u }) + (if hash & 2 == 0 { v } else { -v })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::Rng as _;
+
+    use crate::vec3::Vec3;
+
+    fn random_points(count: usize) -> Vec<Point3> {
+        let mut rng = Rng::from_seed(7);
+
+        (0..count)
+            .map(|_| {
+                Point3::new(
+                    rng.gen_range(-300.0..300.0),
+                    rng.gen_range(-300.0..300.0),
+                    rng.gen_range(-300.0..300.0),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn depends_only_on_the_seed() {
+        let points = random_points(100);
+        let noise = |seed| -> Vec<_> {
+            let perlin = Perlin::new(seed);
+
+            points.iter().map(|&point| perlin.noise(point)).collect()
+        };
+
+        assert_eq!(noise(1), noise(1));
+        assert_ne!(noise(1), noise(2));
+    }
+
+    #[test]
+    fn stays_in_the_unit_range() {
+        let perlin = Perlin::new(3);
+        let values: Vec<_> = random_points(10_000)
+            .into_iter()
+            .map(|point| perlin.noise(point))
+            .collect();
+
+        assert!(values.iter().all(|value| value.abs() <= 1.0));
+
+        let min = values.iter().cloned().fold(f32::INFINITY, f32::min);
+        let max = values.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+        assert!(min < -0.5 && max > 0.5, "{} to {}", min, max);
+
+        let mean = values.iter().sum::<f32>() / values.len() as f32;
+        assert!(mean.abs() < 0.02, "{}", mean);
+    }
+
+    #[test]
+    fn vanishes_on_the_lattice_and_varies_smoothly() {
+        let perlin = Perlin::new(5);
+
+        for point in random_points(100) {
+            let corner = Point3::new(point.x.floor(), point.y.floor(), point.z.floor());
+            assert_eq!(perlin.noise(corner), 0.0);
+
+            let step = Vec3::new(1e-3, -1e-3, 1e-3);
+            assert!((perlin.noise(point + step) - perlin.noise(point)).abs() < 1e-2);
+        }
+    }
+
+    #[test]
+    fn adds_up_the_octaves_of_turbulence() {
+        let perlin = Perlin::new(11);
+
+        for point in random_points(100) {
+            assert_eq!(perlin.turbulence(point, 0), 0.0);
+            assert_eq!(perlin.turbulence(point, 1), perlin.noise(point).abs());
+
+            let turbulence = perlin.turbulence(point, 7);
+            assert!((0.0..2.0).contains(&turbulence));
+            assert!(turbulence >= perlin.turbulence(point, 6));
+        }
+    }
+}
@@ -12,6 +12,10 @@ impl SolidColor {
     pub const fn from_rgb_color(color: RgbColor) -> Self {
         Self { color }
     }
+
+    pub const fn gray(value: f32) -> Self {
+        Self::new(value, value, value)
+    }
 }
 
 impl Texture for SolidColor {
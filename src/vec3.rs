@@ -1,8 +1,6 @@
 use std::f32::consts::PI;
 use std::ops;
 
-use crate::helpers::random;
-
 #[derive(Copy, Clone, PartialEq, Debug)]
 pub struct Vec3 {
     pub x: f32,
@@ -55,12 +53,25 @@ impl Vec3 {
         self / self.len()
     }
 
-    pub fn random_in_sphere(radius: f32) -> Self {
-        random(0.0..=radius) * Self::from_polar(random(0.0..(2.0 * PI)), random(0.0..(2.0 * PI)))
+    pub fn min(self, other: Self) -> Self {
+        Self::new(
+            f32::min(self.x, other.x),
+            f32::min(self.y, other.y),
+            f32::min(self.z, other.z),
+        )
+    }
+
+    pub fn max(self, other: Self) -> Self {
+        Self::new(
+            f32::max(self.x, other.x),
+            f32::max(self.y, other.y),
+            f32::max(self.z, other.z),
+        )
     }
 
-    pub fn random() -> Self {
-        Self::new(random(0.0..=1.0), random(0.0..=1.0), random(0.0..=1.0))
+    /// Uniformly distributed direction.
+    pub fn random_unit((u1, u2): (f32, f32)) -> Self {
+        Self::from_polar(u1 * 2.0 * PI, f32::acos(1.0 - 2.0 * u2))
     }
 
     pub fn reflect(&self, normal: Self) -> Self {
@@ -98,7 +109,7 @@ impl ops::Add for Vec3 {
 }
 
 impl ops::AddAssign for Vec3 {
-    fn add_assign(&mut self, rhs: Self) -> () {
+    fn add_assign(&mut self, rhs: Self) {
         *self = *self + rhs;
     }
 }
@@ -112,7 +123,7 @@ impl ops::Sub for Vec3 {
 }
 
 impl ops::SubAssign for Vec3 {
-    fn sub_assign(&mut self, rhs: Self) -> () {
+    fn sub_assign(&mut self, rhs: Self) {
         *self += -rhs;
     }
 }
@@ -142,7 +153,7 @@ impl ops::Mul<Vec3> for f32 {
 }
 
 impl ops::MulAssign<f32> for Vec3 {
-    fn mul_assign(&mut self, rhs: f32) -> () {
+    fn mul_assign(&mut self, rhs: f32) {
         *self = *self * rhs;
     }
 }
@@ -164,7 +175,7 @@ impl ops::Div<Vec3> for f32 {
 }
 
 impl ops::DivAssign<f32> for Vec3 {
-    fn div_assign(&mut self, rhs: f32) -> () {
+    fn div_assign(&mut self, rhs: f32) {
         *self = *self / rhs;
     }
 }
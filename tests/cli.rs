@@ -0,0 +1,41 @@
+use std::process::{Command, Output};
+
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_tracer"))
+        .args(args)
+        .output()
+        .expect("the binary runs")
+}
+
+#[test]
+fn help_exits_successfully() {
+    let output = run(&["--help"]);
+
+    assert_eq!(output.status.code(), Some(0));
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with("Usage: tracer"));
+}
+
+#[test]
+fn usage_errors_exit_with_two() {
+    for args in [
+        &["--samples", "0", "-o", "image.png"][..],
+        &["-o", "image.png", "--snapshot-seconds", "inf"],
+        &["-o", "image.png", "--snapshot-seconds", "1e30"],
+        &["--frobnicate"],
+    ]
+    .iter()
+    {
+        let output = run(args);
+
+        assert_eq!(output.status.code(), Some(2), "{:?}", args);
+        assert!(String::from_utf8_lossy(&output.stderr).starts_with("error: "));
+    }
+}
+
+#[test]
+fn scene_errors_exit_with_one() {
+    let output = run(&["-o", "image.png", "--scene", "missing-scene.json"]);
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("missing-scene.json"));
+}